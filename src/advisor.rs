@@ -0,0 +1,167 @@
+use crate::attribute_stats::AttributeStatsChartType;
+use crate::group_stats::GroupStatsChartType;
+use crate::moving_stats::MovingStatsChartType;
+
+/// Whether the measurements are continuous values or counts.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DataKind {
+    /// Continuous measurements, e.g. lengths or weights.
+    Variable,
+    /// Counts of defective units or of defects.
+    Attribute,
+}
+
+/// What an attribute count counts.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DefectKind {
+    /// Units that fail, at most one per unit (binomial).
+    Defectives,
+    /// Nonconformities, possibly several per unit (Poisson).
+    Defects,
+}
+
+/// A chart type of one of the chart families.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum RecommendedChart {
+    Group(GroupStatsChartType),
+    Moving(MovingStatsChartType),
+    Attribute(AttributeStatsChartType),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartRecommendation {
+    pub chart: RecommendedChart,
+    pub rationale: String,
+}
+
+/// Picks a control chart by the usual decision tree.
+///
+/// Variable data with subgroups of 1 goes on an individuals chart (with its moving
+/// ranges), of 2 to 9 on an Xbar-R chart and of 10 or more on an Xbar-S chart. Attribute
+/// data needs `defect_kind`: defectives go on an NP chart for a fixed sample size and a P
+/// chart otherwise, defects on a C or a U chart. For attribute data `sub_group_size` is
+/// the sample size, 0 if it varies between samples.
+pub fn recommend_chart(
+    data_kind: DataKind,
+    sub_group_size: usize,
+    defect_kind: Option<DefectKind>,
+) -> Result<ChartRecommendation, String> {
+    let (chart, rationale) = match data_kind {
+        DataKind::Variable => match sub_group_size {
+            0 => return Err("recommend_chart: sub_group_size must be at least 1".to_string()),
+            1 => (
+                RecommendedChart::Moving(MovingStatsChartType::IndividualsChart),
+                "single measurements: individuals chart with moving ranges (I-MR)",
+            ),
+            2..=9 => (
+                RecommendedChart::Group(GroupStatsChartType::XbarRChart),
+                "subgroups of 2 to 9: the range estimates sigma efficiently (Xbar-R)",
+            ),
+            _ => (
+                RecommendedChart::Group(GroupStatsChartType::XbarSChart),
+                "subgroups of 10 or more: the standard deviation uses all values (Xbar-S)",
+            ),
+        },
+        DataKind::Attribute => {
+            let defect_kind = defect_kind
+                .ok_or_else(|| "recommend_chart: attribute data needs a defect kind".to_string())?;
+            match (defect_kind, sub_group_size != 0) {
+                (DefectKind::Defectives, true) => (
+                    RecommendedChart::Attribute(AttributeStatsChartType::NpChart),
+                    "defectives in samples of a fixed size: number defective (NP)",
+                ),
+                (DefectKind::Defectives, false) => (
+                    RecommendedChart::Attribute(AttributeStatsChartType::PChart),
+                    "defectives in samples of varying size: fraction defective (P)",
+                ),
+                (DefectKind::Defects, true) => (
+                    RecommendedChart::Attribute(AttributeStatsChartType::CChart),
+                    "defects in samples of a fixed size: defect count (C)",
+                ),
+                (DefectKind::Defects, false) => (
+                    RecommendedChart::Attribute(AttributeStatsChartType::UChart),
+                    "defects in samples of varying size: defects per unit (U)",
+                ),
+            }
+        }
+    };
+    Ok(ChartRecommendation {
+        chart,
+        rationale: rationale.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test_advisor {
+    use crate::advisor::{DataKind, DefectKind, RecommendedChart, recommend_chart};
+    use crate::attribute_stats::AttributeStatsChartType;
+    use crate::group_stats::GroupStatsChartType;
+    use crate::moving_stats::MovingStatsChartType;
+
+    #[test]
+    pub fn test_recommend_chart() {
+        let cases = [
+            (
+                DataKind::Variable,
+                1,
+                None,
+                RecommendedChart::Moving(MovingStatsChartType::IndividualsChart),
+            ),
+            (
+                DataKind::Variable,
+                2,
+                Some(DefectKind::Defects),
+                RecommendedChart::Group(GroupStatsChartType::XbarRChart),
+            ),
+            (
+                DataKind::Variable,
+                9,
+                None,
+                RecommendedChart::Group(GroupStatsChartType::XbarRChart),
+            ),
+            (
+                DataKind::Variable,
+                10,
+                None,
+                RecommendedChart::Group(GroupStatsChartType::XbarSChart),
+            ),
+            (
+                DataKind::Attribute,
+                50,
+                Some(DefectKind::Defectives),
+                RecommendedChart::Attribute(AttributeStatsChartType::NpChart),
+            ),
+            (
+                DataKind::Attribute,
+                0,
+                Some(DefectKind::Defectives),
+                RecommendedChart::Attribute(AttributeStatsChartType::PChart),
+            ),
+            (
+                DataKind::Attribute,
+                1,
+                Some(DefectKind::Defects),
+                RecommendedChart::Attribute(AttributeStatsChartType::CChart),
+            ),
+            (
+                DataKind::Attribute,
+                0,
+                Some(DefectKind::Defects),
+                RecommendedChart::Attribute(AttributeStatsChartType::UChart),
+            ),
+        ];
+        for (data_kind, sub_group_size, defect_kind, expected) in cases {
+            let recommendation = recommend_chart(data_kind, sub_group_size, defect_kind).unwrap();
+            assert_eq!(expected, recommendation.chart);
+            assert!(!recommendation.rationale.is_empty());
+        }
+        assert!(
+            recommend_chart(DataKind::Variable, 4, None)
+                .unwrap()
+                .rationale
+                .contains("Xbar-R")
+        );
+        assert!(recommend_chart(DataKind::Variable, 0, None).is_err());
+        assert!(recommend_chart(DataKind::Attribute, 5, None).is_err());
+    }
+}
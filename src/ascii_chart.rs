@@ -0,0 +1,81 @@
+/// Renders a plotted series as a `height` line text chart, one column per point.
+///
+/// Only the last `width` points are drawn. The UCL, CL and LCL rows are drawn with `-`
+/// and labelled on the left, points inside the limits are drawn as `o` and points
+/// outside them as `*`. The vertical scale spans the data and the limits. Returns an
+/// empty string if `width` or `height` is zero.
+pub(crate) fn render(
+    chart_data: &[f64],
+    cl: f64,
+    ucl: f64,
+    lcl: f64,
+    width: usize,
+    height: usize,
+) -> String {
+    if width == 0 || height == 0 {
+        return String::new();
+    }
+    let points = &chart_data[chart_data.len().saturating_sub(width)..];
+    let (low, high) = points
+        .iter()
+        .chain([cl, ucl, lcl].iter())
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &v| {
+            (low.min(v), high.max(v))
+        });
+    let row_of = |value: f64| -> Option<usize> {
+        if !value.is_finite() {
+            None
+        } else if high > low {
+            Some(((high - value) / (high - low) * (height - 1) as f64).round() as usize)
+        } else {
+            Some((height - 1) / 2)
+        }
+    };
+
+    let columns = points.len();
+    let mut grid = vec![vec![' '; columns]; height];
+    let mut labels = vec!["   "; height];
+    for (label, limit) in [("UCL", ucl), ("LCL", lcl), ("CL ", cl)] {
+        if let Some(row) = row_of(limit) {
+            if labels[row] == "   " {
+                labels[row] = label;
+            }
+            grid[row] = vec!['-'; columns];
+        }
+    }
+    for (column, &value) in points.iter().enumerate() {
+        if let Some(row) = row_of(value) {
+            grid[row][column] = if value > ucl || value < lcl { '*' } else { 'o' };
+        }
+    }
+
+    let mut chart = String::new();
+    for (label, line) in labels.iter().zip(grid) {
+        chart.push_str(label);
+        chart.push_str(" |");
+        chart.extend(line);
+        chart.push('\n');
+    }
+    chart
+}
+
+#[cfg(test)]
+mod test_ascii_chart {
+    use crate::ascii_chart::render;
+
+    #[test]
+    pub fn test_render() {
+        let chart = render(&[1.0, 2.0, 3.0, 6.0, 2.0, -1.0], 2.0, 4.0, 0.0, 10, 8);
+        let expected = "    |   *  \n    |      \nUCL |------\n    |  o   \n\
+                        CL  |-o--o-\n    |o     \nLCL |------\n    |     *\n";
+        assert_eq!(expected, chart);
+    }
+
+    #[test]
+    pub fn test_render_window() {
+        let chart = render(&[9.0, 1.0, 1.0], 1.0, 1.0, 1.0, 2, 3);
+        assert_eq!("    |  \nUCL |oo\n    |  \n", chart);
+        assert_eq!("", render(&[1.0], 1.0, 2.0, 0.0, 0, 3));
+    }
+}
@@ -1,15 +1,67 @@
-use num_traits::Float;
-use crate::RoundingContext;
+use crate::distribution::{binomial_inverse_cdf, normal_cdf, poisson_inverse_cdf};
+use crate::export::{ChartFamily, ControlChartSpec};
+use crate::{ControlLimits, Rounding, RoundingContext, chart_type_key, write_labeled};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum AttributeStatsChartType {
+    #[default]
     PChart,
     NpChart,
     CChart,
     UChart,
+    /// Weighted defects (demerits) per unit, see `AttributeStats::add_demerits`.
+    DemeritChart,
 }
 
-#[derive(Debug)]
+impl FromStr for AttributeStatsChartType {
+    type Err = String;
+
+    /// Parses a chart type name case-insensitively, ignoring separators, e.g. `"np"` or
+    /// `"NP-Chart"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match chart_type_key(s).as_str() {
+            "p" | "pchart" => Ok(AttributeStatsChartType::PChart),
+            "np" | "npchart" => Ok(AttributeStatsChartType::NpChart),
+            "c" | "cchart" => Ok(AttributeStatsChartType::CChart),
+            "u" | "uchart" => Ok(AttributeStatsChartType::UChart),
+            "demerit" | "d" | "demeritchart" => Ok(AttributeStatsChartType::DemeritChart),
+            _ => Err(format!(
+                "Unknown attribute chart type {:?}, expected one of p, np, c, u, demerit",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AttributeStatsChartType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AttributeStatsChartType::PChart => "p",
+            AttributeStatsChartType::NpChart => "np",
+            AttributeStatsChartType::CChart => "c",
+            AttributeStatsChartType::UChart => "u",
+            AttributeStatsChartType::DemeritChart => "demerit",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How the C and NP chart limits are computed, the other charts always use the normal
+/// approximation.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum LimitMethod {
+    /// `mean ± k * sigma`, with the lower limit clamped to zero.
+    #[default]
+    Normal,
+    /// Probability limits at the Poisson (C chart) or binomial (NP chart) quantiles with
+    /// the tail areas of the `k` sigma normal limits, e.g. 0.00135 and 0.99865 for 3σ.
+    /// Better at low counts, where the normal lower limit is often clamped to zero.
+    Exact,
+}
+
+#[derive(Debug, Clone)]
 pub struct AttributeStats {
     cl: f64,
     ucl: f64,
@@ -19,7 +71,16 @@ pub struct AttributeStats {
     samples: Vec<f64>,
     defects: Vec<f64>,
     data: Vec<f64>,
+    demerit_weights: Vec<f64>,
+    class_counts: Vec<Vec<f64>>,
     average: f64,
+    defect_sum: f64,
+    sample_sum: f64,
+    class_sums: Vec<f64>,
+    sigma_multiple: f64,
+    #[cfg(test)]
+    recomputes: usize,
+    limit_method: LimitMethod,
     dirty: bool,
     rounding_ctx: Option<RoundingContext>,
 }
@@ -35,19 +96,35 @@ impl AttributeStats {
             samples: vec![],
             defects: vec![],
             data: vec![],
+            demerit_weights: vec![],
+            class_counts: vec![],
             average: 0.0,
+            defect_sum: 0.0,
+            sample_sum: 0.0,
+            class_sums: vec![],
+            sigma_multiple: 3.0,
+            #[cfg(test)]
+            recomputes: 0,
+            limit_method: LimitMethod::Normal,
             dirty: false,
             rounding_ctx: None,
         }
     }
 
-
-
+    /// Recomputes the centerline and limits for `sigma_multiple` (3 if `None`) from the
+    /// running totals, unless data, settings and `sigma_multiple` are unchanged since the
+    /// last call.
     pub fn update(&mut self, sigma_multiple: Option<f64>) {
-        if !self.dirty {
+        let sigma_m = sigma_multiple.unwrap_or(3.0);
+        if !self.dirty && sigma_m == self.sigma_multiple {
             return;
         }
-        self.data.clear();
+        self.dirty = false;
+        self.sigma_multiple = sigma_m;
+        #[cfg(test)]
+        {
+            self.recomputes += 1;
+        }
         self.ucl = 0.0;
         self.lcl = 0.0;
         self.cl = 0.0;
@@ -55,19 +132,11 @@ impl AttributeStats {
             return;
         }
 
-        let sigma_m = sigma_multiple.unwrap_or(3.0);
-
+        let n = self.defects.len() as f64;
         match self.chart_type {
             AttributeStatsChartType::PChart => {
-                let mut total1 = 0.0;
-                let mut total2 = 0.0;
-                for i in 0..self.defects.len() {
-                    total1 += self.defects[i];
-                    total2 += self.samples[i];
-                    self.data.push(self.defects[i] / self.samples[i]);
-                }
-                self.average = total1 / total2;
-                let n_avg = total2 / self.samples.len() as f64;
+                self.average = self.defect_sum / self.sample_sum;
+                let n_avg = self.sample_sum / n;
                 self.ucl =
                     self.average + sigma_m * ((self.average * (1.0 - self.average)).sqrt() / n_avg);
                 self.lcl =
@@ -76,75 +145,283 @@ impl AttributeStats {
                 self.cl = self.average;
             }
             AttributeStatsChartType::NpChart => {
-                let mut sum = 0.0;
-                for d in &self.defects {
-                    sum += d;
-                    self.data.push(*d);
+                let k = self.samples.first().unwrap();
+                let pbar = self.defect_sum / (n * k);
+                self.average = self.defect_sum / n;
+                if self.limit_method == LimitMethod::Exact {
+                    let tail = normal_cdf(-sigma_m);
+                    let trials = k.round() as u64;
+                    self.ucl = binomial_inverse_cdf(1.0 - tail, trials, pbar);
+                    self.lcl = binomial_inverse_cdf(tail, trials, pbar);
+                } else {
+                    self.ucl = self.average + sigma_m * (self.average * (1.0 - pbar)).sqrt();
+                    self.lcl = self.average - sigma_m * (self.average * (1.0 - pbar)).sqrt();
+                    self.lcl = self.lcl.max(0.0);
                 }
-                let n = self.defects.len() as f64;
-                let k = self.samples.get(0).unwrap();
-                let pbar = sum / (n * k);
-                self.average = sum / n;
-                self.ucl = self.average + sigma_m * (self.average * (1.0 - pbar)).sqrt();
-                self.lcl = self.average - sigma_m * (self.average * (1.0 - pbar)).sqrt();
-                self.lcl = self.lcl.max(0.0);
                 self.cl = self.average;
             }
             AttributeStatsChartType::CChart => {
-                let mut sum = 0.0;
-                for d in &self.defects {
-                    sum += d;
-                    self.data.push(*d);
+                self.average = self.defect_sum / n;
+                if self.limit_method == LimitMethod::Exact {
+                    let tail = normal_cdf(-sigma_m);
+                    self.ucl = poisson_inverse_cdf(1.0 - tail, self.average);
+                    self.lcl = poisson_inverse_cdf(tail, self.average);
+                } else {
+                    let sigma = self.average.sqrt();
+                    self.ucl = self.average + sigma_m * sigma;
+                    self.lcl = self.average - sigma_m * sigma;
+                    self.lcl = self.lcl.max(0.0);
                 }
-                let n = self.defects.len() as f64;
-                self.average = sum / n;
-                let sigma = self.average.sqrt();
-                self.ucl = self.average + sigma_m * sigma;
-                self.lcl = self.average - sigma_m * sigma;
-                self.lcl = self.lcl.max(0.0);
                 self.cl = self.average;
             }
             AttributeStatsChartType::UChart => {
-                let mut csum = 0.0;
-                let mut nsum = 0.0;
-                for i in 0..self.defects.len() {
-                    csum += self.defects[i];
-                    nsum += self.samples[i];
-                    self.data.push(self.defects[i] / self.samples[i]);
-                }
-                self.average = csum / nsum;
-                let n_avg = nsum / self.samples.len() as f64;
+                self.average = self.defect_sum / self.sample_sum;
+                let n_avg = self.sample_sum / n;
                 self.ucl = self.average + sigma_m * (self.average / n_avg).sqrt();
                 self.lcl = self.average - sigma_m * (self.average / n_avg).sqrt();
                 self.lcl = self.lcl.max(0.0);
                 self.cl = self.average;
             }
+            AttributeStatsChartType::DemeritChart => {
+                // ū_k, the class k defects per unit over all samples
+                let class_rates: Vec<f64> = self
+                    .class_sums
+                    .iter()
+                    .map(|c| c / self.sample_sum)
+                    .collect();
+                let n_avg = self.sample_sum / n;
+                self.average = class_rates
+                    .iter()
+                    .zip(&self.demerit_weights)
+                    .map(|(u, w)| w * u)
+                    .sum();
+                let variance: f64 = class_rates
+                    .iter()
+                    .zip(&self.demerit_weights)
+                    .map(|(u, w)| w * w * u)
+                    .sum();
+                let sigma = (variance / n_avg).sqrt();
+                self.ucl = self.average + sigma_m * sigma;
+                self.lcl = (self.average - sigma_m * sigma).max(0.0);
+                self.cl = self.average;
+            }
+        }
+        // clamp (above), round, then clamp again: a slightly negative LCL must not
+        // surface as -0.0 or below zero under any rounding mode
+        if let Some(ctx) = &self.rounding_ctx {
+            for v in [&mut self.cl, &mut self.ucl, &mut self.lcl] {
+                if v.is_finite() {
+                    *v = v.scale(ctx.scale, &ctx.rounding_mode);
+                }
+            }
+        }
+        self.lcl = clamp_to_zero(self.lcl);
+    }
+
+    /// The plotted value of the sample at `index`.
+    fn plotted(&self, index: usize) -> f64 {
+        match self.chart_type {
+            AttributeStatsChartType::NpChart | AttributeStatsChartType::CChart => {
+                self.defects[index]
+            }
+            AttributeStatsChartType::PChart | AttributeStatsChartType::UChart => {
+                self.defects[index] / self.samples[index]
+            }
+            AttributeStatsChartType::DemeritChart => {
+                let demerits: f64 = self.class_counts[index]
+                    .iter()
+                    .zip(&self.demerit_weights)
+                    .map(|(c, w)| c * w)
+                    .sum();
+                demerits / self.samples[index]
+            }
+        }
+    }
+
+    /// Adds the newest sample to the running totals and evicts the oldest ones beyond
+    /// `max_elements`.
+    fn push_totals(&mut self) {
+        let last = self.defects.len() - 1;
+        self.defect_sum += self.defects[last];
+        self.sample_sum += self.samples[last];
+        if let Some(counts) = self.class_counts.get(last) {
+            for (sum, c) in self.class_sums.iter_mut().zip(counts) {
+                *sum += c;
+            }
+        }
+        self.data.push(self.plotted(last));
+        while self.defects.len() > self.max_elements {
+            self.defect_sum -= self.defects.remove(0);
+            self.sample_sum -= self.samples.remove(0);
+            if !self.class_counts.is_empty() {
+                let counts = self.class_counts.remove(0);
+                for (sum, c) in self.class_sums.iter_mut().zip(counts) {
+                    *sum -= c;
+                }
+            }
+            self.data.remove(0);
+        }
+        self.dirty = true;
+    }
+
+    /// Recomputes the running totals and plotted values from all stored samples.
+    fn rebuild(&mut self) {
+        self.defect_sum = self.defects.iter().sum();
+        self.sample_sum = self.samples.iter().sum();
+        self.class_sums = vec![0.0; self.demerit_weights.len()];
+        for counts in &self.class_counts {
+            for (sum, c) in self.class_sums.iter_mut().zip(counts) {
+                *sum += c;
+            }
         }
+        self.data = (0..self.defects.len()).map(|i| self.plotted(i)).collect();
         self.dirty = true;
     }
 
+    /// Adds the `defect` count (defectives for the P and NP charts) found in a sample of
+    /// `sample` units.
+    ///
+    /// `defect` must not be negative, and for the P and NP charts not exceed `sample`. The
+    /// P, NP and U charts need a positive `sample`, the C chart ignores it.
     pub fn add_data(&mut self, defect: f64, sample: f64) -> Result<(), String> {
-        if self.chart_type.eq(&AttributeStatsChartType::NpChart) {
-            if !self.samples.is_empty() {
-                let f = self.samples.get(0).unwrap();
-                if *f != sample {
-                    return Err("Can't change number test for NP charts".parse().unwrap());
-                }
+        if self.chart_type == AttributeStatsChartType::DemeritChart {
+            return Err("Use add_demerits for demerit charts".to_string());
+        }
+        if defect.is_nan() || defect < 0.0 {
+            return Err(format!("Defect count {} must not be negative", defect));
+        }
+        if self.chart_type != AttributeStatsChartType::CChart {
+            if sample.is_nan() || sample <= 0.0 {
+                return Err("Sample size must be positive".to_string());
+            }
+            if matches!(
+                self.chart_type,
+                AttributeStatsChartType::PChart | AttributeStatsChartType::NpChart
+            ) && defect > sample
+            {
+                return Err(format!(
+                    "Defectives {} exceed the sample size {}",
+                    defect, sample
+                ));
             }
         }
+        if self.chart_type.eq(&AttributeStatsChartType::NpChart)
+            && let Some(f) = self.samples.first()
+            && *f != sample
+        {
+            return Err("Can't change number test for NP charts".parse().unwrap());
+        }
         self.defects.push(defect);
         self.samples.push(sample);
-        loop {
-            if self.defects.len() <= self.max_elements {
-                break;
-            }
-            self.defects.remove(0);
-            self.samples.remove(0);
+        self.push_totals();
+        Ok(())
+    }
+
+    /// Adds the defect counts of one sample of `sample` units to a demerit chart, one count
+    /// per severity class in the order of `set_demerit_weights`.
+    ///
+    /// The plotted value is the demerits per unit `Σ w_k * c_k / sample`. The centerline is
+    /// `D̄ = Σ w_k * ū_k` and the limits `D̄ ± 3 * sqrt(Σ w_k² * ū_k / n̄)`, with `ū_k` the
+    /// defects of class `k` per unit over all samples (weighted Poisson variance).
+    pub fn add_demerits(&mut self, counts_by_class: Vec<f64>, sample: f64) -> Result<(), String> {
+        if self.chart_type != AttributeStatsChartType::DemeritChart {
+            return Err("add_demerits needs a demerit chart".to_string());
+        }
+        if counts_by_class.len() != self.demerit_weights.len() {
+            return Err(format!(
+                "Expected {} class counts, got {}",
+                self.demerit_weights.len(),
+                counts_by_class.len()
+            ));
+        }
+        if sample.is_nan() || sample <= 0.0 {
+            return Err("Sample size must be positive".to_string());
+        }
+        self.defects.push(counts_by_class.iter().sum());
+        self.samples.push(sample);
+        self.class_counts.push(counts_by_class);
+        self.push_totals();
+        Ok(())
+    }
+
+    pub fn demerit_weights(&self) -> Vec<f64> {
+        self.demerit_weights.to_vec()
+    }
+
+    /// Sets the demerit weight of each severity class, e.g. `[100.0, 50.0, 10.0, 1.0]` for
+    /// critical, major, minor and incidental defects. Samples already added are dropped
+    /// when the number of classes changes.
+    pub fn set_demerit_weights(&mut self, weights: Vec<f64>) {
+        if weights.len() != self.demerit_weights.len() {
+            self.defects.clear();
+            self.samples.clear();
+            self.class_counts.clear();
+        }
+        self.demerit_weights = weights;
+        self.rebuild();
+    }
+
+    /// Adds each `(defect, sample)` pair with `add_data`, stopping at the first one that
+    /// is rejected.
+    ///
+    /// The error names the zero-based position of the failing pair within `iter`; the
+    /// pairs before it stay added.
+    pub fn extend_from_pairs<I: IntoIterator<Item = (f64, f64)>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), String> {
+        for (index, (defect, sample)) in iter.into_iter().enumerate() {
+            self.add_data(defect, sample)
+                .map_err(|e| format!("pair {}: {}", index, e))?;
         }
-        self.dirty = true;
         Ok(())
     }
 
+    /// The same counts as a U chart, the defects per unit of each sample, e.g. to compare
+    /// a C chart with the per-unit model.
+    ///
+    /// Fails unless this is a C chart whose samples all have a positive size, which the C
+    /// chart itself does not require.
+    pub fn as_u_chart(&self) -> Result<AttributeStats, String> {
+        if self.chart_type != AttributeStatsChartType::CChart {
+            return Err(format!(
+                "Can't convert a {:?} to a U chart",
+                self.chart_type
+            ));
+        }
+        if let Some(sample) = self.samples.iter().find(|s| s.is_nan() || **s <= 0.0) {
+            return Err(format!(
+                "Sample size {} must be positive for a U chart",
+                sample
+            ));
+        }
+        Ok(self.converted(AttributeStatsChartType::UChart))
+    }
+
+    /// The same counts as a C chart, the defects per sample.
+    ///
+    /// Fails unless this is a U chart with a constant sample size, as the C chart limits
+    /// assume equal areas of opportunity.
+    pub fn as_c_chart(&self) -> Result<AttributeStats, String> {
+        if self.chart_type != AttributeStatsChartType::UChart {
+            return Err(format!(
+                "Can't convert a {:?} to a C chart",
+                self.chart_type
+            ));
+        }
+        if self.samples.windows(2).any(|w| w[0] != w[1]) {
+            return Err("A C chart needs a constant sample size".to_string());
+        }
+        Ok(self.converted(AttributeStatsChartType::CChart))
+    }
+
+    fn converted(&self, chart_type: AttributeStatsChartType) -> AttributeStats {
+        let mut stats = self.clone();
+        stats.chart_type = chart_type;
+        stats.rebuild();
+        stats
+    }
+
     pub fn lcl(&mut self, sigma_multiple: Option<f64>) -> f64 {
         self.update(sigma_multiple);
         self.lcl
@@ -160,14 +437,67 @@ impl AttributeStats {
         self.cl
     }
 
+    /// Updates the chart and returns, for each `data` point, whether it lies within the
+    /// control limits.
+    pub fn control_status(&mut self, sigma_multiple: Option<f64>) -> Vec<bool> {
+        self.update(sigma_multiple);
+        self.data
+            .iter()
+            .map(|&x| !(x > self.ucl || x < self.lcl))
+            .collect()
+    }
+
+    /// Updates the chart and returns its centerline, limits and zone boundaries, rounded
+    /// with the rounding context.
+    ///
+    /// Like `lcl`, the lower zone boundaries are clamped to zero.
+    pub fn control_limits(&mut self, sigma_multiple: Option<f64>) -> ControlLimits {
+        self.update(sigma_multiple);
+        let sigma = (self.ucl - self.cl) / sigma_multiple.unwrap_or(3.0);
+        let mut limits = ControlLimits::new(self.cl, self.ucl, self.lcl, sigma, &self.rounding_ctx);
+        limits.lower_1s = clamp_to_zero(limits.lower_1s);
+        limits.lower_2s = clamp_to_zero(limits.lower_2s);
+        limits
+    }
+
+    /// Updates the chart for 3σ limits and returns it in the shape shared by all chart
+    /// families.
+    pub fn to_spec(&mut self) -> ControlChartSpec {
+        ControlChartSpec {
+            family: ChartFamily::Attribute,
+            chart_type: self.chart_type.to_string(),
+            limits: self.control_limits(None),
+            data: self.data(),
+            violations: None,
+        }
+    }
+
     pub fn chart_type(&self) -> &AttributeStatsChartType {
         &self.chart_type
     }
 
+    pub fn limit_method(&self) -> LimitMethod {
+        self.limit_method
+    }
+
+    pub fn set_limit_method(&mut self, limit_method: LimitMethod) {
+        self.limit_method = limit_method;
+        self.dirty = true;
+    }
+
     pub fn max_elements(&self) -> usize {
         self.max_elements
     }
 
+    /// The number of stored samples, at most `max_elements`.
+    pub fn len(&self) -> usize {
+        self.defects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defects.is_empty()
+    }
+
     pub fn samples(&self) -> Vec<f64> {
         self.samples.to_vec()
     }
@@ -176,13 +506,15 @@ impl AttributeStats {
         self.defects.to_vec()
     }
 
-    pub fn data(&mut self) -> Vec<f64> {
-        self.update(None);
+    /// The plotted values, kept up to date as samples are added.
+    pub fn data(&self) -> Vec<f64> {
         self.data.to_vec()
     }
 
+    /// Updates the chart for the `sigma_multiple` of the last `update` and returns the
+    /// average.
     pub fn average(&mut self) -> f64 {
-        self.update(None);
+        self.update(Some(self.sigma_multiple));
         self.average
     }
 
@@ -190,11 +522,304 @@ impl AttributeStats {
         self.dirty
     }
 
+    /// Keeps at most `max_elements` samples, evicting the oldest ones right away if more
+    /// are stored.
     pub fn set_max_elements(&mut self, max_elements: usize) {
         self.max_elements = max_elements;
+        if self.defects.len() > max_elements {
+            let excess = self.defects.len() - max_elements;
+            self.defects.drain(..excess);
+            self.samples.drain(..excess);
+            if !self.class_counts.is_empty() {
+                self.class_counts.drain(..excess);
+            }
+            self.rebuild();
+        }
     }
 
+    /// Rounds the centerline, limits and zone boundaries. The LCL is clamped to zero
+    /// before and after rounding, so it is never negative nor `-0.0`.
     pub fn set_rounding_ctx(&mut self, rounding_ctx: Option<RoundingContext>) {
         self.rounding_ctx = rounding_ctx;
+        self.dirty = true;
+    }
+}
+
+/// A summary of the chart as of the last `update`: the chart type and sample count, the
+/// limits and the sigma implied by the upper limit, `(UCL - CL) / sigma_multiple`. `{:.3}`
+/// rounds the values.
+impl fmt::Display for AttributeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} chart, {} samples", self.chart_type, self.len())?;
+        if self.dirty {
+            write!(f, " (not updated)")?;
+        }
+        writeln!(f)?;
+        write_labeled(
+            f,
+            &[
+                ("CL", self.cl),
+                ("UCL", self.ucl),
+                ("LCL", self.lcl),
+                ("sigma", (self.ucl - self.cl) / self.sigma_multiple),
+            ],
+        )
+    }
+}
+
+/// `x` if positive, otherwise `+0.0`, turning `-0.0` into `0.0` (`NAN` is kept).
+fn clamp_to_zero(x: f64) -> f64 {
+    if x <= 0.0 { 0.0 } else { x }
+}
+
+#[cfg(test)]
+mod test_attribute_stats {
+    use crate::RoundingContext;
+    use crate::RoundingMode::{
+        RoundCeiling, RoundDown, RoundFloor, RoundHalfDown, RoundHalfEven, RoundHalfUp, RoundUp,
+    };
+    use crate::assert_almost_eq;
+    use crate::attribute_stats::{AttributeStats, AttributeStatsChartType, LimitMethod};
+
+    #[test]
+    pub fn test_c_chart_control_limits() {
+        let mut stats = AttributeStats::new(AttributeStatsChartType::CChart);
+        for defect in [2.0, 4.0, 3.0, 5.0, 1.0] {
+            stats.add_data(defect, 1.0).unwrap();
+        }
+        let limits = stats.control_limits(None);
+        assert_eq!(3.0, limits.cl);
+        assert_almost_eq!(limits.ucl, 3.0 + 3.0 * 3f64.sqrt(), 1e-12);
+        assert_eq!(0.0, limits.lcl);
+        assert_almost_eq!(limits.sigma, 3f64.sqrt(), 1e-12);
+        assert_almost_eq!(limits.upper_2s, 3.0 + 2.0 * 3f64.sqrt(), 1e-12);
+        assert_almost_eq!(limits.lower_1s, 3.0 - 3f64.sqrt(), 1e-12);
+        assert_eq!(0.0, limits.lower_2s);
+    }
+
+    #[test]
+    pub fn test_exact_limits() {
+        // c̄ = 2: the Poisson limits are 0 and 7, the normal UCL 2 + 3 * sqrt(2) ≈ 6.24
+        let mut stats = AttributeStats::new(AttributeStatsChartType::CChart);
+        for defect in [1.0, 3.0, 2.0, 0.0, 4.0] {
+            stats.add_data(defect, 1.0).unwrap();
+        }
+        assert_eq!(LimitMethod::Normal, stats.limit_method());
+        assert_almost_eq!(stats.ucl(None), 2.0 + 3.0 * 2f64.sqrt(), 1e-12);
+        stats.set_limit_method(LimitMethod::Exact);
+        assert_eq!(2.0, stats.cl(None));
+        assert_eq!(7.0, stats.ucl(None));
+        assert_eq!(0.0, stats.lcl(None));
+
+        // c̄ = 8: the normal LCL 8 - 3 * sqrt(8) is clamped to zero, P(X = 0) < 0.00135
+        let mut stats = AttributeStats::new(AttributeStatsChartType::CChart);
+        for defect in [6.0, 10.0, 8.0] {
+            stats.add_data(defect, 1.0).unwrap();
+        }
+        assert_eq!(0.0, stats.lcl(None));
+        stats.set_limit_method(LimitMethod::Exact);
+        assert_eq!(1.0, stats.lcl(None));
+        assert_eq!(18.0, stats.ucl(None));
+        assert_eq!(vec![true, true, true], stats.control_status(None));
+
+        // np̄ = 8 of 100: the binomial limits are 1 and 17
+        let mut stats = AttributeStats::new(AttributeStatsChartType::NpChart);
+        for defect in [7.0, 9.0, 8.0] {
+            stats.add_data(defect, 100.0).unwrap();
+        }
+        assert_eq!(0.0, stats.lcl(None));
+        stats.set_limit_method(LimitMethod::Exact);
+        assert_eq!(1.0, stats.lcl(None));
+        assert_eq!(17.0, stats.ucl(None));
+        assert_eq!(8.0, stats.cl(None));
+    }
+
+    #[test]
+    pub fn test_cached_update() {
+        let mut stats = AttributeStats::new(AttributeStatsChartType::UChart);
+        stats.set_max_elements(3);
+        for (defect, sample) in [(9.0, 1.0), (2.0, 2.0), (4.0, 2.0), (3.0, 2.0)] {
+            stats.add_data(defect, sample).unwrap();
+        }
+        assert!(stats.dirty());
+        assert_eq!(vec![1.0, 2.0, 1.5], stats.data());
+        let cl = stats.cl(None);
+        let ucl = stats.ucl(None);
+        let lcl = stats.lcl(None);
+        assert_eq!(cl, stats.average());
+        assert_eq!(vec![true; 3], stats.control_status(None));
+        assert_eq!(1, stats.recomputes);
+        assert!(!stats.dirty());
+        // the totals only cover the 3 kept samples, 9 defects in 6 units
+        assert_eq!(1.5, cl);
+        assert_almost_eq!(ucl, 1.5 + 3.0 * (1.5f64 / 2.0).sqrt(), 1e-12);
+        assert_eq!(0.0, lcl);
+
+        assert!(stats.ucl(Some(2.0)) < ucl);
+        assert_eq!(stats.cl(Some(2.0)), stats.average());
+        assert_eq!(2, stats.recomputes);
+        stats.add_data(5.0, 2.0).unwrap();
+        assert_eq!(2.0, stats.average());
+        assert_eq!(3, stats.recomputes);
+    }
+
+    #[test]
+    pub fn test_rounded_lcl() {
+        let modes = [
+            RoundUp,
+            RoundDown,
+            RoundCeiling,
+            RoundFloor,
+            RoundHalfUp,
+            RoundHalfDown,
+            RoundHalfEven,
+        ];
+        for mode in modes {
+            // c̄ = 8.99, the raw LCL 8.99 - 3 * sqrt(8.99) is about -0.005
+            let mut stats = AttributeStats::new(AttributeStatsChartType::CChart);
+            stats.set_rounding_ctx(Some(RoundingContext::new(2, mode)));
+            stats.add_data(8.99, 1.0).unwrap();
+            let lcl = stats.lcl(None);
+            assert_eq!(0.0, lcl);
+            assert!(lcl.is_sign_positive(), "{:?}", mode);
+            let limits = stats.control_limits(None);
+            assert!(limits.lcl.is_sign_positive() && limits.lower_2s >= 0.0);
+            assert_eq!(8.99, stats.cl(None));
+        }
+
+        // c̄ = 9.01, the raw LCL is about 0.005
+        let mut stats = AttributeStats::new(AttributeStatsChartType::CChart);
+        stats.add_data(9.01, 1.0).unwrap();
+        stats.set_rounding_ctx(Some(RoundingContext::new(2, RoundUp)));
+        assert_eq!(0.01, stats.lcl(None));
+        stats.set_rounding_ctx(Some(RoundingContext::new(2, RoundDown)));
+        let lcl = stats.lcl(None);
+        assert_eq!(0.0, lcl);
+        assert!(lcl.is_sign_positive());
+        assert_eq!(18.01, stats.ucl(None));
+    }
+
+    #[test]
+    pub fn test_extend_from_pairs() {
+        let mut stats = AttributeStats::new(AttributeStatsChartType::NpChart);
+        let err = stats
+            .extend_from_pairs([(2.0, 50.0), (3.0, 50.0), (1.0, 40.0)])
+            .unwrap_err();
+        assert!(err.starts_with("pair 2: "));
+        assert_eq!(vec![2.0, 3.0], stats.defects());
+    }
+
+    #[test]
+    pub fn test_control_status() {
+        let mut stats = AttributeStats::new(AttributeStatsChartType::CChart);
+        stats
+            .extend_from_pairs([(2.0, 1.0), (4.0, 1.0), (3.0, 1.0), (12.0, 1.0)])
+            .unwrap();
+        // cl 5.25, ucl 5.25 + 3 * sqrt(5.25) = 12.12
+        assert_eq!(vec![true; 4], stats.control_status(None));
+        assert_eq!(
+            vec![true, true, true, false],
+            stats.control_status(Some(2.0))
+        );
+    }
+
+    #[test]
+    pub fn test_window_size() {
+        let mut stats = AttributeStats::new(AttributeStatsChartType::CChart);
+        assert!(stats.is_empty());
+        stats.set_max_elements(3);
+        stats
+            .extend_from_pairs((0..5).map(|i| (i as f64, 1.0)))
+            .unwrap();
+        assert_eq!(3, stats.len());
+        assert_eq!(3, stats.max_elements());
+        assert!(!stats.is_empty());
+
+        // shrinking the window evicts right away
+        assert_eq!(3.0, stats.cl(None));
+        stats.set_max_elements(2);
+        assert!(stats.dirty());
+        assert_eq!(2, stats.len());
+        assert_eq!(vec![3.0, 4.0], stats.data());
+        assert_eq!(3.5, stats.cl(None));
+    }
+
+    #[test]
+    pub fn test_invalid_samples() {
+        for chart_type in [
+            AttributeStatsChartType::PChart,
+            AttributeStatsChartType::NpChart,
+            AttributeStatsChartType::UChart,
+        ] {
+            let mut stats = AttributeStats::new(chart_type);
+            assert!(stats.add_data(1.0, 0.0).is_err());
+            assert!(stats.add_data(1.0, f64::NAN).is_err());
+            assert!(stats.add_data(-1.0, 10.0).is_err());
+            assert!(stats.is_empty());
+        }
+        let mut p_chart = AttributeStats::new(AttributeStatsChartType::PChart);
+        assert!(p_chart.add_data(11.0, 10.0).is_err());
+        assert!(p_chart.add_data(10.0, 10.0).is_ok());
+        let mut u_chart = AttributeStats::new(AttributeStatsChartType::UChart);
+        assert!(u_chart.add_data(11.0, 10.0).is_ok());
+        let mut c_chart = AttributeStats::new(AttributeStatsChartType::CChart);
+        assert!(c_chart.add_data(3.0, 0.0).is_ok());
+    }
+
+    #[test]
+    pub fn test_c_u_conversion() {
+        let mut c_chart = AttributeStats::new(AttributeStatsChartType::CChart);
+        c_chart
+            .extend_from_pairs([(4.0, 2.0), (6.0, 3.0), (2.0, 1.0), (8.0, 2.0)])
+            .unwrap();
+        let mut u_chart = c_chart.as_u_chart().unwrap();
+        assert_eq!(AttributeStatsChartType::UChart, *u_chart.chart_type());
+        assert_eq!(vec![2.0, 2.0, 2.0, 4.0], u_chart.data());
+        // 20 defects in 8 units
+        assert_almost_eq!(u_chart.cl(None), 2.5, 1e-12);
+        assert_eq!(5.0, c_chart.cl(None));
+
+        // the per-unit model needs constant sizes to go back
+        assert!(u_chart.as_c_chart().is_err());
+        assert!(u_chart.as_u_chart().is_err());
+        let mut constant = AttributeStats::new(AttributeStatsChartType::UChart);
+        constant
+            .extend_from_pairs([(4.0, 2.0), (6.0, 2.0), (2.0, 2.0)])
+            .unwrap();
+        let back = constant.as_c_chart().unwrap();
+        assert_eq!(vec![4.0, 6.0, 2.0], back.data());
+        assert_eq!(constant.samples(), back.samples());
+
+        let mut no_sizes = AttributeStats::new(AttributeStatsChartType::CChart);
+        no_sizes.add_data(3.0, 0.0).unwrap();
+        assert!(no_sizes.as_u_chart().is_err());
+        assert!(
+            AttributeStats::new(AttributeStatsChartType::PChart)
+                .as_u_chart()
+                .is_err()
+        );
+    }
+
+    #[test]
+    pub fn test_demerit_chart() {
+        let mut stats = AttributeStats::new(AttributeStatsChartType::DemeritChart);
+        stats.set_demerit_weights(vec![10.0, 5.0, 1.0]);
+        assert!(stats.add_data(3.0, 50.0).is_err());
+        assert!(stats.add_demerits(vec![1.0, 2.0], 50.0).is_err());
+        stats.add_demerits(vec![0.0, 2.0, 8.0], 50.0).unwrap();
+        stats.add_demerits(vec![1.0, 1.0, 5.0], 50.0).unwrap();
+        stats.add_demerits(vec![0.0, 3.0, 12.0], 50.0).unwrap();
+        assert_eq!(vec![10.0, 7.0, 15.0], stats.defects());
+
+        // demerits 18, 20, 27 per 50 units; class rates 1/150, 6/150, 25/150
+        assert_eq!(vec![0.36, 0.4, 0.54], stats.data());
+        let cl = (10.0 * 1.0 + 5.0 * 6.0 + 25.0) / 150.0;
+        let sigma = ((100.0 * 1.0 + 25.0 * 6.0 + 25.0) / 150.0 / 50.0f64).sqrt();
+        assert_almost_eq!(stats.cl(None), cl, 1e-12);
+        assert_almost_eq!(stats.ucl(None), cl + 3.0 * sigma, 1e-12);
+        assert_eq!(0.0, stats.lcl(None));
+
+        let mut c_chart = AttributeStats::new(AttributeStatsChartType::CChart);
+        assert!(c_chart.add_demerits(vec![], 1.0).is_err());
     }
 }
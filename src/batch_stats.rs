@@ -0,0 +1,222 @@
+use crate::group_stats::{GroupStats, GroupStatsChartType};
+use crate::moving_stats::{MovingStats, MovingStatsChartType};
+use crate::{ControlLimits, RoundingContext};
+
+/// Three-way I-MR-R/S chart for batch processes, where the variation between batches
+/// exceeds the variation within them.
+///
+/// The batch averages are plotted on an individuals chart with moving range limits
+/// between consecutive batches, and the within-batch ranges (or standard deviations) on
+/// an R (or S) chart. An Xbar chart of the same data judges the batch averages against
+/// the within-batch variation only, and so flags most batches.
+#[derive(Debug, Clone)]
+pub struct BatchStats {
+    within: GroupStats,
+    between: MovingStats,
+    dirty: bool,
+}
+
+impl BatchStats {
+    /// Creates an empty chart for batches of `sub_group_size` measurements, with
+    /// `within_chart` either `RChart` or `SChart`.
+    pub fn new(
+        sub_group_size: usize,
+        within_chart: GroupStatsChartType,
+    ) -> Result<BatchStats, String> {
+        if !matches!(
+            within_chart,
+            GroupStatsChartType::RChart | GroupStatsChartType::SChart
+        ) {
+            return Err(format!(
+                "BatchStats: the within-batch chart must be an R or S chart, not {:?}",
+                within_chart
+            ));
+        }
+        Ok(Self {
+            within: GroupStats::new(sub_group_size, within_chart)?,
+            between: MovingStats::new(2, MovingStatsChartType::IndividualsChart)?,
+            dirty: false,
+        })
+    }
+
+    pub fn add_data(&mut self, group_data: &[f64]) -> Result<(), String> {
+        self.within.add_data(group_data)?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Recomputes the within-batch chart and rebuilds the individuals chart of the
+    /// batch averages.
+    pub fn update(&mut self) {
+        self.within.update();
+        if !self.dirty {
+            return;
+        }
+        let mut between = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        between.set_rounding_ctx(*self.within.rounding_ctx());
+        between.extend(self.within.average());
+        between.update();
+        self.between = between;
+        self.dirty = false;
+    }
+
+    /// Updates the chart and returns the batch averages, the individuals chart series.
+    pub fn individuals_data(&mut self) -> Vec<f64> {
+        self.update();
+        self.between.data()
+    }
+
+    /// Updates the chart and returns the moving ranges of consecutive batch averages,
+    /// the first entry is `NAN`.
+    pub fn moving_range_data(&mut self) -> Vec<f64> {
+        self.update();
+        self.between.range_data()
+    }
+
+    /// Updates the chart and returns the within-batch ranges or standard deviations.
+    pub fn within_data(&mut self) -> Vec<f64> {
+        self.update();
+        self.within.chart_data()
+    }
+
+    /// Updates the chart and returns the individuals chart limits `X̄ ± E2 * MR̄` of the
+    /// batch averages.
+    pub fn individuals_limits(&mut self) -> ControlLimits {
+        self.update();
+        self.between.control_limits()
+    }
+
+    /// Updates the chart and returns the moving range chart limits `MR̄`, `D4 * MR̄` and
+    /// `D3 * MR̄` of the batch averages.
+    pub fn moving_range_limits(&mut self) -> ControlLimits {
+        self.update();
+        let (cl, ucl, lcl) = (
+            self.between.mr_cl(),
+            self.between.mr_ucl(),
+            self.between.mr_lcl(),
+        );
+        ControlLimits::new(cl, ucl, lcl, (ucl - cl) / 3.0, self.within.rounding_ctx())
+    }
+
+    /// Updates the chart and returns the R or S chart limits of the within-batch
+    /// variation.
+    pub fn within_limits(&mut self) -> ControlLimits {
+        self.update();
+        self.within.control_limits()
+    }
+
+    /// Updates the chart and returns a copy of the individuals chart of the batch
+    /// averages, e.g. to apply rules to it.
+    pub fn individuals(&mut self) -> MovingStats {
+        self.update();
+        self.between.clone()
+    }
+
+    /// Updates the chart and returns a copy of the within-batch R or S chart.
+    pub fn within(&mut self) -> GroupStats {
+        self.update();
+        self.within.clone()
+    }
+
+    /// The number of stored batches, at most `group_count`.
+    pub fn len(&self) -> usize {
+        self.within.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.within.is_empty()
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.within.group_count()
+    }
+
+    pub fn set_group_count(&mut self, group_count: usize) {
+        self.within.set_group_count(group_count);
+        self.dirty = true;
+    }
+
+    pub fn rounding_ctx(&self) -> &Option<RoundingContext> {
+        self.within.rounding_ctx()
+    }
+
+    pub fn set_rounding_ctx(&mut self, rounding_ctx: Option<RoundingContext>) {
+        self.within.set_rounding_ctx(rounding_ctx);
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod test_batch_stats {
+    use crate::SpcRule;
+    use crate::assert_almost_eq;
+    use crate::batch_stats::BatchStats;
+    use crate::group_stats::{GroupStats, GroupStatsChartType};
+
+    #[test]
+    pub fn test_batch_chart() {
+        let batches = [
+            [10.0, 11.0, 12.0],
+            [14.0, 15.0, 16.0],
+            [11.0, 12.0, 13.0],
+            [13.0, 14.0, 15.0],
+            [9.0, 10.0, 11.0],
+        ];
+        let mut stats = BatchStats::new(3, GroupStatsChartType::RChart).unwrap();
+        let mut xbar_r = GroupStats::new(3, GroupStatsChartType::XbarRChart).unwrap();
+        for batch in batches {
+            stats.add_data(&batch).unwrap();
+            xbar_r.add_data(&batch).unwrap();
+        }
+        assert_eq!(5, stats.len());
+
+        // batch averages 11, 15, 12, 14, 10 with average 12.4 and moving ranges
+        // 4, 3, 2, 4 with MR̄ = 3.25
+        assert_eq!(vec![11.0, 15.0, 12.0, 14.0, 10.0], stats.individuals_data());
+        let moving_ranges = stats.moving_range_data();
+        assert!(moving_ranges[0].is_nan());
+        assert_eq!(vec![4.0, 3.0, 2.0, 4.0], moving_ranges[1..].to_vec());
+
+        let individuals = stats.individuals_limits();
+        assert_almost_eq!(individuals.cl, 12.4, 1e-12);
+        assert_almost_eq!(individuals.ucl, 12.4 + 2.660 * 3.25, 1e-12);
+        assert_almost_eq!(individuals.lcl, 12.4 - 2.660 * 3.25, 1e-12);
+
+        let moving_range = stats.moving_range_limits();
+        assert_almost_eq!(moving_range.cl, 3.25, 1e-12);
+        assert_almost_eq!(moving_range.ucl, 3.267 * 3.25, 1e-12);
+        assert_eq!(0.0, moving_range.lcl);
+
+        // every batch ranges over 2, R̄ = 2 with D4 = 2.574 for batches of 3
+        assert_eq!(vec![2.0; 5], stats.within_data());
+        let within = stats.within_limits();
+        assert_almost_eq!(within.cl, 2.0, 1e-12);
+        assert_almost_eq!(within.ucl, 2.574 * 2.0, 1e-12);
+        assert_eq!(0.0, within.lcl);
+
+        // the Xbar chart limits 12.4 ± 1.023 * 2 ignore the batch-to-batch variation and
+        // flag the batches averaging 15 and 10
+        xbar_r.update();
+        let rule = vec![SpcRule::Rule1Beyond3Sigma(1, 3)];
+        let flagged = xbar_r.out_of_control_points(rule.clone()).unwrap();
+        assert_eq!(vec![1, 4], flagged.into_iter().collect::<Vec<usize>>());
+        let mut individuals = stats.individuals();
+        assert!(individuals.out_of_control_points(rule).unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_within_chart_type() {
+        assert!(BatchStats::new(3, GroupStatsChartType::XbarRChart).is_err());
+        assert!(BatchStats::new(1, GroupStatsChartType::SChart).is_err());
+
+        let mut stats = BatchStats::new(2, GroupStatsChartType::SChart).unwrap();
+        stats.add_data(&[1.0, 3.0]).unwrap();
+        stats.add_data(&[2.0, 6.0]).unwrap();
+        assert!(stats.add_data(&[1.0, 2.0, 3.0]).is_err());
+        // s = sqrt(2), sqrt(8)
+        let within = stats.within_data();
+        assert_almost_eq!(within[0], 2f64.sqrt(), 1e-12);
+        assert_almost_eq!(within[1], 8f64.sqrt(), 1e-12);
+        assert_eq!(vec![2.0, 4.0], stats.individuals_data());
+    }
+}
@@ -0,0 +1,266 @@
+//! Control chart constants indexed by the subgroup size (or moving range span) `n`.
+//!
+//! The tables cover `n` in `2..=25`, except `E2` and the median range `d4`, which cover
+//! the moving range spans `2..=10`. Every lookup panics outside its range.
+
+const A2_TABLE: [f64; 26] = [
+    0.0, 0.0, 1.880, 1.023, 0.729, 0.577, 0.483, 0.419, 0.373, 0.337, 0.308, 0.285, 0.266, 0.249,
+    0.235, 0.223, 0.212, 0.203, 0.194, 0.187, 0.180, 0.173, 0.167, 0.162, 0.157, 0.153,
+];
+
+const D2_TABLE: [f64; 26] = [
+    0.0, 0.0, 1.128, 1.693, 2.059, 2.326, 2.534, 2.704, 2.847, 2.97, 3.078, 3.173, 3.258, 3.336,
+    3.407, 3.472, 3.532, 3.588, 3.64, 3.689, 3.735, 3.778, 3.819, 3.858, 3.895, 3.931,
+];
+
+const D3_TABLE: [f64; 26] = [
+    0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.076, 0.136, 0.184, 0.223, 0.256, 0.283, 0.307, 0.328,
+    0.347, 0.363, 0.378, 0.391, 0.403, 0.415, 0.425, 0.434, 0.443, 0.451, 0.459,
+];
+
+const D4_TABLE: [f64; 26] = [
+    0.0, 0.0, 3.267, 2.574, 2.282, 2.114, 2.004, 1.924, 1.864, 1.816, 1.777, 1.744, 1.717, 1.693,
+    1.672, 1.653, 1.637, 1.622, 1.608, 1.597, 1.585, 1.575, 1.566, 1.557, 1.548, 1.541,
+];
+
+const A3_TABLE: [f64; 26] = [
+    0.0, 0.0, 2.659, 1.954, 1.628, 1.427, 1.287, 1.182, 1.099, 1.032, 0.975, 0.927, 0.886, 0.850,
+    0.817, 0.789, 0.763, 0.739, 0.718, 0.698, 0.680, 0.663, 0.647, 0.633, 0.619, 0.606,
+];
+
+const C4_TABLE: [f64; 26] = [
+    0.0, 0.0, 0.7979, 0.8862, 0.9213, 0.94, 0.9515, 0.9594, 0.965, 0.9693, 0.9727, 0.9754, 0.9776,
+    0.9794, 0.981, 0.9823, 0.9835, 0.9845, 0.9854, 0.9862, 0.9869, 0.9876, 0.9882, 0.9887, 0.9892,
+    0.9896,
+];
+
+const B3_TABLE: [f64; 26] = [
+    0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.030, 0.118, 0.185, 0.239, 0.284, 0.321, 0.354, 0.382, 0.406,
+    0.428, 0.448, 0.466, 0.482, 0.497, 0.510, 0.523, 0.534, 0.545, 0.555, 0.565,
+];
+
+const B4_TABLE: [f64; 26] = [
+    0.0, 0.0, 3.267, 2.568, 2.266, 2.089, 1.970, 1.882, 1.815, 1.761, 1.716, 1.679, 1.646, 1.618,
+    1.594, 1.572, 1.552, 1.534, 1.518, 1.503, 1.490, 1.477, 1.466, 1.455, 1.445, 1.435,
+];
+
+const E2_TABLE: [f64; 11] = [
+    0.0, 0.0, 2.660, 1.772, 1.457, 1.290, 1.184, 1.109, 1.054, 1.010, 0.975,
+];
+
+const MEDIAN_D4_TABLE: [f64; 11] = [
+    0.0, 0.0, 0.954, 1.588, 1.978, 2.257, 2.472, 2.645, 2.791, 2.915, 3.024,
+];
+
+/// The constants of one subgroup size, e.g. to cite them in a report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlConstants {
+    pub n: usize,
+    pub a2: f64,
+    pub a3: f64,
+    pub d2: f64,
+    /// `D3`, the R chart lower limit factor (`d4` is the median range constant).
+    pub d3_factor: f64,
+    /// `D4`, the R chart upper limit factor.
+    pub d4_factor: f64,
+    pub b3: f64,
+    pub b4: f64,
+    pub c4: f64,
+}
+
+impl ControlConstants {
+    /// Looks up every constant for subgroups of size `n`.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is not in `2..=25`.
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            a2: a2(n),
+            a3: a3(n),
+            d2: d2(n),
+            d3_factor: D3(n),
+            d4_factor: D4(n),
+            b3: b3(n),
+            b4: b4(n),
+            c4: c4(n),
+        }
+    }
+}
+
+fn lookup(name: &str, table: &[f64], n: usize) -> f64 {
+    if !(2..table.len()).contains(&n) {
+        panic!(
+            "constants: no {} for n = {}, expected 2..={}",
+            name,
+            n,
+            table.len() - 1
+        );
+    }
+    table[n]
+}
+
+/// Xbar-R chart limit factor, `cl ± A2 * R̄`.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=25`.
+pub fn a2(n: usize) -> f64 {
+    lookup("A2", &A2_TABLE, n)
+}
+
+/// Xbar-S chart limit factor, `cl ± A3 * s̄`.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=25`.
+pub fn a3(n: usize) -> f64 {
+    lookup("A3", &A3_TABLE, n)
+}
+
+/// S chart lower limit factor, `B3 * s̄`.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=25`.
+pub fn b3(n: usize) -> f64 {
+    lookup("B3", &B3_TABLE, n)
+}
+
+/// S chart upper limit factor, `B4 * s̄`.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=25`.
+pub fn b4(n: usize) -> f64 {
+    lookup("B4", &B4_TABLE, n)
+}
+
+/// Bias correction for the standard deviation, `s̄ / c4` estimates sigma.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=25`.
+pub fn c4(n: usize) -> f64 {
+    lookup("c4", &C4_TABLE, n)
+}
+
+/// Bias correction for the range, `R̄ / d2` estimates sigma.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=25`.
+pub fn d2(n: usize) -> f64 {
+    lookup("d2", &D2_TABLE, n)
+}
+
+/// R chart lower limit factor, `D3 * R̄`.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=25`.
+#[allow(non_snake_case)]
+pub fn D3(n: usize) -> f64 {
+    lookup("D3", &D3_TABLE, n)
+}
+
+/// R chart upper limit factor, `D4 * R̄`.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=25`.
+#[allow(non_snake_case)]
+pub fn D4(n: usize) -> f64 {
+    lookup("D4", &D4_TABLE, n)
+}
+
+/// Bias correction for the median range, `median(R) / d4` estimates sigma.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=10`.
+pub fn d4(n: usize) -> f64 {
+    lookup("d4", &MEDIAN_D4_TABLE, n)
+}
+
+/// Individuals chart limit factor, `cl ± E2 * MR̄`, equal to `3 / d2`.
+///
+/// # Panics
+///
+/// If `n` is not in `2..=10`.
+pub fn e2(n: usize) -> f64 {
+    lookup("E2", &E2_TABLE, n)
+}
+
+#[cfg(test)]
+mod test_constants {
+    use crate::constants::{ControlConstants, D3, D4, b3, b4, c4, d2, e2};
+
+    #[test]
+    pub fn test_moving_tables_agree() {
+        // The moving range tables moving_stats.rs used to carry for spans 2..=10.
+        let moving_d2 = [1.128, 1.693, 2.059, 2.326, 2.534, 2.704, 2.847, 2.97, 3.078];
+        let moving_d3 = [0.0, 0.0, 0.0, 0.0, 0.0, 0.076, 0.136, 0.184, 0.223];
+        let moving_d4 = [
+            3.267, 2.574, 2.282, 2.114, 2.004, 1.924, 1.864, 1.816, 1.777,
+        ];
+        for n in 2..=10 {
+            assert_eq!(moving_d2[n - 2], d2(n));
+            assert_eq!(moving_d3[n - 2], D3(n));
+            assert_eq!(moving_d4[n - 2], D4(n));
+            assert!((e2(n) - 3.0 / d2(n)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    pub fn test_control_constants() {
+        let constants = ControlConstants::new(5);
+        assert_eq!(5, constants.n);
+        assert_eq!(0.577, constants.a2);
+        assert_eq!(1.427, constants.a3);
+        assert_eq!(2.326, constants.d2);
+        assert_eq!(0.0, constants.d3_factor);
+        assert_eq!(2.114, constants.d4_factor);
+        assert_eq!(0.0, constants.b3);
+        assert_eq!(2.089, constants.b4);
+        assert_eq!(0.94, constants.c4);
+        // group_stats.rs used to carry 2.571
+        assert_eq!(2.574, D4(3));
+        assert_eq!(2.574, ControlConstants::new(3).d4_factor);
+    }
+
+    #[test]
+    pub fn test_s_chart_constants() {
+        // Montgomery, Introduction to Statistical Quality Control, Appendix VI
+        let textbook = [
+            (2, 0.0, 3.267),
+            (5, 0.0, 2.089),
+            (6, 0.030, 1.970),
+            (10, 0.284, 1.716),
+            (12, 0.354, 1.646),
+            (17, 0.466, 1.534),
+            (25, 0.565, 1.435),
+        ];
+        for (n, expected_b3, expected_b4) in textbook {
+            assert_eq!(expected_b3, b3(n), "b3({})", n);
+            assert_eq!(expected_b4, b4(n), "b4({})", n);
+        }
+        // B3 and B4 = 1 ∓ 3 * sqrt(1 - c4²) / c4, so B3 + B4 = 2 wherever B3 is not clamped
+        for n in 2..=25 {
+            let half_width = 3.0 * (1.0 - c4(n) * c4(n)).sqrt() / c4(n);
+            assert!((b4(n) - (1.0 + half_width)).abs() < 2e-3, "b4({})", n);
+            assert!(
+                (b3(n) - (1.0 - half_width).max(0.0)).abs() < 2e-3,
+                "b3({})",
+                n
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "no d2 for n = 26")]
+    pub fn test_out_of_range() {
+        d2(26);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::statistics::Statistics;
+
+/// Cumulative sum chart of individual values against a target, for detecting small
+/// sustained shifts of the process mean.
+///
+/// The decision interval `h` and the reference value (allowance) `k` are in units of
+/// `sigma`, 5 and 0.5 by default, which detect a 1σ shift.
+#[derive(Debug, Clone)]
+pub struct Cusum {
+    target: f64,
+    sigma: f64,
+    k: f64,
+    h: f64,
+    data: Vec<f64>,
+}
+
+impl Cusum {
+    pub fn new(target: f64, sigma: f64) -> Result<Cusum, String> {
+        if !(sigma > 0.0 && sigma.is_finite()) {
+            return Err("Cusum: sigma must be positive".to_string());
+        }
+        Ok(Self {
+            target,
+            sigma,
+            k: 0.5,
+            h: 5.0,
+            data: vec![],
+        })
+    }
+
+    pub fn add_data(&mut self, value: f64) {
+        self.data.push(value);
+    }
+
+    /// Adds each value with `add_data`.
+    pub fn extend<I: IntoIterator<Item = f64>>(&mut self, iter: I) {
+        for value in iter {
+            self.add_data(value);
+        }
+    }
+
+    /// The standardized cumulative sums `S_i = Σ_{j<=i} (x_j - target) / sigma`, the
+    /// series a V-mask is laid over.
+    pub fn cumulative_sums(&self) -> Vec<f64> {
+        self.data
+            .iter()
+            .map(|x| (x - self.target) / self.sigma)
+            .collect::<Vec<f64>>()
+            .cumulative_sum()
+    }
+
+    /// The upper tabular CUSUM `C+_i = max(0, x_i - (target + k * sigma) + C+_{i-1})`.
+    pub fn upper_cusum(&self) -> Vec<f64> {
+        let reference = self.target + self.k * self.sigma;
+        let mut sum = 0.0;
+        self.data
+            .iter()
+            .map(|x| {
+                sum = (x - reference + sum).max(0.0);
+                sum
+            })
+            .collect()
+    }
+
+    /// The lower tabular CUSUM `C-_i = max(0, (target - k * sigma) - x_i + C-_{i-1})`.
+    pub fn lower_cusum(&self) -> Vec<f64> {
+        let reference = self.target - self.k * self.sigma;
+        let mut sum = 0.0;
+        self.data
+            .iter()
+            .map(|x| {
+                sum = (reference - x + sum).max(0.0);
+                sum
+            })
+            .collect()
+    }
+
+    /// Indices at which either tabular CUSUM exceeds the decision interval `h * sigma`.
+    pub fn tabular_signals(&self) -> Vec<usize> {
+        let limit = self.h * self.sigma;
+        self.upper_cusum()
+            .into_iter()
+            .zip(self.lower_cusum())
+            .enumerate()
+            .filter(|(_, (upper, lower))| *upper > limit || *lower > limit)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Places a V-mask at the most recent point of `cumulative_sums` and returns the
+    /// indices of the earlier sums falling outside its arms.
+    ///
+    /// The vertex of the mask lies `lead_distance_d` samples ahead of the last point `n`,
+    /// at the height `S_n`, and each arm opens at `half_angle_theta` (radians) from the
+    /// horizontal, so point `j` is outside when
+    /// `|S_j - S_n| > (lead_distance_d + n - j) * tan(half_angle_theta)`. A point below
+    /// the lower arm signals an upward shift, above the upper arm a downward one. With
+    /// `tan(half_angle_theta) = k` and `lead_distance_d = h / k` the mask placed at a point
+    /// signals exactly when the tabular CUSUM does at that point.
+    pub fn vmask_signals(&self, lead_distance_d: f64, half_angle_theta: f64) -> Vec<usize> {
+        let sums = self.cumulative_sums();
+        let Some(&last) = sums.last() else {
+            return vec![];
+        };
+        let n = sums.len() - 1;
+        let slope = half_angle_theta.tan();
+        sums.iter()
+            .enumerate()
+            .filter(|&(j, s)| (s - last).abs() > (lead_distance_d + (n - j) as f64) * slope)
+            .map(|(j, _)| j)
+            .collect()
+    }
+
+    pub fn target(&self) -> f64 {
+        self.target
+    }
+
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+
+    pub fn data(&self) -> Vec<f64> {
+        self.data.to_vec()
+    }
+
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    pub fn h(&self) -> f64 {
+        self.h
+    }
+
+    /// Sets the reference value in sigma, half the shift to detect.
+    pub fn set_k(&mut self, k: f64) -> Result<(), String> {
+        if !(k >= 0.0 && k.is_finite()) {
+            return Err("Cusum: k must not be negative".to_string());
+        }
+        self.k = k;
+        Ok(())
+    }
+
+    /// Sets the decision interval in sigma.
+    pub fn set_h(&mut self, h: f64) -> Result<(), String> {
+        if !(h > 0.0 && h.is_finite()) {
+            return Err("Cusum: h must be positive".to_string());
+        }
+        self.h = h;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_cusum {
+    use crate::cusum::Cusum;
+
+    const IN_CONTROL: [f64; 8] = [10.2, 9.8, 10.1, 9.9, 10.0, 10.3, 9.7, 10.0];
+    const SHIFTED: [f64; 6] = [12.1, 11.9, 12.2, 12.0, 11.8, 12.1];
+
+    #[test]
+    pub fn test_tabular_signals() {
+        assert!(Cusum::new(10.0, 0.0).is_err());
+        let mut cusum = Cusum::new(10.0, 1.0).unwrap();
+        assert!(cusum.set_h(0.0).is_err());
+        cusum.extend(IN_CONTROL);
+        assert!(cusum.tabular_signals().is_empty());
+        cusum.extend(SHIFTED);
+        // C+ after the shift: 1.6, 3.0, 4.7, 6.2, 7.5, 9.1
+        assert_eq!(vec![11, 12, 13], cusum.tabular_signals());
+        assert!(cusum.lower_cusum().iter().all(|&c| c < 0.5));
+    }
+
+    #[test]
+    pub fn test_vmask_signals() {
+        let mut cusum = Cusum::new(10.0, 1.0).unwrap();
+        let (d, theta) = (10.0, 0.5f64.atan());
+        assert!(cusum.vmask_signals(d, theta).is_empty());
+        cusum.extend(IN_CONTROL);
+        assert!(cusum.vmask_signals(d, theta).is_empty());
+
+        // S_13 = 12.1, the lower arm at j is 12.1 - (23 - j) / 2, above S_j up to j = 9
+        cusum.extend(SHIFTED);
+        assert_eq!(
+            (0..=9).collect::<Vec<usize>>(),
+            cusum.vmask_signals(d, theta)
+        );
+    }
+}
@@ -0,0 +1,543 @@
+//! Provides the cumulative distribution functions and quantiles needed for
+//! probability plots, normality tests and capability indices
+
+use crate::SQRT_2PI;
+#[cfg(all(not(feature = "std"), feature = "libm"))]
+#[allow(unused_imports)]
+use num_traits::Float;
+
+/// Evaluates the cumulative distribution function of the standard normal
+/// distribution at `x`
+///
+/// # Remarks
+///
+/// Uses Hart's double precision algorithm as given by West (2005), "Better
+/// approximations to cumulative normal functions", with an absolute error
+/// below `1e-14`
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate spc_rs;
+///
+/// use spc_rs::distribution::normal_cdf;
+///
+/// # fn main() {
+/// assert_eq!(normal_cdf(0.0), 0.5);
+/// assert_almost_eq!(normal_cdf(1.96), 0.9750021048517795, 1e-14);
+/// # }
+/// ```
+pub fn normal_cdf(x: f64) -> f64 {
+    const NUMERATOR: [f64; 7] = [
+        3.52624965998911e-02,
+        0.700383064443688,
+        6.37396220353165,
+        33.912866078383,
+        112.079291497871,
+        221.213596169931,
+        220.206867912376,
+    ];
+    const DENOMINATOR: [f64; 8] = [
+        8.83883476483184e-02,
+        1.75566716318264,
+        16.064177579207,
+        86.7807322029461,
+        296.564248779674,
+        637.333633378831,
+        793.826512519948,
+        440.413735824752,
+    ];
+
+    if x.is_nan() {
+        return f64::NAN;
+    }
+    let z = x.abs();
+    let tail = if z > 37.0 {
+        0.0
+    } else {
+        let e = (-z * z / 2.0).exp();
+        if z < 7.07106781186547 {
+            let n = NUMERATOR.iter().fold(0.0, |acc, &c| acc * z + c);
+            let d = DENOMINATOR.iter().fold(0.0, |acc, &c| acc * z + c);
+            e * n / d
+        } else {
+            let f = z + 1.0 / (z + 2.0 / (z + 3.0 / (z + 4.0 / (z + 0.65))));
+            e / f / SQRT_2PI
+        }
+    };
+    if x <= 0.0 { tail } else { 1.0 - tail }
+}
+
+/// Evaluates the quantile (inverse cumulative distribution function) of the
+/// standard normal distribution at the probability `p`
+///
+/// # Remarks
+///
+/// Uses Acklam's rational approximation refined by one step of Halley's
+/// method against `normal_cdf`.
+///
+/// Returns `f64::NEG_INFINITY` for `p = 0`, `f64::INFINITY` for `p = 1` and
+/// `f64::NAN` if `p` is outside `[0, 1]` or `f64::NAN`
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate spc_rs;
+///
+/// use spc_rs::distribution::normal_inverse_cdf;
+///
+/// # fn main() {
+/// assert_eq!(normal_inverse_cdf(0.5), 0.0);
+/// assert_almost_eq!(normal_inverse_cdf(0.975), 1.959963984540054, 1e-12);
+/// # }
+/// ```
+pub fn normal_inverse_cdf(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p.is_nan() || !(0.0..=1.0).contains(&p) {
+        return f64::NAN;
+    }
+    if p == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p == 1.0 {
+        return f64::INFINITY;
+    }
+    if p == 0.5 {
+        return 0.0;
+    }
+
+    let x = if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        let q = (-2.0 * (1.0 - p).ln()).sqrt();
+        -(((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    let e = normal_cdf(x) - p;
+    let u = e * SQRT_2PI * (x * x / 2.0).exp();
+    x - u / (1.0 + x * u / 2.0)
+}
+
+/// Evaluates the standard normal score of the percentile `p`, the number of
+/// standard deviations above the mean below which `p` percent of a normal
+/// population falls
+///
+/// # Remarks
+///
+/// Equals `normal_inverse_cdf(p / 100)`, so returns `f64::NEG_INFINITY` for
+/// `p = 0`, `f64::INFINITY` for `p = 100` and `f64::NAN` if `p` is outside
+/// `[0, 100]` or `f64::NAN`
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate spc_rs;
+///
+/// use spc_rs::distribution::z_from_percentile;
+///
+/// # fn main() {
+/// assert_eq!(z_from_percentile(50.0), 0.0);
+/// assert_almost_eq!(z_from_percentile(87.0), 1.1263911290388, 1e-12);
+/// # }
+/// ```
+pub fn z_from_percentile(p: f64) -> f64 {
+    normal_inverse_cdf(p / 100.0)
+}
+
+/// Evaluates the cumulative distribution function of Student's t
+/// distribution with `df` degrees of freedom at `t`
+///
+/// # Remarks
+///
+/// Evaluated through the regularized incomplete beta function. Returns
+/// `f64::NAN` if `t` is `f64::NAN` or `df` is not positive
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate spc_rs;
+///
+/// use spc_rs::distribution::student_t_cdf;
+///
+/// # fn main() {
+/// assert_eq!(student_t_cdf(0.0, 5.0), 0.5);
+/// assert_almost_eq!(student_t_cdf(2.228138851986274, 10.0), 0.975, 1e-12);
+/// # }
+/// ```
+pub fn student_t_cdf(t: f64, df: f64) -> f64 {
+    if t.is_nan() || df.is_nan() || df <= 0.0 {
+        return f64::NAN;
+    }
+    if t.is_infinite() {
+        return if t > 0.0 { 1.0 } else { 0.0 };
+    }
+    let tail = 0.5 * regularized_incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+    if t <= 0.0 { tail } else { 1.0 - tail }
+}
+
+/// Evaluates the quantile (inverse cumulative distribution function) of
+/// Student's t distribution with `df` degrees of freedom at the probability
+/// `p`
+///
+/// # Remarks
+///
+/// Solved by bisection on `student_t_cdf` to a relative accuracy of about
+/// `1e-13`.
+///
+/// Returns `f64::NEG_INFINITY` for `p = 0`, `f64::INFINITY` for `p = 1` and
+/// `f64::NAN` if `p` is outside `[0, 1]` or `df` is not positive
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate spc_rs;
+///
+/// use spc_rs::distribution::student_t_inverse_cdf;
+///
+/// # fn main() {
+/// assert_almost_eq!(student_t_inverse_cdf(0.975, 10.0), 2.228138851986274, 1e-10);
+/// assert_almost_eq!(student_t_inverse_cdf(0.025, 1.0), -12.706204736174698, 1e-9);
+/// # }
+/// ```
+pub fn student_t_inverse_cdf(p: f64, df: f64) -> f64 {
+    if p.is_nan() || !(0.0..=1.0).contains(&p) || df.is_nan() || df <= 0.0 {
+        return f64::NAN;
+    }
+    if p == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p == 1.0 {
+        return f64::INFINITY;
+    }
+    if p == 0.5 {
+        return 0.0;
+    }
+    if p < 0.5 {
+        return -student_t_inverse_cdf(1.0 - p, df);
+    }
+
+    let mut low = 0.0;
+    let mut high = 1.0;
+    while student_t_cdf(high, df) < p {
+        low = high;
+        high *= 2.0;
+    }
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if student_t_cdf(mid, df) < p {
+            low = mid;
+        } else {
+            high = mid;
+        }
+        if high - low <= 1e-14 * high {
+            break;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Evaluates the quantile of the Poisson distribution with mean `lambda` at
+/// the probability `p`, the smallest count `k` with `P(X <= k) >= p`
+///
+/// # Remarks
+///
+/// Sums the probability mass function upwards from zero, so takes about
+/// `lambda` steps. Returns `f64::INFINITY` for `p = 1` and `f64::NAN` if `p`
+/// is outside `[0, 1]` or `lambda` is negative
+///
+/// # Examples
+///
+/// ```
+/// use spc_rs::distribution::poisson_inverse_cdf;
+///
+/// assert_eq!(poisson_inverse_cdf(0.5, 2.0), 2.0);
+/// assert_eq!(poisson_inverse_cdf(0.99865, 2.0), 7.0);
+/// assert_eq!(poisson_inverse_cdf(0.00135, 8.0), 1.0);
+/// ```
+pub fn poisson_inverse_cdf(p: f64, lambda: f64) -> f64 {
+    if p.is_nan() || !(0.0..=1.0).contains(&p) || lambda.is_nan() || lambda < 0.0 {
+        return f64::NAN;
+    }
+    if p == 1.0 {
+        return f64::INFINITY;
+    }
+    if lambda == 0.0 {
+        return 0.0;
+    }
+    let mut cdf = 0.0;
+    let mut k = 0.0;
+    loop {
+        let pmf = (k * lambda.ln() - lambda - ln_gamma(k + 1.0)).exp();
+        cdf += pmf;
+        if cdf >= p || (k > lambda && pmf == 0.0) {
+            return k;
+        }
+        k += 1.0;
+    }
+}
+
+/// Evaluates the quantile of the binomial distribution of `n` trials with
+/// success probability `prob` at the probability `p`, the smallest count `k`
+/// with `P(X <= k) >= p`
+///
+/// # Remarks
+///
+/// Sums the probability mass function upwards from zero. Returns `f64::NAN`
+/// if `p` or `prob` is outside `[0, 1]`
+///
+/// # Examples
+///
+/// ```
+/// use spc_rs::distribution::binomial_inverse_cdf;
+///
+/// assert_eq!(binomial_inverse_cdf(0.5, 10, 0.5), 5.0);
+/// assert_eq!(binomial_inverse_cdf(0.00135, 100, 0.08), 1.0);
+/// assert_eq!(binomial_inverse_cdf(1.0, 10, 0.5), 10.0);
+/// ```
+pub fn binomial_inverse_cdf(p: f64, n: u64, prob: f64) -> f64 {
+    if p.is_nan() || !(0.0..=1.0).contains(&p) || prob.is_nan() || !(0.0..=1.0).contains(&prob) {
+        return f64::NAN;
+    }
+    if prob == 0.0 {
+        return 0.0;
+    }
+    if prob == 1.0 || p == 1.0 {
+        return n as f64;
+    }
+    let n = n as f64;
+    let ln_n_factorial = ln_gamma(n + 1.0);
+    let mut cdf = 0.0;
+    let mut k = 0.0;
+    while k < n {
+        cdf += (ln_n_factorial - ln_gamma(k + 1.0) - ln_gamma(n - k + 1.0)
+            + k * prob.ln()
+            + (n - k) * (1.0 - prob).ln())
+        .exp();
+        if cdf >= p {
+            return k;
+        }
+        k += 1.0;
+    }
+    n
+}
+
+/// Natural logarithm of the gamma function for `x > 0`, by the Lanczos
+/// approximation (`g = 7`, nine coefficients)
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // reflection formula
+        return (core::f64::consts::PI / (core::f64::consts::PI * x).sin()).ln()
+            - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, &c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + G + 0.5;
+    crate::LN_SQRT_2PI + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`, evaluated with the
+/// continued fraction of Numerical Recipes (`betacf`)
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - ln_front.exp() * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-16 {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod test_distribution {
+    use crate::assert_almost_eq;
+    use crate::distribution::{
+        binomial_inverse_cdf, ln_gamma, normal_cdf, normal_inverse_cdf, poisson_inverse_cdf,
+        student_t_cdf, student_t_inverse_cdf, z_from_percentile,
+    };
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!(normal_cdf(f64::NAN).is_nan());
+        assert_eq!(0.0, normal_cdf(f64::NEG_INFINITY));
+        assert_eq!(1.0, normal_cdf(f64::INFINITY));
+        assert_almost_eq!(normal_cdf(-1.0), 0.15865525393145707, 1e-15);
+        assert_almost_eq!(normal_cdf(3.0), 0.9986501019683699, 1e-15);
+        assert_almost_eq!(normal_cdf(-8.0), 6.22096057427178e-16, 1e-20);
+    }
+
+    #[test]
+    fn test_normal_inverse_cdf() {
+        assert!(normal_inverse_cdf(-0.1).is_nan());
+        assert!(normal_inverse_cdf(f64::NAN).is_nan());
+        assert_eq!(f64::NEG_INFINITY, normal_inverse_cdf(0.0));
+        assert_eq!(f64::INFINITY, normal_inverse_cdf(1.0));
+        for p in [1e-10, 0.001, 0.02425, 0.1, 0.3, 0.7, 0.975, 0.999] {
+            assert_almost_eq!(normal_cdf(normal_inverse_cdf(p)), p, 1e-12 * p.max(1e-3));
+        }
+        assert_almost_eq!(normal_inverse_cdf(0.975), 1.959963984540054, 1e-12);
+    }
+
+    #[test]
+    fn test_z_from_percentile() {
+        assert_eq!(normal_inverse_cdf(0.975), z_from_percentile(97.5));
+        assert_almost_eq!(z_from_percentile(99.865), 3.0, 1e-3);
+        assert_eq!(f64::INFINITY, z_from_percentile(100.0));
+        assert!(z_from_percentile(0.87).is_finite());
+        assert!(z_from_percentile(100.1).is_nan());
+        assert!(z_from_percentile(-1.0).is_nan());
+    }
+
+    #[test]
+    fn test_ln_gamma() {
+        assert_almost_eq!(ln_gamma(1.0), 0.0, 1e-14);
+        assert_almost_eq!(ln_gamma(0.5), std::f64::consts::PI.sqrt().ln(), 1e-14);
+        assert_almost_eq!(ln_gamma(10.0), 362880f64.ln(), 1e-12);
+    }
+
+    #[test]
+    fn test_discrete_inverse_cdf() {
+        let tail = normal_cdf(-3.0);
+        assert_eq!(0.0, poisson_inverse_cdf(tail, 2.0));
+        assert_eq!(7.0, poisson_inverse_cdf(1.0 - tail, 2.0));
+        assert_eq!(18.0, poisson_inverse_cdf(1.0 - tail, 8.0));
+        assert_eq!(1000.0, poisson_inverse_cdf(0.5, 1000.0));
+        assert_eq!(0.0, poisson_inverse_cdf(0.0, 3.0));
+        assert_eq!(0.0, poisson_inverse_cdf(0.9, 0.0));
+        assert_eq!(f64::INFINITY, poisson_inverse_cdf(1.0, 3.0));
+        assert!(poisson_inverse_cdf(1.1, 3.0).is_nan());
+        assert!(poisson_inverse_cdf(0.5, -1.0).is_nan());
+
+        assert_eq!(17.0, binomial_inverse_cdf(1.0 - tail, 100, 0.08));
+        assert_eq!(392.0, binomial_inverse_cdf(0.3, 1000, 0.4));
+        assert_eq!(0.0, binomial_inverse_cdf(0.0, 10, 0.5));
+        assert_eq!(0.0, binomial_inverse_cdf(0.9, 10, 0.0));
+        assert_eq!(10.0, binomial_inverse_cdf(0.1, 10, 1.0));
+        assert!(binomial_inverse_cdf(0.5, 10, 1.5).is_nan());
+    }
+
+    #[test]
+    fn test_student_t() {
+        assert!(student_t_cdf(1.0, 0.0).is_nan());
+        assert!(student_t_inverse_cdf(1.5, 3.0).is_nan());
+        // df = 1 is the Cauchy distribution
+        assert_almost_eq!(student_t_cdf(1.0, 1.0), 0.75, 1e-14);
+        assert_almost_eq!(student_t_cdf(-2.0, 3.0), 0.0696629842794216, 1e-13);
+        assert_almost_eq!(student_t_inverse_cdf(0.995, 2.0), 9.924843200918272, 1e-9);
+        assert_almost_eq!(student_t_inverse_cdf(0.95, 30.0), 1.697260886593959, 1e-10);
+        // large df approaches the normal distribution
+        assert_almost_eq!(
+            student_t_inverse_cdf(0.975, 1e6),
+            normal_inverse_cdf(0.975),
+            1e-5
+        );
+    }
+}
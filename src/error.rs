@@ -1,5 +1,5 @@
-use std::error::Error;
-use std::fmt;
+use core::error::Error;
+use core::fmt;
 
 /// Enumeration of possible errors thrown within the `statrs` library
 #[derive(Debug)]
@@ -48,6 +48,8 @@ pub enum StatsError {
     ContainerExpectedSumVar(&'static str, &'static str),
     /// Special case exception
     SpecialCase(&'static str),
+    /// The data have no variation, e.g. all measurements are identical
+    ZeroVariance,
 }
 
 impl Error for StatsError {
@@ -104,6 +106,7 @@ impl fmt::Display for StatsError {
                 write!(f, "Elements in container {} expected to sum to {}", s, sum)
             }
             StatsError::SpecialCase(s) => write!(f, "{}", s),
+            StatsError::ZeroVariance => write!(f, "Data have zero variance"),
         }
     }
 }
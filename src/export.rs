@@ -0,0 +1,203 @@
+//! Rule validation results and charts in formats a dashboard or spreadsheet can consume.
+
+use crate::{ControlLimits, SpcRuleValidationResult};
+
+/// The chart family a `ControlChartSpec` was taken from.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChartFamily {
+    Group,
+    Attribute,
+    Moving,
+}
+
+/// A chart in the same shape for all chart families, as returned by the `to_spec`
+/// methods of `GroupStats`, `AttributeStats` and `MovingStats`.
+///
+/// `chart_type` is the `Display` name of the family's chart type, e.g. `"xbar_r"`, `"p"`
+/// or `"individuals"`. `violations` is `None` until results are attached with
+/// `with_violations`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlChartSpec {
+    pub family: ChartFamily,
+    pub chart_type: String,
+    pub limits: ControlLimits,
+    pub data: Vec<f64>,
+    pub violations: Option<Vec<SpcRuleValidationResult>>,
+}
+
+impl ControlChartSpec {
+    pub fn with_violations(mut self, violations: Vec<SpcRuleValidationResult>) -> Self {
+        self.violations = Some(violations);
+        self
+    }
+}
+
+/// Serializes `results` to a JSON array, one object per rule with its `rule`,
+/// `bad_point_index`, `bad_point_data` and `validation_passed` fields.
+#[cfg(feature = "serde")]
+pub fn validation_to_json(results: &[SpcRuleValidationResult]) -> String {
+    serde_json::to_string(results).expect("validation results always serialize")
+}
+
+/// Formats `results` as CSV with the header `rule,validation_passed,index,value`.
+///
+/// Each violating point gets a row with its `bad_point_index` and `bad_point_data`, and
+/// a rule without violations a single row with empty `index` and `value`. The rule is
+/// described by its `Display` text, quoted.
+pub fn validation_to_csv(results: &[SpcRuleValidationResult]) -> String {
+    let mut csv = String::from("rule,validation_passed,index,value\n");
+    for res in results {
+        let rule = format!("\"{}\"", res.rule.to_string().replace('"', "\"\""));
+        if res.bad_point_index.is_empty() {
+            csv.push_str(&format!("{},{},,\n", rule, res.validation_passed));
+        }
+        for (index, value) in res.bad_point_index.iter().zip(&res.bad_point_data) {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                rule, res.validation_passed, index, value
+            ));
+        }
+    }
+    csv
+}
+
+#[cfg(test)]
+mod test_export {
+    use crate::RoundingContext;
+    use crate::RoundingMode::RoundHalfUp;
+    use crate::attribute_stats::{AttributeStats, AttributeStatsChartType};
+    use crate::export::{ChartFamily, ControlChartSpec, validation_to_csv};
+    use crate::group_stats::{GroupStats, GroupStatsChartType};
+    use crate::moving_stats::{MovingStats, MovingStatsChartType};
+    use crate::{SpcRule, SpcRuleValidationResult};
+
+    /// The README example chart with its two rules applied.
+    fn readme_results() -> Vec<SpcRuleValidationResult> {
+        let v1 = [
+            0.65, 0.75, 0.75, 0.60, 0.70, 0.60, 0.75, 0.60, 0.65, 0.60, 0.80, 0.85, 0.70, 0.65,
+            0.90, 0.75, 0.75, 0.75, 0.65, 0.60, 0.50, 0.60, 0.80, 0.65, 0.65,
+        ];
+        let v2 = [
+            0.70, 0.85, 0.80, 0.70, 0.75, 0.75, 0.80, 0.70, 0.80, 0.70, 0.75, 0.75, 0.70, 0.70,
+            0.80, 0.80, 0.70, 0.70, 0.65, 0.60, 0.55, 0.80, 0.65, 0.60, 0.70,
+        ];
+        let v3 = [
+            0.65, 0.75, 0.80, 0.70, 0.65, 0.75, 0.65, 0.80, 0.85, 0.60, 0.90, 0.85, 0.75, 0.85,
+            0.80, 0.75, 0.85, 0.60, 0.85, 0.65, 0.65, 0.65, 0.75, 0.65, 0.70,
+        ];
+        let v4 = [
+            0.65, 0.85, 0.70, 0.75, 0.85, 0.85, 0.75, 0.75, 0.85, 0.80, 0.50, 0.65, 0.75, 0.75,
+            0.75, 0.80, 0.70, 0.70, 0.65, 0.60, 0.80, 0.65, 0.65, 0.60, 0.60,
+        ];
+        let v5 = [
+            0.85, 0.65, 0.75, 0.65, 0.80, 0.70, 0.70, 0.75, 0.75, 0.65, 0.80, 0.70, 0.70, 0.60,
+            0.85, 0.65, 0.80, 0.60, 0.70, 0.65, 0.80, 0.75, 0.65, 0.70, 0.65,
+        ];
+        let mut stats = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_rounding_ctx(Some(RoundingContext::new(2, RoundHalfUp)));
+        for i in 0..v1.len() {
+            stats
+                .add_data(&[v1[i], v2[i], v3[i], v4[i], v5[i]])
+                .unwrap();
+        }
+        stats.update();
+        stats
+            .apply_rule_validation(vec![
+                SpcRule::Rule1Beyond3Sigma(1, 3),
+                SpcRule::Rule2Of3Beyond2Sigma(2, 3, 2),
+            ])
+            .unwrap()
+    }
+
+    #[test]
+    pub fn test_validation_to_csv() {
+        let mut results = readme_results();
+        results.push(SpcRuleValidationResult {
+            rule: SpcRule::Rule1Beyond3Sigma(1, 3),
+            bad_point_index: vec![4, 9],
+            bad_point_data: vec![0.85, 0.5],
+            validation_passed: false,
+        });
+        let expected = "rule,validation_passed,index,value\n\
+                        \"1 points beyond from 3 sigma\",true,,\n\
+                        \"2 out of 3 consecutive points beyond from 2 sigma\",true,,\n\
+                        \"1 points beyond from 3 sigma\",false,4,0.85\n\
+                        \"1 points beyond from 3 sigma\",false,9,0.5\n";
+        assert_eq!(expected, validation_to_csv(&results));
+    }
+
+    #[test]
+    pub fn test_to_spec() {
+        let mut group = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        let mut attribute = AttributeStats::new(AttributeStatsChartType::CChart);
+        let mut moving = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        for (i, x) in [10.0, 10.4, 9.8, 10.2, 10.1].into_iter().enumerate() {
+            group.add_data(&[x, x + 0.2]).unwrap();
+            attribute.add_data(i as f64, 1.0).unwrap();
+            moving.add_data(x);
+        }
+
+        let specs = [group.to_spec(), attribute.to_spec(), moving.to_spec()];
+        assert_eq!(
+            vec![
+                ChartFamily::Group,
+                ChartFamily::Attribute,
+                ChartFamily::Moving
+            ],
+            specs.iter().map(|spec| spec.family).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["xbar_r", "c", "individuals"],
+            specs
+                .iter()
+                .map(|spec| spec.chart_type.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(group.control_limits(), specs[0].limits);
+        assert_eq!(attribute.control_limits(None), specs[1].limits);
+        assert_eq!(moving.control_limits(), specs[2].limits);
+        assert_eq!(group.chart_data(), specs[0].data);
+        assert_eq!(vec![0.0, 1.0, 2.0, 3.0, 4.0], specs[1].data);
+        assert_eq!(moving.chart_data(), specs[2].data);
+        for spec in &specs {
+            assert_eq!(5, spec.data.len());
+            assert!(spec.limits.lcl < spec.limits.cl && spec.limits.cl < spec.limits.ucl);
+            assert!(spec.violations.is_none());
+        }
+
+        let violations = group
+            .apply_rule_validation(vec![SpcRule::Rule1Beyond3Sigma(1, 3)])
+            .unwrap();
+        let spec: ControlChartSpec = specs[0].clone().with_violations(violations.clone());
+        assert_eq!(Some(violations), spec.violations);
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&spec).unwrap();
+            let parsed: ControlChartSpec = serde_json::from_str(&json).unwrap();
+            assert_eq!(
+                (spec.family, &spec.chart_type),
+                (parsed.family, &parsed.chart_type)
+            );
+            assert_eq!(spec.violations, parsed.violations);
+            assert_eq!(spec.data.len(), parsed.data.len());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn test_validation_to_json() {
+        use crate::export::validation_to_json;
+
+        let results = readme_results();
+        let json = validation_to_json(&results);
+        let expected = "[{\"rule\":{\"Rule1Beyond3Sigma\":[1,3]},\"bad_point_index\":[],\
+                        \"bad_point_data\":[],\"validation_passed\":true},\
+                        {\"rule\":{\"Rule2Of3Beyond2Sigma\":[2,3,2]},\"bad_point_index\":[],\
+                        \"bad_point_data\":[],\"validation_passed\":true}]";
+        assert_eq!(expected, json);
+        let parsed: Vec<SpcRuleValidationResult> = serde_json::from_str(&json).unwrap();
+        assert_eq!(results, parsed);
+    }
+}
@@ -1,52 +1,166 @@
+use crate::ascii_chart;
+use crate::constants::ControlConstants;
+use crate::cusum::Cusum;
+use crate::distribution::{normal_inverse_cdf, student_t_inverse_cdf};
+use crate::error::StatsError;
+use crate::export::{ChartFamily, ControlChartSpec};
+#[cfg(feature = "plotters")]
+use crate::render::{self, ChartRenderOptions};
+use crate::rules::validate_rules;
 use crate::statistics::Statistics;
-use crate::{is_alternating, is_decreasing, is_increasing, Rounding, RoundingContext, SpcRule, SpcRuleValidationResult};
-
-const A2: [f64; 26] = [
-    0.0, 0.0, 1.880, 1.023, 0.729, 0.577, 0.483, 0.419, 0.373, 0.337, 0.308, 0.285, 0.266, 0.249,
-    0.235, 0.223, 0.212, 0.203, 0.194, 0.187, 0.180, 0.173, 0.167, 0.162, 0.157, 0.153,
-];
-const d2: [f64; 26] = [
-    0.0, 0.0, 1.128, 1.693, 2.059, 2.326, 2.534, 2.704, 2.847, 2.97, 3.078, 3.173, 3.258, 3.336,
-    3.407, 3.472, 3.532, 3.588, 3.64, 3.689, 3.735, 3.778, 3.819, 3.858, 3.895, 3.931,
-];
-
-const D3: [f64; 26] = [
-    0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.076, 0.136, 0.184, 0.223, 0.256, 0.283, 0.307, 0.328,
-    0.347, 0.363, 0.378, 0.391, 0.403, 0.415, 0.425, 0.434, 0.443, 0.451, 0.459,
-];
-const D4: [f64; 26] = [
-    0.0, 0.0, 3.267, 2.571, 2.282, 2.114, 2.004, 1.924, 1.864, 1.816, 1.777, 1.744, 1.717, 1.693,
-    1.672, 1.653, 1.637, 1.622, 1.608, 1.597, 1.585, 1.575, 1.566, 1.557, 1.548, 1.541,
-];
-
-const A3: [f64; 26] = [
-    0.0, 0.0, 2.659, 1.954, 1.628, 1.427, 1.287, 1.182, 1.099, 1.032, 0.975, 0.927, 0.886, 0.850,
-    0.817, 0.789, 0.763, 0.739, 0.718, 0.698, 0.680, 0.663, 0.647, 0.633, 0.619, 0.606,
-];
-const c4: [f64; 26] = [
-    0.0, 0.0, 0.7979, 0.8862, 0.9213, 0.94, 0.9515, 0.9594, 0.965, 0.9693, 0.9727, 0.9754, 0.9776,
-    0.9794, 0.981, 0.9823, 0.9835, 0.9845, 0.9854, 0.9862, 0.9869, 0.9876, 0.9882, 0.9887, 0.9892,
-    0.9896,
-];
-
-const B3: [f64; 26] = [
-    0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.030, 0.118, 0.185, 0.239, 0.284, 0.321, 0.354, 0.382, 0.406,
-    0.428, 0.448, 0.446, 0.482, 0.497, 0.510, 0.523, 0.534, 0.545, 0.555, 0.565,
-];
-const B4: [f64; 26] = [
-    0.0, 0.0, 3.276, 2.568, 2.266, 2.089, 1.970, 1.882, 1.815, 1.761, 1.716, 1.679, 1.640, 1.618,
-    1.594, 1.572, 1.552, 1.534, 1.518, 1.503, 1.490, 1.477, 1.466, 1.455, 1.445, 1.435,
-];
-
-#[derive(Debug, Eq, PartialEq)]
+use crate::{
+    ControlLimits, DataPoint, ForecastMethod, Rounding, RoundingContext, SpcRule,
+    SpcRuleValidationResult, TrendTies, Zone, ZoneProportions, chart_type_key, write_labeled,
+};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "plotters")]
+use plotters::coord::Shift;
+#[cfg(feature = "plotters")]
+use plotters::prelude::{BitMapBackend, DrawingArea, DrawingBackend, IntoDrawingArea, SVGBackend};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum GroupStatsChartType {
     RChart,
+    #[default]
     XbarRChart,
     SChart,
     XbarSChart,
+    /// Plots the subgroup maxima, with the minima as `secondary_chart_data`, against the
+    /// natural process limits `X̿ ± 3 * R̄ / d2` shared by both series. The extremes are
+    /// not centered on `X̿`, so only `Rule1Beyond3Sigma` applies, to both series, see
+    /// `GroupStats::apply_rule_validation`.
+    MinMaxChart,
+}
+
+impl FromStr for GroupStatsChartType {
+    type Err = String;
+
+    /// Parses a chart type name case-insensitively, ignoring separators, e.g. `"xbar_r"`,
+    /// `"Xbar-R"` or `"XbarRChart"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match chart_type_key(s).as_str() {
+            "r" | "rchart" => Ok(GroupStatsChartType::RChart),
+            "xbarr" | "xr" | "xbarrchart" => Ok(GroupStatsChartType::XbarRChart),
+            "s" | "schart" => Ok(GroupStatsChartType::SChart),
+            "xbars" | "xs" | "xbarschart" => Ok(GroupStatsChartType::XbarSChart),
+            "minmax" | "minmaxchart" => Ok(GroupStatsChartType::MinMaxChart),
+            _ => Err(format!(
+                "Unknown group chart type {:?}, expected one of r, xbar_r, s, xbar_s, min_max",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for GroupStatsChartType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GroupStatsChartType::RChart => "r",
+            GroupStatsChartType::XbarRChart => "xbar_r",
+            GroupStatsChartType::SChart => "s",
+            GroupStatsChartType::XbarSChart => "xbar_s",
+            GroupStatsChartType::MinMaxChart => "min_max",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How `GroupStats` drops old subgroups, see `set_retention_policy`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum RetentionPolicy {
+    /// Keeps the newest `n` subgroups (`group_count`), by default 100.
+    Count(usize),
+    /// Keeps the subgroups at most this old, measured back from the newest timestamp
+    /// given to `add_data_at`. Subgroups added without a timestamp are never aged out.
+    #[cfg(feature = "chrono")]
+    Duration(chrono::Duration),
+}
+
+/// How `sigma_estimate` is derived from the subgroups.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SigmaMethod {
+    /// The average of `s_i / c4[n_i]`, `s̄ / c4` for equal subgroup sizes.
+    SBarOverC4,
+    /// `pooled_std_dev`, the RMS of the subgroup standard deviations for equal sizes.
+    PooledRms,
+    /// The average of `R_i / d2[n_i]`, `R̄ / d2` for equal subgroup sizes.
+    RBarOverD2,
+}
+
+/// The statistics of one stored subgroup, see `GroupStats::subgroup_summaries` and
+/// `GroupStats::retain_subgroups`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubgroupSummary {
+    /// The position of the subgroup among the stored subgroups, oldest first.
+    pub index: usize,
+    pub average: f64,
+    pub range: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Called with each rule result the newest subgroup violates, see
+/// `GroupStats::set_signal_handler`.
+pub type SignalHandler = Box<dyn FnMut(&SpcRuleValidationResult) + Send>;
+
+/// The rules and handler of `GroupStats::set_signal_handler`, shared by clones.
+#[derive(Clone)]
+struct SignalMonitor {
+    rules: Vec<SpcRule>,
+    handler: Arc<Mutex<SignalHandler>>,
+}
+
+impl fmt::Debug for SignalMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignalMonitor")
+            .field("rules", &self.rules)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The constants of subgroups of size `n`, `custom` if it is for `n`.
+fn constants_of(custom: Option<ControlConstants>, n: usize) -> ControlConstants {
+    match custom {
+        Some(c) if c.n == n => c,
+        _ => ControlConstants::new(n),
+    }
 }
 
-#[derive(Debug)]
+/// The standards-given limits for subgroups of size `n` from a known process `sigma`:
+/// `center ± 3σ / sqrt(n)` for the Xbar charts, `d2σ` with `D4 * d2σ` and `D3 * d2σ` for
+/// the R chart, `c4σ` with `B4 * c4σ` and `B3 * c4σ` for the S chart.
+fn known_sigma_limits(
+    custom: Option<ControlConstants>,
+    chart_type: &GroupStatsChartType,
+    center: f64,
+    sigma: f64,
+    n: usize,
+) -> (f64, f64, f64) {
+    let k = constants_of(custom, n);
+    match chart_type {
+        GroupStatsChartType::RChart => {
+            let cl = k.d2 * sigma;
+            (cl, k.d4_factor * cl, k.d3_factor * cl)
+        }
+        GroupStatsChartType::SChart => {
+            let cl = k.c4 * sigma;
+            (cl, k.b4 * cl, k.b3 * cl)
+        }
+        GroupStatsChartType::XbarRChart | GroupStatsChartType::XbarSChart => {
+            let half_width = 3.0 * sigma / (n as f64).sqrt();
+            (center, center + half_width, center - half_width)
+        }
+        GroupStatsChartType::MinMaxChart => (center, center + 3.0 * sigma, center - 3.0 * sigma),
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct GroupStats {
     cl: f64,
     ucl: f64,
@@ -54,6 +168,14 @@ pub struct GroupStats {
     pub chart_type: GroupStatsChartType,
     data: Vec<Vec<f64>>,
     sub_group_size: usize,
+    sub_group_sizes: Vec<usize>,
+    weights: Vec<f64>,
+    variable_sub_group_size: bool,
+    use_pooled_std_dev: bool,
+    use_population_std_dev: bool,
+    sigma_method: Option<SigmaMethod>,
+    target_centerline: Option<f64>,
+    known_sigma: Option<f64>,
     all_data: Vec<f64>,
     ranges: Vec<f64>,
     stddev: Vec<f64>,
@@ -69,187 +191,193 @@ pub struct GroupStats {
     sigma_estimate: f64,
     minimum: Vec<f64>,
     maximum: Vec<f64>,
+    cl_data: Vec<f64>,
+    ucl_data: Vec<f64>,
+    lcl_data: Vec<f64>,
     dirty: bool,
     group_count: usize,
+    #[cfg(feature = "chrono")]
+    max_age: Option<chrono::Duration>,
+    #[cfg(feature = "chrono")]
+    timestamps: Vec<Option<DateTime<Utc>>>,
+    sigma_multiple: f64,
+    lsl: Option<f64>,
+    usl: Option<f64>,
+    frozen_limits: Option<(f64, f64, f64, f64)>,
+    custom_constants: Option<ControlConstants>,
+    signal_monitor: Option<SignalMonitor>,
+    trend_ties: TrendTies,
     rounding_ctx: Option<RoundingContext>,
 }
 
 impl GroupStats {
-    pub fn apply_rule_validation(&mut self, rules: Vec<SpcRule>) -> Vec<SpcRuleValidationResult> {
-        let mut res = vec![];
+    /// Applies `rules` to `chart_data` against the limits of the last `update`, so call
+    /// `update` after adding data.
+    ///
+    /// The min-max chart flags a subgroup under `Rule1Beyond3Sigma` if its maximum or its
+    /// minimum lies beyond `s` sigma, reporting the offending extreme (the maximum if both
+    /// do). The zone and run rules assume points scattered around the centerline, which
+    /// the maxima and minima are not, so on the min-max chart they always pass.
+    ///
+    /// Fails if the chart has no data, if data was added or settings changed since the
+    /// last `update`, or if any rule has invalid parameters, see `SpcRule::validate`.
+    pub fn apply_rule_validation(
+        &self,
+        rules: Vec<SpcRule>,
+    ) -> Result<Vec<SpcRuleValidationResult>, String> {
+        if self.data.is_empty() {
+            return Err("GroupStats: no data to apply the rules to".to_string());
+        }
+        if self.dirty {
+            return Err("GroupStats: call update before applying the rules".to_string());
+        }
+        if self.chart_type == GroupStatsChartType::MinMaxChart {
+            return self.min_max_rule_validation(rules);
+        }
         let chart_data = self.chart_data();
         let chart_average = self.chart_average();
         let sigma = self.chart_sigma();
+        validate_rules(
+            rules,
+            &chart_data,
+            chart_average,
+            sigma,
+            self.ucl,
+            self.lcl,
+            &self.rounding_ctx,
+            self.trend_ties,
+        )
+    }
+
+    /// Applies `rules` to both series of a min-max chart, see `apply_rule_validation`.
+    fn min_max_rule_validation(
+        &self,
+        rules: Vec<SpcRule>,
+    ) -> Result<Vec<SpcRuleValidationResult>, String> {
+        for rule in &rules {
+            rule.validate()?;
+        }
+        let mut res = vec![];
         for rule in rules {
             let mut bad_point_index = vec![];
-            let mut passed = true;
-            match rule {
-                SpcRule::Rule1Beyond3Sigma(p, s) => {
-                    let mut ucl = chart_average + s as f64 * sigma;
-                    let mut lcl = chart_average - s as f64 * sigma;
-                    if let Some(ctx) = &self.rounding_ctx {
-                        ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
-                        lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
-                    }
-                    for (index, &value) in chart_data.iter().enumerate() {
-                        if value > ucl || value < lcl {
-                            bad_point_index.push(index);
-                        }
-                    }
-                    if bad_point_index.len() >= p {
-                        passed = false;
-                    }
-                    if passed {
-                        bad_point_index.clear();
-                    }
-                }
-
-                SpcRule::Rule2Of3Beyond2Sigma(p, n, s) | SpcRule::Rule4Of5Beyond1Sigma(p, n, s) => {
-                    if chart_data.len() >= n {
-                        let mut ucl = chart_average + s as f64 * sigma;
-                        let mut lcl = chart_average - s as f64 * sigma;
-                        if let Some(ctx) = &self.rounding_ctx {
-                            ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
-                            lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
-                        }
-                        for i in 0..chart_data.len().saturating_sub(n - 1) {
-                            let window = &chart_data[i..i + n]; // Take n consecutive elements
-                            let count = window.iter().filter(|&&x| x > ucl || x < lcl).count();
-                            if count >= p {
-                                passed = false;
-                                for (offset, &value) in window.iter().enumerate() {
-                                    if value > ucl || value < lcl {
-                                        if !bad_point_index.contains(&(offset + i)) {
-                                            bad_point_index.push(i + offset);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                SpcRule::Rule6PointsUpOrDown(p) => {
-                    if chart_data.len() >= p {
-                        for i in 0..chart_data.len().saturating_sub(p - 1) {
-                            let window = &chart_data[i..i + p];
-                            if is_increasing(window) || is_decreasing(window) {
-                                passed = false;
-                                for j in 0..window.len() {
-                                    if !bad_point_index.contains(&(i + j)) {
-                                        bad_point_index.push(i + j);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                SpcRule::Rule8PointsAboveOrBelowCenter(p) => {
-                    let mut ucl = chart_average + sigma;
-                    let mut lcl = chart_average - sigma;
-                    if let Some(ctx) = &self.rounding_ctx {
-                        ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
-                        lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
-                    }
-                    if chart_data.len() >= p {
-                        for i in 0..chart_data.len().saturating_sub(p - 1) {
-                            let window = &chart_data[i..i + p];
-                            let count = window.iter().filter(|&&x| x > ucl || x < lcl).count();
-                            if count >= p {
-                                passed = false;
-                                for (offset, &value) in window.iter().enumerate() {
-                                    if value < ucl || value > lcl {
-                                        if !bad_point_index.contains(&(i + offset)) {
-                                            bad_point_index.push(i + offset);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                SpcRule::Rule9PointsOnSameSideOfCenter(p) => {
-                    if chart_data.len() >= p {
-                        for i in 0..chart_data.len().saturating_sub(p - 1) {
-                            let window = &chart_data[i..i + p];
-                            let flag = window
-                                .iter()
-                                .all(|&x| x < chart_average || x > chart_average);
-                            if flag {
-                                passed = false;
-                                for (offset, &value) in window.iter().enumerate() {
-                                    if !bad_point_index.contains(&(i + offset)) {
-                                        bad_point_index.push(i + offset);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                SpcRule::Rule14PointsOscillating(p) => {
-                    if chart_data.len() >= p {
-                        for i in 0..chart_data.len().saturating_sub(p - 1) {
-                            let window = &chart_data[i..i + p];
-                            if is_alternating(window) {
-                                passed = false;
-                                for j in 0..window.len() {
-                                    if !bad_point_index.contains(&(i + j)) {
-                                        bad_point_index.push(i + j);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                SpcRule::Rule15PointsWithin1Sigma(p, s) => {
-                    if chart_data.len() >= p {
-                        let mut ucl = chart_average + s as f64 * sigma;
-                        let mut lcl = chart_average - s as f64 * sigma;
-                        if let Some(ctx) = &self.rounding_ctx {
-                            ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
-                            lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
-                        }
-                        for i in 0..chart_data.len().saturating_sub(p - 1) {
-                            let window = &chart_data[i..i + p];
-                            let count = window.iter().filter(|&&x| x < ucl || x > lcl).count();
-                            if count >= p {
-                                passed = false;
-                                for (offset, &value) in window.iter().enumerate() {
-                                    if value < ucl || value > lcl {
-                                        if !bad_point_index.contains(&(i + offset)) {
-                                            bad_point_index.push(i + offset);
-                                        }
-                                    }
-                                }
-                            }
-                        }
+            let mut bad_point_data = vec![];
+            if let SpcRule::Rule1Beyond3Sigma(p, s) = rule {
+                let (upper, lower) = self.limits_at_sigma(s as f64);
+                for (i, (&max, &min)) in self.maximum.iter().zip(&self.minimum).enumerate() {
+                    if max > upper || max < lower {
+                        bad_point_index.push(i);
+                        bad_point_data.push(max);
+                    } else if min > upper || min < lower {
+                        bad_point_index.push(i);
+                        bad_point_data.push(min);
                     }
                 }
-            }
-
-            let mut bad_point_data = vec![];
-            for i in 0..chart_data.len() {
-                if bad_point_index.contains(&i) {
-                    bad_point_data.push(chart_data[i]);
+                if bad_point_index.len() < p {
+                    bad_point_index.clear();
+                    bad_point_data.clear();
                 }
             }
             res.push(SpcRuleValidationResult {
                 rule,
+                validation_passed: bad_point_index.is_empty(),
                 bad_point_index,
                 bad_point_data,
-                validation_passed: passed,
             });
         }
-        res
+        Ok(res)
     }
-}
 
+    /// Applies `rules` and returns the indices of the plotted points violating any of them.
+    pub fn out_of_control_points(&self, rules: Vec<SpcRule>) -> Result<BTreeSet<usize>, String> {
+        Ok(self
+            .apply_rule_validation(rules)?
+            .into_iter()
+            .flat_map(|res| res.bad_point_index)
+            .collect())
+    }
+
+    /// Estimates the in-control average run length (ARL) of `rules`, the mean number of
+    /// points between false alarms of an in-control process, e.g. about 370 for
+    /// `Rule1Beyond3Sigma(1, 3)` alone.
+    ///
+    /// The estimate is simulated: standard normal points from a fixed seed are plotted on
+    /// a chart with limits at `±sigma_multiple` until 1000 alarms or 2,000,000 points, and
+    /// the ARL is the number of points per alarm. Each new point is judged on a trailing
+    /// window as long as the longest rule, so `Rule1Beyond3Sigma(p, s)` with `p > 1` counts
+    /// the points beyond `s` sigma in that window. Returns `f64::INFINITY` if no alarm is
+    /// raised and `f64::NAN` if any rule has invalid parameters.
+    #[cfg(feature = "rand")]
+    pub fn in_control_arl(&self, rules: &[SpcRule]) -> f64 {
+        use rand::{Rng, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+
+        const MAX_ALARMS: usize = 1000;
+        const MAX_POINTS: usize = 2_000_000;
+        if rules.iter().any(|rule| rule.validate().is_err()) {
+            return f64::NAN;
+        }
+        let window = rules
+            .iter()
+            .map(|rule| match *rule {
+                SpcRule::Rule1Beyond3Sigma(p, _) => p,
+                SpcRule::Rule2Of3Beyond2Sigma(_, n, _)
+                | SpcRule::Rule4Of5Beyond1Sigma(_, n, _)
+                | SpcRule::RuleNPointsNearCenter(_, n) => n,
+                SpcRule::Rule6PointsUpOrDown(n)
+                | SpcRule::Rule8PointsAboveOrBelowCenter(n)
+                | SpcRule::Rule9PointsOnSameSideOfCenter(n)
+                | SpcRule::Rule14PointsOscillating(n)
+                | SpcRule::Rule15PointsWithin1Sigma(n, _)
+                | SpcRule::RuleNPointsTrendingTowardLimit(n)
+                | SpcRule::RuleNIdenticalPoints(n) => n + 1,
+            })
+            .max()
+            .unwrap_or(0);
+        if window == 0 {
+            return f64::INFINITY;
+        }
+        let limit = self.sigma_multiple;
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut points = Vec::with_capacity(window);
+        let (mut total, mut alarms) = (0, 0);
+        while alarms < MAX_ALARMS && total < MAX_POINTS {
+            if points.len() == window {
+                points.remove(0);
+            }
+            points.push(normal_inverse_cdf(rng.gen_range(0.0..1.0)));
+            total += 1;
+            let alarm = validate_rules(
+                rules.to_vec(),
+                &points,
+                0.0,
+                1.0,
+                limit,
+                -limit,
+                &None,
+                self.trend_ties,
+            )
+            .unwrap()
+            .iter()
+            .any(|res| !res.validation_passed);
+            if alarm {
+                alarms += 1;
+                points.clear();
+            }
+        }
+        if alarms == 0 {
+            f64::INFINITY
+        } else {
+            total as f64 / alarms as f64
+        }
+    }
+}
 
 impl GroupStats {
     pub fn new(
         sub_group_size: usize,
         chart_type: GroupStatsChartType,
     ) -> Result<GroupStats, String> {
-        if sub_group_size < 2 || sub_group_size > 25 {
+        if !(2..=25).contains(&sub_group_size) {
             return Err("GroupStats: sub_group_size must be in range 2..25".to_string());
         }
         Ok(Self {
@@ -259,6 +387,14 @@ impl GroupStats {
             chart_type,
             data: vec![],
             sub_group_size,
+            sub_group_sizes: vec![],
+            weights: vec![],
+            variable_sub_group_size: false,
+            use_pooled_std_dev: false,
+            use_population_std_dev: false,
+            sigma_method: None,
+            target_centerline: None,
+            known_sigma: None,
             all_data: vec![],
             ranges: vec![],
             stddev: vec![],
@@ -274,14 +410,119 @@ impl GroupStats {
             sigma_estimate: 0.0,
             minimum: vec![],
             maximum: vec![],
+            cl_data: vec![],
+            ucl_data: vec![],
+            lcl_data: vec![],
             dirty: true,
             group_count: 100,
+            #[cfg(feature = "chrono")]
+            max_age: None,
+            #[cfg(feature = "chrono")]
+            timestamps: vec![],
+            sigma_multiple: 3.0,
+            lsl: None,
+            usl: None,
+            frozen_limits: None,
+            custom_constants: None,
+            signal_monitor: None,
+            trend_ties: TrendTies::Break,
             rounding_ctx: None,
         })
     }
 
+    /// Builds an updated chart from all subgroups at once, taking `sub_group_size` from the
+    /// first subgroup.
+    ///
+    /// `group_count` is raised to the number of subgroups so none are evicted. A subgroup
+    /// whose size differs from the first one is rejected with an error naming its index.
+    pub fn from_subgroups(
+        data: Vec<Vec<f64>>,
+        chart_type: GroupStatsChartType,
+    ) -> Result<GroupStats, String> {
+        let first = data
+            .first()
+            .ok_or_else(|| "GroupStats: no subgroups given".to_string())?;
+        let mut stats = GroupStats::new(first.len(), chart_type)?;
+        stats.set_group_count(stats.group_count.max(data.len()));
+        stats.extend_from_subgroups(data)?;
+        stats.update();
+        Ok(stats)
+    }
+
+    /// Builds an updated chart from a stream of individual measurements, taking each
+    /// `sub_group_size` consecutive values as a subgroup.
+    ///
+    /// A trailing partial subgroup is dropped if `drop_partial` is set, otherwise a length
+    /// that is not a multiple of `sub_group_size` is an error. See `from_subgroups`.
+    pub fn from_individuals(
+        data: &[f64],
+        sub_group_size: usize,
+        chart_type: GroupStatsChartType,
+        drop_partial: bool,
+    ) -> Result<GroupStats, String> {
+        if sub_group_size == 0 {
+            return Err("GroupStats: sub_group_size must be in range 2..25".to_string());
+        }
+        let remainder = data.len() % sub_group_size;
+        if remainder != 0 && !drop_partial {
+            return Err(format!(
+                "GroupStats: {} values leave a partial subgroup of {}",
+                data.len(),
+                remainder
+            ));
+        }
+        let subgroups = data
+            .chunks_exact(sub_group_size)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        GroupStats::from_subgroups(subgroups, chart_type)
+    }
+
     pub fn add_data(&mut self, group_data: &[f64]) -> Result<(), String> {
-        if group_data.len() != self.sub_group_size {
+        self.add_data_weighted(group_data, 1.0)
+    }
+
+    /// Adds a subgroup with a weight, e.g. the production volume it represents, 1 for
+    /// `add_data`.
+    ///
+    /// Unless all weights are equal, `average_average` and `range_average`, and the
+    /// centerlines and limits built on them, are weighted means of the subgroup averages
+    /// and ranges (for variable subgroup sizes the grand average weighs `n_i * w_i`). The
+    /// subgroup statistics themselves, `stddev_average` and `all_average` stay unweighted.
+    pub fn add_data_weighted(&mut self, group_data: &[f64], weight: f64) -> Result<(), String> {
+        self.push_subgroup(group_data, weight)?;
+        self.evict();
+        self.signal_newest();
+        Ok(())
+    }
+
+    /// Adds a subgroup measured at `timestamp`, which a `RetentionPolicy::Duration` ages
+    /// out. Timestamps should not decrease from one subgroup to the next.
+    #[cfg(feature = "chrono")]
+    pub fn add_data_at(
+        &mut self,
+        group_data: &[f64],
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), String> {
+        self.push_subgroup(group_data, 1.0)?;
+        *self.timestamps.last_mut().unwrap() = Some(timestamp);
+        self.evict();
+        self.signal_newest();
+        Ok(())
+    }
+
+    fn push_subgroup(&mut self, group_data: &[f64], weight: f64) -> Result<(), String> {
+        if !(weight > 0.0 && weight.is_finite()) {
+            return Err("GroupStats: weight must be positive".to_string());
+        }
+        if self.variable_sub_group_size {
+            if !(2..=25).contains(&group_data.len()) {
+                return Err(format!(
+                    "GroupStats: Trying to add groupData with size {} out of range 2..25",
+                    group_data.len()
+                ));
+            }
+        } else if group_data.len() != self.sub_group_size {
             return Err(format!(
                 "GroupStats: Trying to add groupData with size {} not equal to sub_group_size {}",
                 group_data.len(),
@@ -289,9 +530,13 @@ impl GroupStats {
             ));
         }
         self.data.push(group_data.to_vec());
+        self.sub_group_sizes.push(group_data.len());
+        self.weights.push(weight);
+        #[cfg(feature = "chrono")]
+        self.timestamps.push(None);
         self.all_data.extend_from_slice(group_data);
         let mut range = group_data.range();
-        let mut stddev = group_data.std_dev();
+        let mut stddev = self.subgroup_std_dev(group_data);
         let mut average = group_data.average();
         let mut minimum = group_data.min();
         let mut maximum = group_data.max();
@@ -308,58 +553,208 @@ impl GroupStats {
         self.minimum.push(minimum);
         self.maximum.push(maximum);
         self.dirty = true;
-        if self.data.len() > self.group_count {
+        Ok(())
+    }
+
+    /// Drops the subgroups the retention policy no longer keeps.
+    fn evict(&mut self) {
+        #[cfg(feature = "chrono")]
+        if let Some(max_age) = self.max_age {
+            if let Some(newest) = self.timestamps.iter().flatten().max() {
+                let cutoff = *newest - max_age;
+                let keep: Vec<bool> = self
+                    .timestamps
+                    .iter()
+                    .map(|t| t.is_none_or(|t| t >= cutoff))
+                    .collect();
+                self.retain_flagged(&keep);
+            }
+            return;
+        }
+        while self.data.len() > self.group_count {
             self.data.remove(0);
             self.ranges.remove(0);
             self.stddev.remove(0);
             self.average.remove(0);
             self.minimum.remove(0);
             self.maximum.remove(0);
-            if self.sub_group_size > 0 {
-                self.all_data.drain(0..self.sub_group_size);
+            self.weights.remove(0);
+            #[cfg(feature = "chrono")]
+            self.timestamps.remove(0);
+            let evicted_size = self.sub_group_sizes.remove(0);
+            self.all_data.drain(0..evicted_size);
+        }
+    }
+
+    /// Calls `handler` with each result of `rules` that the newest subgroup violates, every
+    /// time a subgroup is added.
+    ///
+    /// Each `add_data` then runs `update` and applies the rules to the whole chart, so an
+    /// insert costs as much as an `update` plus `apply_rule_validation` (linear in the
+    /// stored subgroups) instead of just storing the subgroup. Clones share the handler.
+    /// Fails, leaving any previous handler in place, if a rule has invalid parameters.
+    pub fn set_signal_handler(
+        &mut self,
+        rules: Vec<SpcRule>,
+        handler: SignalHandler,
+    ) -> Result<(), String> {
+        for rule in &rules {
+            rule.validate()?;
+        }
+        self.signal_monitor = Some(SignalMonitor {
+            rules,
+            handler: Arc::new(Mutex::new(handler)),
+        });
+        Ok(())
+    }
+
+    /// Removes the handler of `set_signal_handler`.
+    pub fn clear_signal_handler(&mut self) {
+        self.signal_monitor = None;
+    }
+
+    fn signal_newest(&mut self) {
+        let Some(monitor) = self.signal_monitor.clone() else {
+            return;
+        };
+        self.update();
+        let newest = self.data.len() - 1;
+        let Ok(results) = self.apply_rule_validation(monitor.rules) else {
+            return;
+        };
+        let Ok(mut handler) = monitor.handler.lock() else {
+            return;
+        };
+        for res in results
+            .iter()
+            .filter(|res| res.bad_point_index.contains(&newest))
+        {
+            handler(res);
+        }
+    }
+
+    /// Adds each subgroup with `add_data`, stopping at the first one that is rejected.
+    ///
+    /// The error names the zero-based position of the failing subgroup within `iter`;
+    /// the subgroups before it stay added.
+    pub fn extend_from_subgroups<I: IntoIterator<Item = Vec<f64>>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), String> {
+        for (index, group_data) in iter.into_iter().enumerate() {
+            self.add_data(&group_data)
+                .map_err(|e| format!("subgroup {}: {}", index, e))?;
+        }
+        Ok(())
+    }
+
+    /// Appends the subgroups of `other`, e.g. a parallel machine, after those of this chart.
+    ///
+    /// Both charts must have the same chart type and subgroup size. Old subgroups are
+    /// evicted beyond this chart's `group_count`, and on error this chart is left unchanged.
+    pub fn merge(&mut self, other: &GroupStats) -> Result<(), String> {
+        if self.chart_type != other.chart_type {
+            return Err(format!(
+                "GroupStats: cannot merge {:?} into {:?}",
+                other.chart_type, self.chart_type
+            ));
+        }
+        if self.sub_group_size != other.sub_group_size {
+            return Err(format!(
+                "GroupStats: cannot merge sub_group_size {} into sub_group_size {}",
+                other.sub_group_size, self.sub_group_size
+            ));
+        }
+        let mut merged = self.clone();
+        for (i, group_data) in other.data.iter().enumerate() {
+            merged.push_subgroup(group_data, other.weights[i])?;
+            #[cfg(feature = "chrono")]
+            {
+                *merged.timestamps.last_mut().unwrap() = other.timestamps[i];
             }
+            merged.evict();
+            merged.signal_newest();
         }
+        *self = merged;
         Ok(())
     }
 
+    /// Keeps only the subgroups whose summary satisfies `pred`, e.g. drops the subgroups
+    /// with gross gauge errors before computing Phase I limits.
+    pub fn retain_subgroups<F: Fn(&SubgroupSummary) -> bool>(&mut self, pred: F) {
+        let keep: Vec<bool> = self.subgroup_summaries().iter().map(pred).collect();
+        self.retain_flagged(&keep);
+    }
+
+    /// The average, range, standard deviation, minimum and maximum of each stored
+    /// subgroup, one row per subgroup, oldest first.
+    pub fn subgroup_summaries(&self) -> Vec<SubgroupSummary> {
+        (0..self.data.len())
+            .map(|index| SubgroupSummary {
+                index,
+                average: self.average[index],
+                range: self.ranges[index],
+                stddev: self.stddev[index],
+                min: self.minimum[index],
+                max: self.maximum[index],
+            })
+            .collect()
+    }
+
+    /// Keeps the subgroups whose `keep` flag is set.
+    fn retain_flagged(&mut self, keep: &[bool]) {
+        if keep.iter().all(|&k| k) {
+            return;
+        }
+        let mut flags = keep.iter();
+        self.data.retain(|_| *flags.next().unwrap());
+        #[cfg(feature = "chrono")]
+        {
+            let mut flags = keep.iter();
+            self.timestamps.retain(|_| *flags.next().unwrap());
+        }
+        for buffer in [
+            &mut self.ranges,
+            &mut self.stddev,
+            &mut self.average,
+            &mut self.minimum,
+            &mut self.maximum,
+            &mut self.weights,
+        ] {
+            let mut flags = keep.iter();
+            buffer.retain(|_| *flags.next().unwrap());
+        }
+        self.sub_group_sizes = self.data.iter().map(|g| g.len()).collect();
+        self.all_data = self.data.concat();
+        self.dirty = true;
+    }
+
     pub fn update(&mut self) {
         if !self.dirty {
             return;
         }
-        self.range_average = self.ranges.average();
+        self.range_average = self.weighted_mean(&self.ranges);
         self.range_stddev = self.ranges.std_dev();
         self.stddev_average = self.stddev.average();
         self.stddev_stddev = self.stddev.std_dev();
-        self.average_average = self.average.average();
+        self.average_average = self.weighted_mean(&self.average);
         self.average_stddev = self.average.std_dev();
         self.all_average = self.all_data.average();
         self.all_stddev = self.all_data.std_dev();
 
-        match self.chart_type {
-            GroupStatsChartType::RChart => {
-                self.cl = self.range_average;
-                self.ucl = D4[self.sub_group_size] * self.range_average;
-                self.lcl = D3[self.sub_group_size] * self.range_average;
-                self.sigma_estimate = self.range_average / d2[self.sub_group_size];
-            }
-            GroupStatsChartType::XbarRChart => {
-                self.cl = self.average_average;
-                self.ucl = self.average_average + A2[self.sub_group_size] * self.range_average;
-                self.lcl = self.average_average - A2[self.sub_group_size] * self.range_average;
-                self.sigma_estimate = self.range_average / d2[self.sub_group_size];
-            }
-            GroupStatsChartType::SChart => {
-                self.cl = self.stddev_average;
-                self.ucl = B4[self.sub_group_size] * self.stddev_average;
-                self.lcl = B3[self.sub_group_size] * self.stddev_average;
-                self.sigma_estimate = self.stddev_average / c4[self.sub_group_size];
-            }
-            GroupStatsChartType::XbarSChart => {
-                self.cl = self.average_average;
-                self.ucl = self.average_average + A3[self.sub_group_size] * self.stddev_average;
-                self.lcl = self.average_average - A3[self.sub_group_size] * self.stddev_average;
-                self.sigma_estimate = self.stddev_average / c4[self.sub_group_size];
-            }
+        if self.variable_sub_group_size {
+            self.update_variable_limits();
+        } else {
+            self.update_fixed_limits();
+        }
+        if let Some(method) = self.sigma_method {
+            self.sigma_estimate = self.estimate_sigma(method);
+        }
+        if let Some(sigma) = self.known_sigma {
+            self.apply_known_sigma(sigma);
+        }
+        if self.sigma_multiple != 3.0 {
+            self.scale_limits();
         }
         match &self.rounding_ctx {
             None => {}
@@ -377,179 +772,2803 @@ impl GroupStats {
                 self.ucl = self.ucl.scale(ctx.scale, &ctx.rounding_mode);
                 self.lcl = self.lcl.scale(ctx.scale, &ctx.rounding_mode);
                 self.sigma_estimate = self.sigma_estimate.scale(ctx.scale, &ctx.rounding_mode);
+                for v in self
+                    .cl_data
+                    .iter_mut()
+                    .chain(self.ucl_data.iter_mut())
+                    .chain(self.lcl_data.iter_mut())
+                {
+                    *v = v.scale(ctx.scale, &ctx.rounding_mode);
+                }
             }
         }
+        if let Some((cl, ucl, lcl, sigma_estimate)) = self.frozen_limits {
+            (self.cl, self.ucl, self.lcl, self.sigma_estimate) = (cl, ucl, lcl, sigma_estimate);
+            let count = self.data.len();
+            self.cl_data = vec![cl; count];
+            self.ucl_data = vec![ucl; count];
+            self.lcl_data = vec![lcl; count];
+        }
         self.dirty = false;
     }
 
-    pub fn lcl(&self) -> f64 {
-        self.lcl
+    fn update_fixed_limits(&mut self) {
+        let k = self.constants(self.sub_group_size);
+        match self.chart_type {
+            GroupStatsChartType::RChart => {
+                self.cl = self.range_average;
+                self.ucl = k.d4_factor * self.range_average;
+                self.lcl = k.d3_factor * self.range_average;
+                self.sigma_estimate = self.range_average / k.d2;
+            }
+            GroupStatsChartType::XbarRChart => {
+                let center = self.target_centerline.unwrap_or(self.average_average);
+                self.cl = center;
+                self.ucl = center + k.a2 * self.range_average;
+                self.lcl = center - k.a2 * self.range_average;
+                self.sigma_estimate = self.range_average / k.d2;
+            }
+            GroupStatsChartType::SChart => {
+                let s_bar = self.s_bar();
+                self.cl = s_bar;
+                self.ucl = k.b4 * s_bar;
+                self.lcl = k.b3 * s_bar;
+                self.sigma_estimate = s_bar / k.c4;
+            }
+            GroupStatsChartType::XbarSChart => {
+                let s_bar = self.s_bar();
+                let center = self.target_centerline.unwrap_or(self.average_average);
+                self.cl = center;
+                self.ucl = center + k.a3 * s_bar;
+                self.lcl = center - k.a3 * s_bar;
+                self.sigma_estimate = s_bar / k.c4;
+            }
+            GroupStatsChartType::MinMaxChart => {
+                let center = self.target_centerline.unwrap_or(self.average_average);
+                self.cl = center;
+                self.sigma_estimate = self.range_average / k.d2;
+                self.ucl = center + 3.0 * self.sigma_estimate;
+                self.lcl = center - 3.0 * self.sigma_estimate;
+            }
+        }
+        let count = self.data.len();
+        self.cl_data = vec![self.cl; count];
+        self.ucl_data = vec![self.ucl; count];
+        self.lcl_data = vec![self.lcl; count];
     }
 
-    pub fn ucl(&self) -> f64 {
-        self.ucl
+    /// Rescales the 3σ limits to `sigma_multiple`σ, moving each limit `sigma_multiple / 3`
+    /// of its distance from the centerline.
+    ///
+    /// For the R and S charts a lower limit that the constants clamp to zero is rescaled
+    /// from the upper distance instead (the limits are symmetric before clamping), and
+    /// the result is clamped to zero again.
+    fn scale_limits(&mut self) {
+        let factor = self.sigma_multiple / 3.0;
+        let dispersion = matches!(
+            self.chart_type,
+            GroupStatsChartType::RChart | GroupStatsChartType::SChart
+        );
+        let scale = |cl: f64, ucl: f64, lcl: f64| {
+            if dispersion {
+                scale_dispersion_limits(cl, ucl, lcl, factor)
+            } else {
+                (cl + factor * (ucl - cl), cl - factor * (cl - lcl))
+            }
+        };
+        (self.ucl, self.lcl) = scale(self.cl, self.ucl, self.lcl);
+        for i in 0..self.cl_data.len() {
+            (self.ucl_data[i], self.lcl_data[i]) =
+                scale(self.cl_data[i], self.ucl_data[i], self.lcl_data[i]);
+        }
     }
 
-    pub fn cl(&self) -> f64 {
-        self.cl
+    /// Replaces the estimated limits with the standards-given ones of `sigma`, per point
+    /// for variable subgroup sizes.
+    fn apply_known_sigma(&mut self, sigma: f64) {
+        let center = self.target_centerline.unwrap_or(self.average_average);
+        let custom = self.custom_constants;
+        self.sigma_estimate = sigma;
+        (self.cl, self.ucl, self.lcl) =
+            known_sigma_limits(custom, &self.chart_type, center, sigma, self.limit_size());
+        for (i, &n) in self.sub_group_sizes.iter().enumerate() {
+            (self.cl_data[i], self.ucl_data[i], self.lcl_data[i]) =
+                known_sigma_limits(custom, &self.chart_type, center, sigma, n);
+        }
     }
 
-    pub fn data(&self) -> Vec<Vec<f64>> {
-        self.data.to_vec()
+    /// The mean of a per-subgroup statistic, weighted unless all weights are equal.
+    fn weighted_mean(&self, values: &[f64]) -> f64 {
+        if self.weights.windows(2).all(|w| w[0] == w[1]) {
+            values.average()
+        } else {
+            values.weighted_average(&self.weights)
+        }
     }
 
-    pub fn chart_data(&mut self) -> Vec<f64> {
-        match self.chart_type {
-            GroupStatsChartType::RChart => self.ranges.to_vec(),
-            GroupStatsChartType::XbarRChart => self.average.to_vec(),
-            GroupStatsChartType::SChart => self.stddev.to_vec(),
-            GroupStatsChartType::XbarSChart => self.average.to_vec(),
+    /// The center of the S chart, `c4 * pooled_std_dev` when the pooled estimate is used.
+    fn s_bar(&self) -> f64 {
+        if self.use_pooled_std_dev {
+            self.constants(self.sub_group_size).c4 * self.pooled_std_dev()
+        } else {
+            self.stddev_average
         }
     }
 
-    pub fn chart_average(&mut self) -> f64 {
-        match self.chart_type {
-            GroupStatsChartType::RChart => self.range_average,
-            GroupStatsChartType::XbarRChart => self.average_average,
-            GroupStatsChartType::SChart => self.stddev_average,
-            GroupStatsChartType::XbarSChart => self.average_average,
+    /// Computes the limits for subgroups of differing sizes.
+    ///
+    /// The grand average is weighted by subgroup size and sigma is estimated as the
+    /// mean of the per-subgroup unbiased estimates (`R_i / d2(n_i)` or `s_i / c4(n_i)`).
+    /// Per-point limits use the constants of each subgroup's own size, while the scalar
+    /// `cl`/`ucl`/`lcl` use the constants of the rounded average subgroup size.
+    fn update_variable_limits(&mut self) {
+        let sizes = &self.sub_group_sizes;
+        let total: usize = sizes.iter().sum();
+        self.average_average = self
+            .average
+            .iter()
+            .zip(sizes)
+            .zip(&self.weights)
+            .map(|((&x, &n), &w)| x * n as f64 * w)
+            .sum::<f64>()
+            / sizes
+                .iter()
+                .zip(&self.weights)
+                .map(|(&n, &w)| n as f64 * w)
+                .sum::<f64>();
+        let average_size = (total as f64 / sizes.len() as f64).round() as usize;
+
+        let custom = self.custom_constants;
+        let limits = |chart_type: &GroupStatsChartType, center: f64, sigma: f64, n: usize| {
+            let k = constants_of(custom, n);
+            match chart_type {
+                GroupStatsChartType::RChart => {
+                    let cl = k.d2 * sigma;
+                    (cl, k.d4_factor * cl, k.d3_factor * cl)
+                }
+                GroupStatsChartType::XbarRChart => {
+                    let half_width = k.a2 * k.d2 * sigma;
+                    (center, center + half_width, center - half_width)
+                }
+                GroupStatsChartType::SChart => {
+                    let cl = k.c4 * sigma;
+                    (cl, k.b4 * cl, k.b3 * cl)
+                }
+                GroupStatsChartType::XbarSChart => {
+                    let half_width = k.a3 * k.c4 * sigma;
+                    (center, center + half_width, center - half_width)
+                }
+                GroupStatsChartType::MinMaxChart => {
+                    (center, center + 3.0 * sigma, center - 3.0 * sigma)
+                }
+            }
+        };
+
+        self.sigma_estimate = match self.chart_type {
+            GroupStatsChartType::RChart
+            | GroupStatsChartType::XbarRChart
+            | GroupStatsChartType::MinMaxChart => {
+                let unbiased: Vec<f64> = self
+                    .ranges
+                    .iter()
+                    .zip(sizes)
+                    .map(|(&r, &n)| r / constants_of(custom, n).d2)
+                    .collect();
+                unbiased.average()
+            }
+            GroupStatsChartType::SChart | GroupStatsChartType::XbarSChart
+                if self.use_pooled_std_dev =>
+            {
+                self.pooled_std_dev()
+            }
+            GroupStatsChartType::SChart | GroupStatsChartType::XbarSChart => {
+                let unbiased: Vec<f64> = self
+                    .stddev
+                    .iter()
+                    .zip(sizes)
+                    .map(|(&s, &n)| s / constants_of(custom, n).c4)
+                    .collect();
+                unbiased.average()
+            }
+        };
+
+        let center = self.target_centerline.unwrap_or(self.average_average);
+        let sigma = self.sigma_estimate;
+        (self.cl, self.ucl, self.lcl) = limits(&self.chart_type, center, sigma, average_size);
+        self.cl_data.clear();
+        self.ucl_data.clear();
+        self.lcl_data.clear();
+        for &n in sizes {
+            let (cl, ucl, lcl) = limits(&self.chart_type, center, sigma, n);
+            self.cl_data.push(cl);
+            self.ucl_data.push(ucl);
+            self.lcl_data.push(lcl);
         }
     }
 
-    pub fn chart_sigma(&mut self) -> f64 {
-        (self.ucl - self.chart_average()) / 3.0
+    /// Computes `cl`, `ucl`, `lcl` and `sigma_estimate` from the subgroups in
+    /// `[start, end)` only and freezes them, the Phase-I to Phase-II handoff.
+    ///
+    /// The window is evaluated with this chart's type, sigma multiple and rounding. The
+    /// chart data and rule validation still cover every subgroup, including those added
+    /// later. An empty window or one past the current subgroups is rejected.
+    pub fn compute_limits_from_range(&mut self, start: usize, end: usize) -> Result<(), String> {
+        if start >= end || end > self.data.len() {
+            return Err(format!(
+                "GroupStats: limit window {}..{} out of range 0..{}",
+                start,
+                end,
+                self.data.len()
+            ));
+        }
+        let mut window = self.clone();
+        window.frozen_limits = None;
+        window.signal_monitor = None;
+        window.data.clear();
+        window.sub_group_sizes.clear();
+        window.all_data.clear();
+        window.ranges.clear();
+        window.stddev.clear();
+        window.average.clear();
+        window.minimum.clear();
+        window.maximum.clear();
+        window.weights.clear();
+        #[cfg(feature = "chrono")]
+        window.timestamps.clear();
+        for (group_data, &weight) in self.data[start..end].iter().zip(&self.weights[start..end]) {
+            window.add_data_weighted(group_data, weight)?;
+        }
+        window.update();
+        self.frozen_limits = Some((window.cl, window.ucl, window.lcl, window.sigma_estimate));
+        self.dirty = true;
+        Ok(())
     }
 
-    pub fn sub_group_size(&self) -> usize {
-        self.sub_group_size
+    /// Updates the chart and freezes its current `cl`, `ucl`, `lcl` and `sigma_estimate`, so
+    /// that subgroups added later are judged against them.
+    pub fn freeze_limits(&mut self) {
+        self.update();
+        self.frozen_limits = Some((self.cl, self.ucl, self.lcl, self.sigma_estimate));
     }
 
-    pub fn ranges(&self) -> Vec<f64> {
-        self.ranges.to_vec()
+    /// Releases frozen limits, the next update recomputes them from all subgroups.
+    pub fn unfreeze_limits(&mut self) {
+        self.frozen_limits = None;
+        self.dirty = true;
     }
 
-    pub fn stddev(&self) -> Vec<f64> {
-        self.stddev.to_vec()
+    pub fn limits_frozen(&self) -> bool {
+        self.frozen_limits.is_some()
     }
 
-    pub fn average(&self) -> Vec<f64> {
-        self.average.to_vec()
+    pub fn lcl(&self) -> f64 {
+        self.lcl
     }
 
-    pub fn range_average(&self) -> f64 {
-        self.range_average
+    pub fn ucl(&self) -> f64 {
+        self.ucl
     }
 
-    pub fn range_stddev(&self) -> f64 {
-        self.range_stddev
+    pub fn cl(&self) -> f64 {
+        self.cl
     }
 
-    pub fn stddev_average(&self) -> f64 {
-        self.stddev_average
+    /// Updates the chart and returns, for each `chart_data` point, whether it lies within
+    /// its (per-subgroup) control limits.
+    pub fn control_status(&mut self) -> Vec<bool> {
+        self.update();
+        let chart_data = self.chart_data();
+        chart_data
+            .iter()
+            .zip(self.lcl_data.iter().zip(&self.ucl_data))
+            .map(|(&x, (&lcl, &ucl))| !(x > ucl || x < lcl))
+            .collect()
     }
 
-    pub fn stddev_stddev(&self) -> f64 {
-        self.stddev_stddev
+    /// Updates the chart and returns its centerline, limits and zone boundaries.
+    pub fn control_limits(&mut self) -> ControlLimits {
+        self.update();
+        let sigma = self.chart_sigma();
+        ControlLimits::new(self.cl, self.ucl, self.lcl, sigma, &self.rounding_ctx)
     }
 
-    pub fn average_average(&self) -> f64 {
-        self.average_average
+    /// Updates the chart and returns it in the shape shared by all chart families.
+    pub fn to_spec(&mut self) -> ControlChartSpec {
+        ControlChartSpec {
+            family: ChartFamily::Group,
+            chart_type: self.chart_type.to_string(),
+            limits: self.control_limits(),
+            data: self.chart_data(),
+            violations: None,
+        }
     }
 
-    pub fn average_stddev(&self) -> f64 {
-        self.average_stddev
+    /// Updates the chart and returns the 2σ upper warning limit `chart_average + 2 * chart_sigma`.
+    pub fn upper_warning_limit(&mut self) -> f64 {
+        self.control_limits().upper_2s
     }
 
-    pub fn all_average(&self) -> f64 {
-        self.all_average
+    /// Updates the chart and returns the 2σ lower warning limit `chart_average - 2 * chart_sigma`.
+    pub fn lower_warning_limit(&mut self) -> f64 {
+        self.control_limits().lower_2s
     }
 
-    pub fn all_stddev(&self) -> f64 {
-        self.all_stddev
+    /// The limits `k` sigma from the centerline, `chart_average ± k * chart_sigma`, as
+    /// `(upper, lower)`, e.g. to redraw a 2σ/3σ view without `set_sigma_multiple`. The R
+    /// and S charts scale their limits like `set_sigma_multiple`, so the lower limit never
+    /// drops below zero.
+    ///
+    /// Uses the limits of the last `update` and leaves the chart untouched.
+    pub fn limits_at_sigma(&self, k: f64) -> (f64, f64) {
+        let center = self.chart_average();
+        let sigma = self.chart_sigma();
+        let (upper, lower) = match self.chart_type {
+            GroupStatsChartType::RChart | GroupStatsChartType::SChart => {
+                scale_dispersion_limits(center, self.ucl, self.lcl, k / self.sigma_multiple)
+            }
+            _ => (center + k * sigma, center - k * sigma),
+        };
+        match &self.rounding_ctx {
+            None => (upper, lower),
+            Some(ctx) => (
+                upper.scale(ctx.scale, &ctx.rounding_mode),
+                lower.scale(ctx.scale, &ctx.rounding_mode),
+            ),
+        }
     }
 
-    pub fn sigma_estimate(&self) -> f64 {
-        self.sigma_estimate
+    /// Process capability `(USL - LSL) / 6σ` from the within-subgroup `sigma_estimate`.
+    ///
+    /// Returns `None` unless both spec limits are set, or if `sigma_estimate` is zero
+    /// (e.g. all measurements identical) or undefined.
+    pub fn cp(&mut self) -> Option<f64> {
+        self.update();
+        let sigma = capability_sigma(self.sigma_estimate, self.average_average).ok()?;
+        Some((self.usl? - self.lsl?) / (6.0 * sigma))
     }
 
-    pub fn minimum(&self) -> Vec<f64> {
-        self.minimum.to_vec()
+    /// Process capability index `min(USL - x̄, x̄ - LSL) / 3σ` from the within-subgroup
+    /// `sigma_estimate`, using only the spec limits that are set.
+    ///
+    /// Returns `None` without spec limits, or if `sigma_estimate` is zero (e.g. all
+    /// measurements identical) or undefined.
+    pub fn cpk(&mut self) -> Option<f64> {
+        self.update();
+        let sigma = capability_sigma(self.sigma_estimate, self.average_average).ok()?;
+        self.capability_index(self.average_average, sigma)
     }
 
-    pub fn maximum(&self) -> Vec<f64> {
-        self.maximum.to_vec()
+    /// Approximate two-sided `1 - alpha` confidence interval for `cpk` by Bissell's normal
+    /// approximation, `Cpk ± z(1 - alpha / 2) * sqrt(1 / (9n) + Cpk² / (2(n - 1)))` with `n`
+    /// the number of measurements. The interval narrows as subgroups are added.
+    ///
+    /// Returns `None` when `cpk` does, with fewer than 2 measurements or if `alpha` is not
+    /// in `(0, 1)`.
+    pub fn cpk_confidence_interval(&mut self, alpha: f64) -> Option<(f64, f64)> {
+        let n = self.all_data.len() as f64;
+        if n < 2.0 || !(alpha > 0.0 && alpha < 1.0) {
+            return None;
+        }
+        let cpk = self.cpk()?;
+        let z = normal_inverse_cdf(1.0 - alpha / 2.0);
+        let half_width = z * (1.0 / (9.0 * n) + cpk * cpk / (2.0 * (n - 1.0))).sqrt();
+        Some((cpk - half_width, cpk + half_width))
     }
 
-    pub fn dirty(&self) -> bool {
-        self.dirty
+    /// Two-sided `1 - alpha` confidence interval for the process mean,
+    /// `x̄ ± t(1 - alpha / 2, N - 1) * s / sqrt(N)` from all `N` measurements.
+    ///
+    /// Returns `None` with fewer than 2 subgroups or if `alpha` is not in `(0, 1)`.
+    pub fn mean_confidence_interval(&self, alpha: f64) -> Option<(f64, f64)> {
+        if self.data.len() < 2 || !(alpha > 0.0 && alpha < 1.0) {
+            return None;
+        }
+        let n = self.all_data.len() as f64;
+        let mean = self.all_data.average();
+        let t = student_t_inverse_cdf(1.0 - alpha / 2.0, n - 1.0);
+        let half_width = t * self.all_data.std_dev() / n.sqrt();
+        Some((mean - half_width, mean + half_width))
     }
 
-    pub fn rounding_ctx(&self) -> &Option<RoundingContext> {
-        &self.rounding_ctx
+    /// Process performance `(USL - LSL) / 6s` from the overall standard deviation of
+    /// all measurements.
+    ///
+    /// Returns `None` unless both spec limits are set, or if the overall standard
+    /// deviation is zero or undefined.
+    pub fn pp(&mut self) -> Option<f64> {
+        self.update();
+        let sigma = capability_sigma(self.all_stddev, self.all_average).ok()?;
+        Some((self.usl? - self.lsl?) / (6.0 * sigma))
     }
 
-    pub fn set_rounding_ctx(&mut self, rounding_ctx: Option<RoundingContext>) {
-        self.rounding_ctx = rounding_ctx;
+    /// Process performance index `min(USL - x̄, x̄ - LSL) / 3s` from the overall mean and
+    /// standard deviation of all measurements, using only the spec limits that are set.
+    ///
+    /// Returns `None` without spec limits, or if the overall standard deviation is zero
+    /// or undefined.
+    pub fn ppk(&mut self) -> Option<f64> {
+        self.update();
+        let sigma = capability_sigma(self.all_stddev, self.all_average).ok()?;
+        self.capability_index(self.all_average, sigma)
     }
 
-    pub fn set_group_count(&mut self, group_count: usize) {
-        self.group_count = group_count;
+    /// Distribution-free Cpk by the percentile method (ISO 22514-2), the alternative to
+    /// the sigma-based `cpk` for non-normal processes.
+    ///
+    /// The 0.135% and 99.865% quantiles and the median of `all_data` take the place of
+    /// `mean ± 3σ` and the mean: `Cpu = (usl - median) / (P99.865 - median)` and
+    /// `Cpl = (median - lsl) / (median - P0.135)`, the smaller of the two when both limits
+    /// are set. Returns `None` without spec limits or when a quantile spread is zero.
+    pub fn cpk_percentile(&self) -> Option<f64> {
+        let median = self.all_data.median();
+        let upper_spread = self.all_data.quantile(0.99865) - median;
+        let lower_spread = median - self.all_data.quantile(0.00135);
+        let ratio = |distance: f64, spread: f64| {
+            if spread > 0.0 {
+                Some(distance / spread)
+            } else {
+                None
+            }
+        };
+        let upper = self.usl.and_then(|usl| ratio(usl - median, upper_spread));
+        let lower = self.lsl.and_then(|lsl| ratio(median - lsl, lower_spread));
+        match (upper, lower) {
+            (Some(u), Some(l)) => Some(u.min(l)),
+            (u, l) => u.or(l),
+        }
+    }
+
+    /// Warnings that the normality the capability indices assume is doubtful for
+    /// `all_data`, empty when nothing stands out.
+    ///
+    /// Flags a skewness or excess kurtosis beyond ±1 and an Anderson-Darling p-value
+    /// below 0.05, or too few values (fewer than 8) for the Anderson-Darling test.
+    pub fn capability_caveats(&self) -> Vec<String> {
+        let mut caveats = vec![];
+        let skewness = self.all_data.skewness();
+        if skewness.abs() > 1.0 {
+            caveats.push(format!("data are skewed (skewness {:.3})", skewness));
+        }
+        let kurtosis = self.all_data.kurtosis();
+        if kurtosis.abs() > 1.0 {
+            caveats.push(format!(
+                "data tails differ from normal (excess kurtosis {:.3})",
+                kurtosis
+            ));
+        }
+        let (_, p_value) = self.all_data.anderson_darling();
+        if p_value.is_nan() {
+            caveats.push("too few values to test normality".to_string());
+        } else if p_value < 0.05 {
+            caveats.push(format!(
+                "data fail the Anderson-Darling normality test (p = {:.4})",
+                p_value
+            ));
+        }
+        caveats
+    }
+
+    fn capability_index(&self, mean: f64, sigma: f64) -> Option<f64> {
+        let upper = self.usl.map(|usl| (usl - mean) / (3.0 * sigma));
+        let lower = self.lsl.map(|lsl| (mean - lsl) / (3.0 * sigma));
+        match (upper, lower) {
+            (Some(u), Some(l)) => Some(u.min(l)),
+            (u, l) => u.or(l),
+        }
+    }
+
+    pub fn weights(&self) -> Vec<f64> {
+        self.weights.to_vec()
+    }
+
+    pub fn data(&self) -> Vec<Vec<f64>> {
+        self.data.to_vec()
+    }
+
+    /// The plotted series of the primary chart: the subgroup averages for the Xbar-R and
+    /// Xbar-S charts, the ranges for the R chart, the standard deviations for the S chart
+    /// and the maxima for the min-max chart. See `secondary_chart_data` for the dispersion
+    /// companion of an Xbar chart.
+    pub fn chart_data(&self) -> Vec<f64> {
+        match self.chart_type {
+            GroupStatsChartType::RChart => self.ranges.to_vec(),
+            GroupStatsChartType::XbarRChart => self.average.to_vec(),
+            GroupStatsChartType::SChart => self.stddev.to_vec(),
+            GroupStatsChartType::XbarSChart => self.average.to_vec(),
+            GroupStatsChartType::MinMaxChart => self.maximum.to_vec(),
+        }
+    }
+
+    /// Updates the chart and predicts the next point of `chart_data`, see `ForecastMethod`.
+    pub fn forecast_next(&mut self, method: ForecastMethod) -> f64 {
+        self.update();
+        method.forecast(&self.chart_data())
+    }
+
+    /// Updates the chart and renders the last `width` points of `chart_data` with the
+    /// centerline and control limits as a `height` line text plot, marking points outside
+    /// the limits with `*`.
+    pub fn to_ascii_chart(&mut self, width: usize, height: usize) -> String {
+        self.update();
+        let chart_data = self.chart_data();
+        ascii_chart::render(&chart_data, self.cl, self.ucl, self.lcl, width, height)
+    }
+
+    /// Updates the chart and renders it to a PNG image at `path`, see `render_svg`.
+    #[cfg(feature = "plotters")]
+    pub fn render_png(&mut self, path: &str, opts: ChartRenderOptions) -> Result<(), String> {
+        let root = BitMapBackend::new(path, (opts.width, opts.height)).into_drawing_area();
+        self.render(&root, opts)
+    }
+
+    /// Updates the chart and renders it to an SVG image at `path`: `chart_data`, the
+    /// centerline and control limits, the 1σ and 2σ zone bands, and the points violating
+    /// any of `opts.rules` highlighted.
+    #[cfg(feature = "plotters")]
+    pub fn render_svg(&mut self, path: &str, opts: ChartRenderOptions) -> Result<(), String> {
+        let root = SVGBackend::new(path, (opts.width, opts.height)).into_drawing_area();
+        self.render(&root, opts)
+    }
+
+    #[cfg(feature = "plotters")]
+    fn render<DB: DrawingBackend>(
+        &mut self,
+        root: &DrawingArea<DB, Shift>,
+        opts: ChartRenderOptions,
+    ) -> Result<(), String> {
+        let limits = self.control_limits();
+        let violations = self.out_of_control_points(opts.rules)?;
+        let chart_data = self.chart_data();
+        render::draw_chart(root, &opts.title, &chart_data, &limits, &violations)
+    }
+
+    /// The plotted series of the dispersion chart: the subgroup ranges for the R and
+    /// Xbar-R charts and the standard deviations for the S and Xbar-S charts. For the
+    /// min-max chart, the subgroup minima.
+    pub fn secondary_chart_data(&self) -> Vec<f64> {
+        match self.chart_type {
+            GroupStatsChartType::RChart | GroupStatsChartType::XbarRChart => self.ranges.to_vec(),
+            GroupStatsChartType::SChart | GroupStatsChartType::XbarSChart => self.stddev.to_vec(),
+            GroupStatsChartType::MinMaxChart => self.minimum.to_vec(),
+        }
+    }
+
+    /// Updates the chart and returns the centerline of `secondary_chart_data`.
+    pub fn secondary_cl(&mut self) -> f64 {
+        self.secondary_limits().0
+    }
+
+    /// Updates the chart and returns the upper control limit of `secondary_chart_data`.
+    pub fn secondary_ucl(&mut self) -> f64 {
+        self.secondary_limits().1
+    }
+
+    /// Updates the chart and returns the lower control limit of `secondary_chart_data`.
+    pub fn secondary_lcl(&mut self) -> f64 {
+        self.secondary_limits().2
+    }
+
+    /// The R chart limits `d2 * σ`, `D4 * R̄`, `D3 * R̄` or the S chart limits `c4 * σ`,
+    /// `B4 * s̄`, `B3 * s̄` from `sigma_estimate`, which equal the primary limits of an R or
+    /// S chart. The min-max chart shares its primary limits.
+    fn secondary_limits(&mut self) -> (f64, f64, f64) {
+        self.update();
+        let k = self.constants(self.limit_size());
+        let (cl, mut ucl, mut lcl) = match self.chart_type {
+            GroupStatsChartType::RChart | GroupStatsChartType::XbarRChart => {
+                let cl = k.d2 * self.sigma_estimate;
+                (cl, k.d4_factor * cl, k.d3_factor * cl)
+            }
+            GroupStatsChartType::SChart | GroupStatsChartType::XbarSChart => {
+                let cl = k.c4 * self.sigma_estimate;
+                (cl, k.b4 * cl, k.b3 * cl)
+            }
+            GroupStatsChartType::MinMaxChart => return (self.cl, self.ucl, self.lcl),
+        };
+        if self.sigma_multiple != 3.0 {
+            (ucl, lcl) = scale_dispersion_limits(cl, ucl, lcl, self.sigma_multiple / 3.0);
+        }
+        match &self.rounding_ctx {
+            None => (cl, ucl, lcl),
+            Some(ctx) => (
+                cl.scale(ctx.scale, &ctx.rounding_mode),
+                ucl.scale(ctx.scale, &ctx.rounding_mode),
+                lcl.scale(ctx.scale, &ctx.rounding_mode),
+            ),
+        }
+    }
+
+    /// The subgroup size whose constants give `cl`, `ucl` and `lcl`: `sub_group_size`, or
+    /// the rounded average subgroup size for variable subgroup sizes.
+    fn limit_size(&self) -> usize {
+        if self.variable_sub_group_size && !self.sub_group_sizes.is_empty() {
+            let total: usize = self.sub_group_sizes.iter().sum();
+            (total as f64 / self.sub_group_sizes.len() as f64).round() as usize
+        } else {
+            self.sub_group_size
+        }
+    }
+
+    /// The constants applied to `cl`, `ucl` and `lcl`, those of `sub_group_size` or, for
+    /// variable subgroup sizes, of the rounded average size (the per-point limits use the
+    /// constants of each subgroup's own size).
+    pub fn control_constants(&self) -> ControlConstants {
+        self.constants(self.limit_size())
+    }
+
+    /// The constants of subgroups of size `n`, `custom_constants` if they are for `n`.
+    fn constants(&self, n: usize) -> ControlConstants {
+        constants_of(self.custom_constants, n)
+    }
+
+    /// The constants overriding the built-in tables, see `set_custom_constants`.
+    pub fn custom_constants(&self) -> Option<ControlConstants> {
+        self.custom_constants
+    }
+
+    /// Replaces the tabulated `A2`, `A3`, `d2`, `D3`, `D4`, `B3`, `B4` and `c4` with
+    /// validated constants, e.g. ones pinned by a regulated procedure.
+    ///
+    /// They apply to subgroups of `c.n` observations, other sizes keep the built-in tables.
+    pub fn set_custom_constants(&mut self, c: ControlConstants) {
+        self.custom_constants = Some(c);
+        self.dirty = true;
+    }
+
+    /// Restores the built-in constant tables.
+    pub fn clear_custom_constants(&mut self) {
+        self.custom_constants = None;
+        self.dirty = true;
+    }
+
+    pub fn chart_average(&self) -> f64 {
+        self.cl
+    }
+
+    pub fn chart_sigma(&self) -> f64 {
+        (self.ucl - self.chart_average()) / self.sigma_multiple
+    }
+
+    /// Returns the zone of the plotted point at `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds of `chart_data`
+    pub fn zone_of(&self, index: usize) -> Zone {
+        let value = self.chart_data()[index];
+        let (upper, lower) = self.zone_boundaries();
+        Zone::classify(value, self.chart_average(), upper, lower)
+    }
+
+    /// Updates the chart and returns every plotted point with its limits, zone and the
+    /// `rules` it violates. Fails if any rule has invalid parameters.
+    pub fn data_points(&mut self, rules: &[SpcRule]) -> Result<Vec<DataPoint>, String> {
+        self.update();
+        let results = self.apply_rule_validation(rules.to_vec())?;
+        let zones = self.zones();
+        Ok(self
+            .chart_data()
+            .into_iter()
+            .zip(zones)
+            .enumerate()
+            .map(|(index, (value, zone))| DataPoint {
+                index,
+                value,
+                cl: self.cl_data[index],
+                ucl: self.ucl_data[index],
+                lcl: self.lcl_data[index],
+                zone,
+                violated_rules: results
+                    .iter()
+                    .filter(|res| res.bad_point_index.contains(&index))
+                    .map(|res| res.rule)
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Returns the zone of every plotted point.
+    pub fn zones(&self) -> Vec<Zone> {
+        let (upper, lower) = self.zone_boundaries();
+        let cl = self.chart_average();
+        self.chart_data()
+            .iter()
+            .map(|&value| Zone::classify(value, cl, upper, lower))
+            .collect()
+    }
+
+    /// Updates the chart and returns the fraction of plotted points in each zone, to
+    /// compare with `ZoneProportions::normal`. Too many points in zone C hint at
+    /// stratification, too few at a mixture of processes.
+    pub fn zone_proportions(&mut self) -> ZoneProportions {
+        self.update();
+        ZoneProportions::from_zones(&self.zones())
+    }
+
+    /// Updates the chart and returns the index of the subgroup at which a sustained shift
+    /// of the process mean by about `magnitude_sigma` sigma is first flagged, or `None`.
+    ///
+    /// Runs a tabular CUSUM over the individual measurements in order, against the process
+    /// mean (`target_centerline` if set), the centerline of the Xbar and min-max charts,
+    /// with `sigma_estimate`, the reference value `k = magnitude_sigma / 2` and the default
+    /// decision interval `h = 5`. Small shifts that stay within the Shewhart limits are flagged after a few
+    /// subgroups. Returns `None` as well if `magnitude_sigma` is not positive or
+    /// `sigma_estimate` is zero.
+    pub fn detect_shift(&mut self, magnitude_sigma: f64) -> Option<usize> {
+        if !(magnitude_sigma > 0.0 && magnitude_sigma.is_finite()) {
+            return None;
+        }
+        self.update();
+        let center = match self.chart_type {
+            GroupStatsChartType::RChart | GroupStatsChartType::SChart => {
+                self.target_centerline.unwrap_or(self.average_average)
+            }
+            // X̿ or the target, frozen with the limits
+            GroupStatsChartType::XbarRChart
+            | GroupStatsChartType::XbarSChart
+            | GroupStatsChartType::MinMaxChart => self.cl,
+        };
+        let mut cusum = Cusum::new(center, self.sigma_estimate).ok()?;
+        cusum.set_k(magnitude_sigma / 2.0).ok()?;
+        cusum.extend(self.all_data.iter().copied());
+        let signal = *cusum.tabular_signals().first()?;
+        let mut end = 0;
+        self.sub_group_sizes.iter().position(|size| {
+            end += size;
+            signal < end
+        })
+    }
+
+    fn zone_boundaries(&self) -> ([f64; 3], [f64; 3]) {
+        let chart_average = self.chart_average();
+        let sigma = self.chart_sigma();
+        let mut upper = [1.0, 2.0, 3.0].map(|k| chart_average + k * sigma);
+        let mut lower = [1.0, 2.0, 3.0].map(|k| chart_average - k * sigma);
+        if let Some(ctx) = &self.rounding_ctx {
+            for v in upper.iter_mut().chain(lower.iter_mut()) {
+                *v = v.scale(ctx.scale, &ctx.rounding_mode);
+            }
+        }
+        (upper, lower)
+    }
+
+    pub fn sub_group_size(&self) -> usize {
+        self.sub_group_size
+    }
+
+    pub fn chart_type(&self) -> GroupStatsChartType {
+        self.chart_type
+    }
+
+    /// Switches to another chart of the same subgroups, e.g. from Xbar-R to Xbar-S. The
+    /// stored subgroup statistics are kept, the next `update` recomputes the limits for
+    /// `chart_type`. Frozen limits belong to the previous chart and are released.
+    pub fn set_chart_type(&mut self, chart_type: GroupStatsChartType) {
+        self.chart_type = chart_type;
+        self.frozen_limits = None;
+        self.dirty = true;
+    }
+
+    pub fn sub_group_sizes(&self) -> Vec<usize> {
+        self.sub_group_sizes.to_vec()
+    }
+
+    pub fn variable_sub_group_size(&self) -> bool {
+        self.variable_sub_group_size
+    }
+
+    /// Allows subgroups whose size differs from `sub_group_size` (any size in `2..=25`).
+    ///
+    /// In this mode `ucl_data`/`lcl_data`/`cl_data` carry the limits computed with the
+    /// constants of each subgroup's own size, while `cl`/`ucl`/`lcl` are the limits for
+    /// the rounded average subgroup size.
+    pub fn set_variable_sub_group_size(&mut self, variable_sub_group_size: bool) {
+        self.variable_sub_group_size = variable_sub_group_size;
+        self.dirty = true;
+    }
+
+    /// The pooled standard deviation `sqrt(Σ(n_i - 1) * s_i² / Σ(n_i - 1))` of the
+    /// stored subgroups.
+    ///
+    /// Unlike `s̄ / c4`, which averages the subgroup standard deviations and corrects the
+    /// bias of each, pooling averages the variances, so it is the maximum-likelihood
+    /// estimate under a common within-subgroup sigma and has the smaller variance. It is
+    /// not corrected for its own (small) bias, which vanishes as `Σ(n_i - 1)` grows.
+    pub fn pooled_std_dev(&self) -> f64 {
+        let mut sum_of_squares = 0.0;
+        let mut degrees_of_freedom = 0.0;
+        for (&s, &n) in self.stddev.iter().zip(&self.sub_group_sizes) {
+            sum_of_squares += (n - 1) as f64 * s * s;
+            degrees_of_freedom += (n - 1) as f64;
+        }
+        (sum_of_squares / degrees_of_freedom).sqrt()
+    }
+
+    pub fn use_pooled_std_dev(&self) -> bool {
+        self.use_pooled_std_dev
+    }
+
+    /// Uses `pooled_std_dev` as the `sigma_estimate` of the S and Xbar-S charts.
+    pub fn set_use_pooled_std_dev(&mut self, use_pooled_std_dev: bool) {
+        self.use_pooled_std_dev = use_pooled_std_dev;
+        self.dirty = true;
+    }
+
+    pub fn use_population_std_dev(&self) -> bool {
+        self.use_population_std_dev
+    }
+
+    /// Stores the population (biased, divided by `n`) standard deviation of each subgroup
+    /// instead of the sample (Bessel-corrected, divided by `n - 1`) one, and recomputes
+    /// the stored values.
+    ///
+    /// The `c4`, `B3`, `B4` and `A3` constants are derived for the sample standard
+    /// deviation, the default, so only `s̄ / c4` estimates sigma without bias. With the
+    /// population standard deviation `s̄ / c4` underestimates it by `sqrt((n - 1) / n)`.
+    pub fn set_use_population_std_dev(&mut self, use_population_std_dev: bool) {
+        self.use_population_std_dev = use_population_std_dev;
+        let stddev = self
+            .data
+            .iter()
+            .map(|group_data| {
+                let stddev = self.subgroup_std_dev(group_data);
+                match &self.rounding_ctx {
+                    None => stddev,
+                    Some(ctx) => stddev.scale(ctx.scale, &ctx.rounding_mode),
+                }
+            })
+            .collect();
+        self.stddev = stddev;
+        self.dirty = true;
+    }
+
+    fn subgroup_std_dev(&self, group_data: &[f64]) -> f64 {
+        if self.use_population_std_dev {
+            group_data.population_std_dev()
+        } else {
+            group_data.std_dev()
+        }
+    }
+
+    pub fn target_centerline(&self) -> Option<f64> {
+        self.target_centerline
+    }
+
+    /// Centers the Xbar-R, Xbar-S and min-max charts on a target, e.g. a nominal dimension,
+    /// instead of the grand average: the limits become `target ± A2 * R̄` (`target ± A3 * s̄`,
+    /// `target ± 3 * R̄ / d2`) and the rules use the target as the centerline. `None` restores
+    /// the grand average. The R and S charts are not affected.
+    pub fn set_target_centerline(&mut self, cl: Option<f64>) {
+        self.target_centerline = cl;
+        self.dirty = true;
+    }
+
+    /// The method deriving `sigma_estimate`, by default `RBarOverD2` for the R and Xbar-R
+    /// charts and `SBarOverC4` (`PooledRms` with `use_pooled_std_dev`) for the S and
+    /// Xbar-S charts.
+    pub fn sigma_method(&self) -> SigmaMethod {
+        match (self.sigma_method, self.chart_type) {
+            (Some(method), _) => method,
+            (
+                None,
+                GroupStatsChartType::RChart
+                | GroupStatsChartType::XbarRChart
+                | GroupStatsChartType::MinMaxChart,
+            ) => SigmaMethod::RBarOverD2,
+            (None, _) if self.use_pooled_std_dev => SigmaMethod::PooledRms,
+            (None, _) => SigmaMethod::SBarOverC4,
+        }
+    }
+
+    /// Derives `sigma_estimate` with `method` regardless of the chart type, e.g. `R̄ / d2`
+    /// on an S chart to match legacy reports, `None` restores the default.
+    ///
+    /// The control limits keep the chart type's own estimate, only `sigma_estimate` and
+    /// what builds on it (capability, secondary limits) change.
+    pub fn set_sigma_method(&mut self, method: Option<SigmaMethod>) {
+        self.sigma_method = method;
+        self.dirty = true;
+    }
+
+    pub fn known_sigma(&self) -> Option<f64> {
+        self.known_sigma
+    }
+
+    /// Builds the limits from a known process sigma, e.g. from an extensive history,
+    /// instead of estimating it from the stored subgroups (the "standards given" case):
+    /// `cl ± 3σ / sqrt(n)` for the Xbar charts, `d2σ` and `c4σ` centered limits for the R
+    /// and S charts. `sigma_estimate` returns the known sigma. `None` restores the
+    /// estimate.
+    pub fn set_known_sigma(&mut self, sigma: Option<f64>) -> Result<(), String> {
+        if sigma.is_some_and(|sigma| !(sigma > 0.0 && sigma.is_finite())) {
+            return Err("GroupStats: known sigma must be positive".to_string());
+        }
+        self.known_sigma = sigma;
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn estimate_sigma(&self, method: SigmaMethod) -> f64 {
+        let unbiased = |values: &[f64], constant: fn(&ControlConstants) -> f64| {
+            values
+                .iter()
+                .zip(&self.sub_group_sizes)
+                .map(|(&v, &n)| v / constant(&self.constants(n)))
+                .collect::<Vec<f64>>()
+                .average()
+        };
+        match method {
+            SigmaMethod::SBarOverC4 => unbiased(&self.stddev, |k| k.c4),
+            SigmaMethod::PooledRms => self.pooled_std_dev(),
+            SigmaMethod::RBarOverD2 => unbiased(&self.ranges, |k| k.d2),
+        }
+    }
+
+    pub fn cl_data(&self) -> Vec<f64> {
+        self.cl_data.to_vec()
+    }
+
+    pub fn ucl_data(&self) -> Vec<f64> {
+        self.ucl_data.to_vec()
+    }
+
+    pub fn lcl_data(&self) -> Vec<f64> {
+        self.lcl_data.to_vec()
+    }
+
+    pub fn ranges(&self) -> Vec<f64> {
+        self.ranges.to_vec()
+    }
+
+    pub fn stddev(&self) -> Vec<f64> {
+        self.stddev.to_vec()
+    }
+
+    pub fn average(&self) -> Vec<f64> {
+        self.average.to_vec()
+    }
+
+    pub fn range_average(&self) -> f64 {
+        self.range_average
+    }
+
+    pub fn range_stddev(&self) -> f64 {
+        self.range_stddev
+    }
+
+    pub fn stddev_average(&self) -> f64 {
+        self.stddev_average
+    }
+
+    pub fn stddev_stddev(&self) -> f64 {
+        self.stddev_stddev
+    }
+
+    pub fn average_average(&self) -> f64 {
+        self.average_average
+    }
+
+    pub fn average_stddev(&self) -> f64 {
+        self.average_stddev
+    }
+
+    /// Every individual measurement of the stored subgroups in order, the pooled data behind
+    /// `all_average` and `all_stddev`, e.g. for a histogram or capability study.
+    pub fn all_data(&self) -> Vec<f64> {
+        self.all_data.to_vec()
+    }
+
+    pub fn all_average(&self) -> f64 {
+        self.all_average
+    }
+
+    pub fn all_stddev(&self) -> f64 {
+        self.all_stddev
+    }
+
+    pub fn sigma_estimate(&self) -> f64 {
+        self.sigma_estimate
+    }
+
+    pub fn minimum(&self) -> Vec<f64> {
+        self.minimum.to_vec()
+    }
+
+    pub fn maximum(&self) -> Vec<f64> {
+        self.maximum.to_vec()
+    }
+
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn rounding_ctx(&self) -> &Option<RoundingContext> {
+        &self.rounding_ctx
+    }
+
+    pub fn set_rounding_ctx(&mut self, rounding_ctx: Option<RoundingContext>) {
+        self.rounding_ctx = rounding_ctx;
+    }
+
+    pub fn trend_ties(&self) -> TrendTies {
+        self.trend_ties
+    }
+
+    /// Sets whether equal consecutive points break a run of `Rule6PointsUpOrDown`, by
+    /// default they do.
+    pub fn set_trend_ties(&mut self, trend_ties: TrendTies) {
+        self.trend_ties = trend_ties;
+    }
+
+    /// The maximum number of subgroups kept by a `RetentionPolicy::Count`, older ones are
+    /// evicted.
+    pub fn group_count(&self) -> usize {
+        self.group_count
+    }
+
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        #[cfg(feature = "chrono")]
+        if let Some(max_age) = self.max_age {
+            return RetentionPolicy::Duration(max_age);
+        }
+        RetentionPolicy::Count(self.group_count)
+    }
+
+    /// Chooses between evicting by subgroup count, the default, and by age. A count applies
+    /// right away, an age from the next added subgroup.
+    pub fn set_retention_policy(&mut self, policy: RetentionPolicy) {
+        match policy {
+            RetentionPolicy::Count(group_count) => self.set_group_count(group_count),
+            #[cfg(feature = "chrono")]
+            RetentionPolicy::Duration(max_age) => self.max_age = Some(max_age),
+        }
+    }
+
+    /// The timestamp of each subgroup given to `add_data_at`, `None` for the others.
+    #[cfg(feature = "chrono")]
+    pub fn timestamps(&self) -> Vec<Option<DateTime<Utc>>> {
+        self.timestamps.to_vec()
+    }
+
+    /// The number of stored subgroups, at most `group_count`.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Keeps the newest `group_count` subgroups, same as `RetentionPolicy::Count`, evicting
+    /// the oldest ones right away if more are stored.
+    pub fn set_group_count(&mut self, group_count: usize) {
+        self.group_count = group_count;
+        #[cfg(feature = "chrono")]
+        {
+            self.max_age = None;
+        }
+        if self.data.len() > group_count {
+            self.evict();
+            self.dirty = true;
+        }
+    }
+
+    pub fn lsl(&self) -> Option<f64> {
+        self.lsl
+    }
+
+    pub fn usl(&self) -> Option<f64> {
+        self.usl
+    }
+
+    /// Sets the lower and upper specification limits used by the capability indices,
+    /// either may be omitted for a one-sided specification.
+    pub fn set_spec_limits(&mut self, lsl: Option<f64>, usl: Option<f64>) -> Result<(), String> {
+        if let (Some(l), Some(u)) = (lsl, usl)
+            && l >= u
+        {
+            return Err("GroupStats: lsl must be less than usl".to_string());
+        }
+        self.lsl = lsl;
+        self.usl = usl;
+        Ok(())
+    }
+
+    pub fn sigma_multiple(&self) -> f64 {
+        self.sigma_multiple
+    }
+
+    /// Sets the distance `k` of the control limits from the centerline in sigma, 3 by default.
+    ///
+    /// The `A2`, `A3`, `D3`, `D4`, `B3` and `B4` constants give 3σ limits, so the limits are
+    /// moved `k / 3` of their distance from the centerline, e.g. `cl ± (k / 3) * A2 * R̄`
+    /// for the Xbar-R chart.
+    pub fn set_sigma_multiple(&mut self, k: f64) -> Result<(), String> {
+        if !(k > 0.0 && k.is_finite()) {
+            return Err("GroupStats: sigma_multiple must be positive".to_string());
+        }
+        self.sigma_multiple = k;
+        self.dirty = true;
+        Ok(())
     }
 }
 
-#[cfg(test)]
-mod test_group_stats {
-    use crate::group_stats::{GroupStats, GroupStatsChartType};
-    use crate::RoundingMode::RoundHalfUp;
-    use crate::{RoundingContext, SpcRule};
+/// Moves the limits of an R or S chart `factor` of their distance from the centerline.
+///
+/// A lower limit the constants clamp to zero is rescaled from the upper distance (the
+/// limits are symmetric before clamping) and clamped to zero again.
+fn scale_dispersion_limits(cl: f64, ucl: f64, lcl: f64, factor: f64) -> (f64, f64) {
+    let lower_distance = if lcl > 0.0 { cl - lcl } else { ucl - cl };
+    (
+        cl + factor * (ucl - cl),
+        (cl - factor * lower_distance).max(0.0),
+    )
+}
+
+/// A summary of the chart as of the last `update`: the chart type and subgroup count, the
+/// limits with `sigma_estimate` and, with spec limits, Cp and Cpk. `{:.3}` rounds the values.
+impl fmt::Display for GroupStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} chart, {} subgroups", self.chart_type, self.len())?;
+        if self.dirty {
+            write!(f, " (not updated)")?;
+        }
+        writeln!(f)?;
+        write_labeled(
+            f,
+            &[
+                ("CL", self.cl),
+                ("UCL", self.ucl),
+                ("LCL", self.lcl),
+                ("sigma", self.sigma_estimate),
+            ],
+        )?;
+        if self.lsl.is_none() && self.usl.is_none() {
+            return Ok(());
+        }
+        let sigma = capability_sigma(self.sigma_estimate, self.average_average).ok();
+        let mut capability = vec![];
+        if let (Some(sigma), Some(lsl), Some(usl)) = (sigma, self.lsl, self.usl) {
+            capability.push(("Cp", (usl - lsl) / (6.0 * sigma)));
+        }
+        if let Some(cpk) =
+            sigma.and_then(|sigma| self.capability_index(self.average_average, sigma))
+        {
+            capability.push(("Cpk", cpk));
+        }
+        if !capability.is_empty() {
+            writeln!(f)?;
+            write_labeled(f, &capability)?;
+        }
+        Ok(())
+    }
+}
+
+/// A sigma usable as the denominator of a capability index.
+///
+/// A sigma within floating point noise of the `mean` (identical measurements rarely
+/// average to exactly their value) counts as zero.
+fn capability_sigma(sigma: f64, mean: f64) -> Result<f64, StatsError> {
+    if sigma.is_nan() || sigma <= 1e-12 * mean.abs() || sigma == 0.0 {
+        Err(StatsError::ZeroVariance)
+    } else {
+        Ok(sigma)
+    }
+}
+
+#[cfg(test)]
+mod test_group_stats {
+    use crate::RoundingMode::RoundHalfUp;
+    use crate::constants::ControlConstants;
+    use crate::distribution::normal_inverse_cdf;
+    use crate::group_stats::{
+        GroupStats, GroupStatsChartType, RetentionPolicy, SigmaMethod, SubgroupSummary,
+    };
+    use crate::statistics::Statistics;
+    use crate::{
+        DataPoint, ForecastMethod, RoundingContext, SpcRule, Zone, ZoneProportions,
+        assert_almost_eq, limits_equal,
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    pub fn test_xbar_r_chart() {
+        let v1 = vec![
+            0.65, 0.75, 0.75, 0.60, 0.70, 0.60, 0.75, 0.60, 0.65, 0.60, 0.80, 0.85, 0.70, 0.65,
+            0.90, 0.75, 0.75, 0.75, 0.65, 0.60, 0.50, 0.60, 0.80, 0.65, 0.65,
+        ];
+        let v2 = vec![
+            0.70, 0.85, 0.80, 0.70, 0.75, 0.75, 0.80, 0.70, 0.80, 0.70, 0.75, 0.75, 0.70, 0.70,
+            0.80, 0.80, 0.70, 0.70, 0.65, 0.60, 0.55, 0.80, 0.65, 0.60, 0.70,
+        ];
+        let v3 = vec![
+            0.65, 0.75, 0.80, 0.70, 0.65, 0.75, 0.65, 0.80, 0.85, 0.60, 0.90, 0.85, 0.75, 0.85,
+            0.80, 0.75, 0.85, 0.60, 0.85, 0.65, 0.65, 0.65, 0.75, 0.65, 0.70,
+        ];
+        let v4 = vec![
+            0.65, 0.85, 0.70, 0.75, 0.85, 0.85, 0.75, 0.75, 0.85, 0.80, 0.50, 0.65, 0.75, 0.75,
+            0.75, 0.80, 0.70, 0.70, 0.65, 0.60, 0.80, 0.65, 0.65, 0.60, 0.60,
+        ];
+        let v5 = vec![
+            0.85, 0.65, 0.75, 0.65, 0.80, 0.70, 0.70, 0.75, 0.75, 0.65, 0.80, 0.70, 0.70, 0.60,
+            0.85, 0.65, 0.80, 0.60, 0.70, 0.65, 0.80, 0.75, 0.65, 0.70, 0.65,
+        ];
+
+        let mut xbar_r_chart_stats = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        xbar_r_chart_stats.set_group_count(100);
+        xbar_r_chart_stats.set_rounding_ctx(Some(RoundingContext::new(2, RoundHalfUp)));
+        for i in 0..v1.len() {
+            xbar_r_chart_stats
+                .add_data(&[v1[i], v2[i], v3[i], v4[i], v5[i]])
+                .unwrap();
+        }
+
+        xbar_r_chart_stats.update();
+        let ucl = xbar_r_chart_stats.ucl();
+        let lcl = xbar_r_chart_stats.lcl();
+        let cl = xbar_r_chart_stats.cl();
+        let average = xbar_r_chart_stats.average();
+        let ranges = xbar_r_chart_stats.ranges();
+        println!("ucl: {}", ucl);
+        println!("cl: {}", cl);
+        println!("lcl: {}", lcl);
+        println!("average: {:?}", average);
+        println!("range: {:?}", ranges);
+        let res = xbar_r_chart_stats
+            .apply_rule_validation(vec![
+                SpcRule::Rule1Beyond3Sigma(1, 2),
+                SpcRule::Rule2Of3Beyond2Sigma(2, 3, 1),
+            ])
+            .unwrap();
+        println!("res: {:#?}", res);
+    }
+
+    #[test]
+    pub fn test_variable_sub_group_size() {
+        let groups: Vec<Vec<f64>> = vec![
+            vec![0.65, 0.70, 0.65, 0.65, 0.85],
+            vec![0.75, 0.85, 0.75],
+            vec![0.75, 0.80, 0.80, 0.70],
+            vec![0.60, 0.70, 0.70, 0.75, 0.65],
+            vec![0.70, 0.75, 0.65],
+            vec![0.60, 0.75, 0.75, 0.85, 0.70],
+        ];
+
+        let mut fixed = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        assert!(fixed.add_data(&groups[1]).is_err());
+
+        let mut stats = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_variable_sub_group_size(true);
+        for group in &groups {
+            stats.add_data(group).unwrap();
+        }
+        assert!(stats.add_data(&[0.7]).is_err());
+        stats.update();
+
+        assert_eq!(vec![5, 3, 4, 5, 3, 5], stats.sub_group_sizes());
+        let all: Vec<f64> = groups.concat();
+        assert_almost_eq!(stats.average_average(), all.average(), 1e-12);
+
+        let sigma = [
+            0.20 / 2.326,
+            0.10 / 1.693,
+            0.10 / 2.059,
+            0.15 / 2.326,
+            0.10 / 1.693,
+            0.25 / 2.326,
+        ]
+        .average();
+        assert_almost_eq!(stats.sigma_estimate(), sigma, 1e-12);
+
+        let ucl_data = stats.ucl_data();
+        let lcl_data = stats.lcl_data();
+        assert_almost_eq!(ucl_data[0], all.average() + 0.577 * 2.326 * sigma, 1e-12);
+        assert_almost_eq!(ucl_data[1], all.average() + 1.023 * 1.693 * sigma, 1e-12);
+        assert_almost_eq!(lcl_data[2], all.average() - 0.729 * 2.059 * sigma, 1e-12);
+        // the scalar limits use the average subgroup size (25 / 6 rounds to 4)
+        assert_almost_eq!(stats.ucl(), all.average() + 0.729 * 2.059 * sigma, 1e-12);
+        assert!(stats.cl_data().iter().all(|&cl| cl == stats.cl()));
+
+        let mut r_chart = GroupStats::new(5, GroupStatsChartType::RChart).unwrap();
+        r_chart.set_variable_sub_group_size(true);
+        for group in &groups {
+            r_chart.add_data(group).unwrap();
+        }
+        r_chart.update();
+        let cl_data = r_chart.cl_data();
+        assert_almost_eq!(cl_data[0], 2.326 * sigma, 1e-12);
+        assert_almost_eq!(cl_data[1], 1.693 * sigma, 1e-12);
+        assert_almost_eq!(r_chart.ucl_data()[1], 2.574 * 1.693 * sigma, 1e-12);
+    }
+
+    #[test]
+    pub fn test_variable_sub_group_size_eviction() {
+        let mut stats = GroupStats::new(5, GroupStatsChartType::XbarSChart).unwrap();
+        stats.set_variable_sub_group_size(true);
+        stats.set_group_count(2);
+        stats.add_data(&[1.0, 2.0, 3.0]).unwrap();
+        stats.add_data(&[4.0, 5.0, 6.0, 7.0]).unwrap();
+        stats.add_data(&[8.0, 9.0]).unwrap();
+        stats.update();
+        assert_eq!(vec![4, 2], stats.sub_group_sizes());
+        assert_almost_eq!(stats.all_average(), 39.0 / 6.0, 1e-12);
+    }
+
+    #[test]
+    pub fn test_zones() {
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        for group in [[9.0, 11.0], [10.0, 12.0], [8.0, 10.0], [9.0, 11.0]] {
+            stats.add_data(&group).unwrap();
+        }
+        stats.update();
+        let cl = stats.chart_average();
+        let sigma = stats.chart_sigma();
+        assert_eq!(10.0, cl);
+        assert_eq!(Zone::LowerC, stats.zone_of(2));
+        assert_eq!(Zone::UpperC, stats.zone_of(1));
+
+        // points exactly on the 1σ/2σ boundaries belong to the inner zone
+        stats.add_data(&[cl + sigma, cl + sigma]).unwrap();
+        stats
+            .add_data(&[cl - 2.0 * sigma, cl - 2.0 * sigma])
+            .unwrap();
+        stats
+            .add_data(&[cl + 2.5 * sigma, cl + 2.5 * sigma])
+            .unwrap();
+        stats
+            .add_data(&[cl - 4.0 * sigma, cl - 4.0 * sigma])
+            .unwrap();
+        assert_eq!(
+            vec![
+                Zone::UpperC,
+                Zone::UpperC,
+                Zone::LowerC,
+                Zone::UpperC,
+                Zone::UpperC,
+                Zone::LowerB,
+                Zone::UpperA,
+                Zone::BelowA,
+            ],
+            stats.zones()
+        );
+    }
+
+    #[test]
+    pub fn test_zone_proportions() {
+        // standard normal values from a 64-bit LCG through the inverse normal CDF
+        let mut state: u64 = 42;
+        let mut next_normal = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            normal_inverse_cdf(((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64)
+        };
+        let mut stats = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_group_count(2000);
+        for _ in 0..2000 {
+            let group: Vec<f64> = (0..5).map(|_| 10.0 + next_normal()).collect();
+            stats.add_data(&group).unwrap();
+        }
+        let observed = stats.zone_proportions();
+        let expected = ZoneProportions::normal();
+        assert_almost_eq!(expected.zone_c(), 0.6827, 1e-4);
+        assert_almost_eq!(expected.zone_b(), 0.2718, 1e-4);
+        assert_almost_eq!(expected.zone_a(), 0.0428, 1e-4);
+        assert_almost_eq!(expected.beyond(), 0.0027, 1e-4);
+        assert_almost_eq!(observed.zone_c(), expected.zone_c(), 0.03);
+        assert_almost_eq!(observed.zone_b(), expected.zone_b(), 0.03);
+        assert_almost_eq!(observed.zone_a(), expected.zone_a(), 0.015);
+        assert!(observed.beyond() < 0.01);
+        assert_almost_eq!(observed.upper_c, observed.lower_c, 0.05);
+        let total = observed.zone_c() + observed.zone_b() + observed.zone_a() + observed.beyond();
+        assert_almost_eq!(total, 1.0, 1e-9);
+
+        let mut empty = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        assert_eq!(ZoneProportions::default(), empty.zone_proportions());
+    }
+
+    #[test]
+    pub fn test_trending_toward_limit() {
+        let averages = [
+            10.0, 9.0, 11.0, 10.0, 9.5, 10.5, 10.0, 9.0, 11.0, 10.0, 9.5, 10.0, 10.5, 11.3, 12.1,
+            12.9, 13.6,
+        ];
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        for average in averages {
+            stats.add_data(&[average - 1.0, average + 1.0]).unwrap();
+        }
+        stats.update();
+        // the last five points rise by ~0.78 per subgroup and project to ~14.42,
+        // just beyond the UCL of ~14.34; the window before them stops short
+        let res = stats
+            .apply_rule_validation(vec![SpcRule::RuleNPointsTrendingTowardLimit(5)])
+            .unwrap();
+        assert!(!res[0].validation_passed);
+        assert_eq!(vec![12, 13, 14, 15, 16], res[0].bad_point_index);
+
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        for average in &averages[..13] {
+            stats.add_data(&[average - 1.0, average + 1.0]).unwrap();
+        }
+        stats.update();
+        let res = stats
+            .apply_rule_validation(vec![SpcRule::RuleNPointsTrendingTowardLimit(5)])
+            .unwrap();
+        assert!(res[0].validation_passed);
+    }
+
+    #[test]
+    pub fn test_points_near_center() {
+        // sigma is estimated from the within-subgroup range (R̄ = 2), so subgroup
+        // averages hugging the centerline are suspiciously flat
+        let flat = [10.0, 10.1, 9.9, 10.0, 10.2, 9.8, 10.0, 10.1, 9.9, 10.0];
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        for average in flat {
+            stats.add_data(&[average - 1.0, average + 1.0]).unwrap();
+        }
+        stats.update();
+        let res = stats
+            .apply_rule_validation(vec![SpcRule::RuleNPointsNearCenter(8, 10)])
+            .unwrap();
+        assert!(!res[0].validation_passed);
+        assert_eq!(10, res[0].bad_point_index.len());
+
+        let varying = [10.0, 11.2, 8.9, 10.4, 9.1, 11.5, 9.6, 10.9, 8.5, 10.0];
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        for average in varying {
+            stats.add_data(&[average - 1.0, average + 1.0]).unwrap();
+        }
+        stats.update();
+        let res = stats
+            .apply_rule_validation(vec![SpcRule::RuleNPointsNearCenter(8, 10)])
+            .unwrap();
+        assert!(res[0].validation_passed);
+        assert!(res[0].bad_point_index.is_empty());
+    }
+
+    #[test]
+    pub fn test_control_limits() {
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_rounding_ctx(Some(RoundingContext::new(2, RoundHalfUp)));
+        for group in [[9.0, 11.0], [10.0, 12.0], [8.0, 10.0], [9.0, 11.0]] {
+            stats.add_data(&group).unwrap();
+        }
+        let limits = stats.control_limits();
+        assert_eq!(10.0, limits.cl);
+        assert!(limits_equal(13.76, limits.ucl, 2));
+        assert!(limits_equal(6.24, limits.lcl, 2));
+        assert_eq!(stats.ucl(), limits.ucl);
+        assert_almost_eq!(limits.sigma, 1.2533, 1e-4);
+        assert_eq!(11.25, limits.upper_1s);
+        assert_eq!(12.51, limits.upper_2s);
+        assert_eq!(8.75, limits.lower_1s);
+        assert_eq!(7.49, limits.lower_2s);
+    }
+
+    #[test]
+    pub fn test_pooled_std_dev() {
+        let groups = [
+            vec![10.0, 10.4, 9.8, 10.2],
+            vec![9.9, 10.1, 10.0, 10.6],
+            vec![10.3, 9.7, 10.0, 10.1],
+        ];
+        let mut classic = GroupStats::new(4, GroupStatsChartType::XbarSChart).unwrap();
+        let mut pooled = GroupStats::new(4, GroupStatsChartType::XbarSChart).unwrap();
+        pooled.set_use_pooled_std_dev(true);
+        for group in &groups {
+            classic.add_data(group).unwrap();
+            pooled.add_data(group).unwrap();
+        }
+        classic.update();
+        pooled.update();
+
+        // with equal subgroup sizes pooling is the RMS of the subgroup standard deviations
+        let s: Vec<f64> = groups.iter().map(|g| g.std_dev()).collect();
+        let rms = (s.iter().map(|s| s * s).sum::<f64>() / 3.0).sqrt();
+        assert_almost_eq!(pooled.pooled_std_dev(), rms, 1e-12);
+        assert_almost_eq!(classic.pooled_std_dev(), rms, 1e-12);
+        assert_almost_eq!(pooled.sigma_estimate(), rms, 1e-12);
+        assert_almost_eq!(classic.sigma_estimate(), s.average() / 0.9213, 1e-12);
+        let half_width = 1.628 * 0.9213 * rms;
+        assert_almost_eq!(pooled.ucl(), pooled.cl() + half_width, 1e-12);
+        assert_almost_eq!(pooled.lcl(), pooled.cl() - half_width, 1e-12);
+    }
+
+    #[test]
+    pub fn test_extend_from_subgroups() {
+        let mut stats = GroupStats::new(3, GroupStatsChartType::XbarRChart).unwrap();
+        stats
+            .extend_from_subgroups(vec![vec![1.0, 2.0, 3.0], vec![2.0, 3.0, 4.0]])
+            .unwrap();
+        assert_eq!(2, stats.data().len());
+
+        let err = stats
+            .extend_from_subgroups(vec![vec![1.0, 2.0, 3.0], vec![1.0, 2.0]])
+            .unwrap_err();
+        assert!(err.starts_with("subgroup 1: "));
+        assert_eq!(3, stats.data().len());
+    }
 
     #[test]
-    pub fn test_xbar_r_chart() {
-        let v1 = vec![
-            0.65, 0.75, 0.75, 0.60, 0.70, 0.60, 0.75, 0.60, 0.65, 0.60, 0.80, 0.85, 0.70, 0.65,
-            0.90, 0.75, 0.75, 0.75, 0.65, 0.60, 0.50, 0.60, 0.80, 0.65, 0.65,
+    pub fn test_clone_template() {
+        let ctx = RoundingContext::new(2, RoundHalfUp);
+        let mut template = GroupStats::new(3, GroupStatsChartType::XbarRChart).unwrap();
+        template.set_rounding_ctx(Some(ctx));
+        template.set_group_count(50);
+
+        let mut line_a = template.clone();
+        let mut line_b = template.clone();
+        line_b.set_rounding_ctx(Some(ctx));
+        line_a.add_data(&[1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(1, line_a.data().len());
+        assert!(line_b.data().is_empty());
+        assert!(template.data().is_empty());
+        assert_eq!(&Some(ctx), line_b.rounding_ctx());
+    }
+
+    #[test]
+    pub fn test_sigma_multiple() {
+        let groups = [
+            [0.65, 0.70, 0.65, 0.65, 0.85],
+            [0.75, 0.85, 0.75, 0.85, 0.65],
+            [0.75, 0.80, 0.80, 0.70, 0.75],
+            [0.60, 0.70, 0.70, 0.75, 0.65],
         ];
-        let v2 = vec![
-            0.70, 0.85, 0.80, 0.70, 0.75, 0.75, 0.80, 0.70, 0.80, 0.70, 0.75, 0.75, 0.70, 0.70,
-            0.80, 0.80, 0.70, 0.70, 0.65, 0.60, 0.55, 0.80, 0.65, 0.60, 0.70,
+        let mut three = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        let mut two = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        assert!(two.set_sigma_multiple(0.0).is_err());
+        two.set_sigma_multiple(2.0).unwrap();
+        for group in &groups {
+            three.add_data(group).unwrap();
+            two.add_data(group).unwrap();
+        }
+        three.update();
+        two.update();
+
+        assert_eq!(three.cl(), two.cl());
+        assert_almost_eq!(
+            two.ucl() - two.lcl(),
+            (three.ucl() - three.lcl()) * 2.0 / 3.0,
+            1e-12
+        );
+        assert_almost_eq!(two.chart_sigma(), three.chart_sigma(), 1e-12);
+        assert_eq!(vec![two.ucl(); 4], two.ucl_data());
+
+        // the clamped lower limit of the R chart is rescaled from the upper distance
+        let mut range = GroupStats::new(5, GroupStatsChartType::RChart).unwrap();
+        range.set_sigma_multiple(1.5).unwrap();
+        for group in &groups {
+            range.add_data(group).unwrap();
+        }
+        range.update();
+        let r_bar = range.cl();
+        assert_almost_eq!(range.ucl(), r_bar + 0.5 * 1.114 * r_bar, 1e-12);
+        assert_almost_eq!(range.lcl(), r_bar - 0.5 * 1.114 * r_bar, 1e-12);
+    }
+
+    #[test]
+    pub fn test_warning_limits() {
+        let mut stats = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_rounding_ctx(Some(RoundingContext::new(3, RoundHalfUp)));
+        for group in [
+            [0.65, 0.70, 0.65, 0.65, 0.85],
+            [0.75, 0.85, 0.75, 0.85, 0.65],
+            [0.75, 0.80, 0.80, 0.70, 0.75],
+        ] {
+            stats.add_data(&group).unwrap();
+        }
+        // Xbar 0.7 0.77 0.76 with average 0.743, R 0.2 0.2 0.1 with average 0.167,
+        // sigma (0.84 - 0.743) / 3
+        let upper = stats.upper_warning_limit();
+        let lower = stats.lower_warning_limit();
+        assert!(limits_equal(0.84, stats.ucl(), 2));
+        assert!(limits_equal(0.808, upper, 3));
+        assert!(limits_equal(0.678, lower, 3));
+        assert!(stats.lcl() < lower && upper < stats.ucl());
+        assert_eq!((upper, lower), stats.limits_at_sigma(2.0));
+        // rebuilt from the rounded centerline and sigma, so within one unit of the rounding
+        let (ucl, lcl) = stats.limits_at_sigma(3.0);
+        assert_almost_eq!(ucl, stats.ucl(), 1.001e-3);
+        assert_almost_eq!(lcl, stats.lcl(), 1.001e-3);
+        assert_eq!((0.743, 0.743), stats.limits_at_sigma(0.0));
+    }
+
+    #[test]
+    pub fn test_limits_at_sigma_dispersion() {
+        let groups = [
+            [0.65, 0.70, 0.65, 0.65, 0.85],
+            [0.75, 0.85, 0.75, 0.85, 0.65],
+            [0.75, 0.80, 0.80, 0.70, 0.75],
         ];
-        let v3 = vec![
-            0.65, 0.75, 0.80, 0.70, 0.65, 0.75, 0.65, 0.80, 0.85, 0.60, 0.90, 0.85, 0.75, 0.85,
-            0.80, 0.75, 0.85, 0.60, 0.85, 0.65, 0.65, 0.65, 0.75, 0.65, 0.70,
+        let mut range = GroupStats::new(5, GroupStatsChartType::RChart).unwrap();
+        for group in groups {
+            range.add_data(&group).unwrap();
+        }
+        range.update();
+        // D3 is 0 for subgroups of 5, so the unclamped lower limit would be negative
+        assert_eq!((range.ucl(), range.lcl()), range.limits_at_sigma(3.0));
+        assert_eq!(0.0, range.limits_at_sigma(4.0).1);
+        let (upper, lower) = range.limits_at_sigma(2.0);
+        range.set_sigma_multiple(2.0).unwrap();
+        range.update();
+        assert_eq!((range.ucl(), range.lcl()), (upper, lower));
+
+        let mut deviation = GroupStats::new(5, GroupStatsChartType::SChart).unwrap();
+        for group in groups {
+            deviation.add_data(&group).unwrap();
+        }
+        deviation.update();
+        assert_eq!(
+            (deviation.ucl(), deviation.lcl()),
+            deviation.limits_at_sigma(3.0)
+        );
+        assert_eq!(0.0, deviation.limits_at_sigma(4.0).1);
+    }
+
+    #[test]
+    pub fn test_capability() {
+        let mut stats = GroupStats::new(4, GroupStatsChartType::XbarRChart).unwrap();
+        for group in [
+            [10.0, 10.4, 9.8, 10.2],
+            [9.9, 10.1, 10.0, 10.6],
+            [10.3, 9.7, 10.0, 10.1],
+        ] {
+            stats.add_data(&group).unwrap();
+        }
+        assert_eq!(None, stats.cpk());
+        assert!(stats.set_spec_limits(Some(11.0), Some(9.0)).is_err());
+        stats.set_spec_limits(Some(9.0), Some(11.5)).unwrap();
+
+        // R 0.6 0.7 0.6, sigma 0.6333 / 2.059, grand mean 10.0917
+        let sigma = 1.9 / 3.0 / 2.059;
+        let mean = 121.1 / 12.0;
+        assert_almost_eq!(stats.cp().unwrap(), 2.5 / (6.0 * sigma), 1e-12);
+        assert_almost_eq!(stats.cpk().unwrap(), (mean - 9.0) / (3.0 * sigma), 1e-12);
+        let s = stats.all_stddev();
+        assert_almost_eq!(stats.pp().unwrap(), 2.5 / (6.0 * s), 1e-12);
+        assert_almost_eq!(stats.ppk().unwrap(), (mean - 9.0) / (3.0 * s), 1e-12);
+
+        stats.set_spec_limits(None, Some(11.5)).unwrap();
+        assert_eq!(None, stats.cp());
+        assert_almost_eq!(stats.cpk().unwrap(), (11.5 - mean) / (3.0 * sigma), 1e-12);
+    }
+
+    #[test]
+    pub fn test_capability_constant_process() {
+        let mut stats = GroupStats::new(5, GroupStatsChartType::XbarSChart).unwrap();
+        stats.set_spec_limits(Some(0.5), Some(0.9)).unwrap();
+        for _ in 0..5 {
+            stats.add_data(&[0.70; 5]).unwrap();
+        }
+        assert_eq!(None, stats.cp());
+        assert_eq!(None, stats.cpk());
+        assert_eq!(None, stats.pp());
+        assert_eq!(None, stats.ppk());
+    }
+
+    #[test]
+    pub fn test_out_of_control_points() {
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        for group in [
+            [1.0, 2.0],
+            [2.0, 1.0],
+            [1.5, 2.5],
+            [2.0, 1.5],
+            [6.0, 6.5],
+            [2.0, 2.5],
+        ] {
+            stats.add_data(&group).unwrap();
+        }
+        stats.update();
+        let rules = vec![
+            SpcRule::Rule1Beyond3Sigma(1, 3),
+            SpcRule::Rule9PointsOnSameSideOfCenter(3),
         ];
-        let v4 = vec![
-            0.65, 0.85, 0.70, 0.75, 0.85, 0.85, 0.75, 0.75, 0.85, 0.80, 0.50, 0.65, 0.75, 0.75,
-            0.75, 0.80, 0.70, 0.70, 0.65, 0.60, 0.80, 0.65, 0.65, 0.60, 0.60,
+        let res = stats.apply_rule_validation(rules.clone()).unwrap();
+        assert_eq!(vec![4], res[0].bad_point_index);
+        assert_eq!(vec![0, 1, 2, 3], res[1].bad_point_index);
+        let points = stats.out_of_control_points(rules).unwrap();
+        assert_eq!(
+            vec![0, 1, 2, 3, 4],
+            points.into_iter().collect::<Vec<usize>>()
+        );
+    }
+
+    #[test]
+    pub fn test_detect_shift() {
+        let mut state: u64 = 7;
+        let mut next_normal = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            normal_inverse_cdf(((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64)
+        };
+        let in_control: Vec<Vec<f64>> = (0..30)
+            .map(|_| (0..5).map(|_| 10.0 + next_normal()).collect())
+            .collect();
+        let shifted: Vec<Vec<f64>> = (0..10)
+            .map(|_| (0..5).map(|_| 11.0 + next_normal()).collect())
+            .collect();
+        let mut stats = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        let mut min_max = GroupStats::new(5, GroupStatsChartType::MinMaxChart).unwrap();
+        for chart in [&mut stats, &mut min_max] {
+            for group in &in_control {
+                chart.add_data(group).unwrap();
+            }
+            chart.freeze_limits();
+            assert_eq!(None, chart.detect_shift(1.0));
+
+            // a 1σ shift moves the averages by 1 / sqrt(5) of their own sigma, mostly
+            // inside the Xbar limits, but the CUSUM flags it within a few subgroups
+            for group in &shifted {
+                chart.add_data(group).unwrap();
+            }
+            let index = chart.detect_shift(1.0).unwrap();
+            assert!((30..34).contains(&index), "{}: {}", chart.chart_type, index);
+        }
+
+        // identical in-control subgroups: the CUSUM runs against the process mean, not
+        // against the centerline of the plotted series
+        let groups = vec![vec![9.0, 9.5, 10.0, 10.5, 11.0]; 30];
+        let mut min_max =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::MinMaxChart).unwrap();
+        assert_eq!(None, min_max.detect_shift(1.0));
+
+        assert_eq!(None, stats.detect_shift(0.0));
+        assert_eq!(None, stats.detect_shift(f64::NAN));
+        let mut empty = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        assert_eq!(None, empty.detect_shift(1.0));
+    }
+
+    #[test]
+    pub fn test_set_chart_type() {
+        let groups = vec![
+            vec![0.65, 0.70, 0.65, 0.65, 0.85],
+            vec![0.75, 0.85, 0.75, 0.85, 0.65],
+            vec![0.75, 0.80, 0.80, 0.70, 0.75],
+            vec![0.60, 0.70, 0.70, 0.75, 0.65],
+            vec![0.70, 0.75, 0.65, 0.85, 0.80],
         ];
-        let v5 = vec![
-            0.85, 0.65, 0.75, 0.65, 0.80, 0.70, 0.70, 0.75, 0.75, 0.65, 0.80, 0.70, 0.70, 0.60,
-            0.85, 0.65, 0.80, 0.60, 0.70, 0.65, 0.80, 0.75, 0.65, 0.70, 0.65,
+        let mut stats =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::XbarRChart).unwrap();
+        stats.freeze_limits();
+        for chart_type in [
+            GroupStatsChartType::XbarSChart,
+            GroupStatsChartType::RChart,
+            GroupStatsChartType::SChart,
+            GroupStatsChartType::MinMaxChart,
+            GroupStatsChartType::XbarRChart,
+        ] {
+            stats.set_chart_type(chart_type);
+            assert_eq!(chart_type, stats.chart_type());
+            assert!(stats.dirty());
+            assert!(!stats.limits_frozen());
+            stats.update();
+            let fresh = GroupStats::from_subgroups(groups.clone(), chart_type).unwrap();
+            assert_eq!(fresh.cl(), stats.cl());
+            assert_eq!(fresh.ucl(), stats.ucl());
+            assert_eq!(fresh.lcl(), stats.lcl());
+            assert_eq!(fresh.sigma_estimate(), stats.sigma_estimate());
+            assert_eq!(fresh.chart_data(), stats.chart_data());
+        }
+    }
+
+    #[test]
+    pub fn test_rules_need_data() {
+        let rules = vec![SpcRule::Rule1Beyond3Sigma(1, 3)];
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        let err = stats.apply_rule_validation(rules.clone()).unwrap_err();
+        assert!(err.contains("no data"), "{}", err);
+        assert!(stats.out_of_control_points(rules.clone()).is_err());
+        stats.update();
+        assert!(stats.apply_rule_validation(rules.clone()).is_err());
+
+        stats.add_data(&[1.0, 2.0]).unwrap();
+        stats.add_data(&[2.0, 1.5]).unwrap();
+        let err = stats.apply_rule_validation(rules.clone()).unwrap_err();
+        assert!(err.contains("update"), "{}", err);
+        stats.update();
+        assert!(stats.apply_rule_validation(rules).unwrap()[0].validation_passed);
+    }
+
+    #[test]
+    pub fn test_from_subgroups() {
+        let data: Vec<Vec<f64>> = (0..150)
+            .map(|i| vec![1.0 + (i % 3) as f64, 2.0, 3.0])
+            .collect();
+        let stats = GroupStats::from_subgroups(data, GroupStatsChartType::XbarRChart).unwrap();
+        assert_eq!(3, stats.sub_group_size());
+        assert_eq!(150, stats.data().len());
+        assert_eq!(450, stats.all_data().len());
+        assert_eq!(stats.data().concat(), stats.all_data());
+        assert!(!stats.dirty());
+        assert_almost_eq!(stats.cl(), 2.0 + 1.0 / 3.0, 1e-12);
+
+        let ragged = vec![vec![1.0, 2.0], vec![1.0, 2.0], vec![1.0, 2.0, 3.0]];
+        let err = GroupStats::from_subgroups(ragged, GroupStatsChartType::RChart).unwrap_err();
+        assert!(err.starts_with("subgroup 2: "));
+        assert!(GroupStats::from_subgroups(vec![], GroupStatsChartType::RChart).is_err());
+    }
+
+    #[test]
+    pub fn test_from_individuals() {
+        let data: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let stats =
+            GroupStats::from_individuals(&data, 2, GroupStatsChartType::XbarRChart, false).unwrap();
+        assert_eq!(2, stats.sub_group_size());
+        assert_eq!(
+            vec![vec![0.0, 1.0], vec![2.0, 3.0]],
+            stats.data()[..2].to_vec()
+        );
+        assert_eq!(5, stats.len());
+        assert_eq!(data, stats.all_data());
+
+        let err = GroupStats::from_individuals(&data, 3, GroupStatsChartType::XbarRChart, false)
+            .unwrap_err();
+        assert!(err.contains("partial subgroup of 1"), "{}", err);
+        let stats =
+            GroupStats::from_individuals(&data, 3, GroupStatsChartType::XbarRChart, true).unwrap();
+        assert_eq!(3, stats.len());
+        assert_eq!(data[..9].to_vec(), stats.all_data());
+
+        assert!(GroupStats::from_individuals(&data, 0, GroupStatsChartType::RChart, true).is_err());
+        assert!(
+            GroupStats::from_individuals(&data, 1, GroupStatsChartType::RChart, false).is_err()
+        );
+        assert!(
+            GroupStats::from_individuals(&data, 20, GroupStatsChartType::RChart, true).is_err()
+        );
+    }
+
+    #[test]
+    pub fn test_secondary_chart() {
+        let groups = vec![
+            vec![0.65, 0.70, 0.65, 0.65, 0.85],
+            vec![0.75, 0.85, 0.75, 0.85, 0.65],
+            vec![0.75, 0.80, 0.80, 0.70, 0.75],
+            vec![0.60, 0.70, 0.70, 0.75, 0.65],
         ];
+        let mut xbar_r =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::XbarRChart).unwrap();
+        let r = GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::RChart).unwrap();
+        assert_eq!(r.chart_data(), xbar_r.secondary_chart_data());
+        assert_almost_eq!(xbar_r.secondary_cl(), r.cl(), 1e-12);
+        assert_almost_eq!(xbar_r.secondary_ucl(), r.ucl(), 1e-12);
+        assert_eq!(0.0, xbar_r.secondary_lcl());
 
-        let mut xbar_r_chart_stats = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
-        xbar_r_chart_stats.set_group_count(100);
-        xbar_r_chart_stats.set_rounding_ctx(Some(RoundingContext::new(2, RoundHalfUp)));
-        for i in 0..v1.len() {
-            let _r = xbar_r_chart_stats
-                .add_data(&vec![v1[i], v2[i], v3[i], v4[i], v5[i]])
+        let mut xbar_s =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::XbarSChart).unwrap();
+        let s = GroupStats::from_subgroups(groups, GroupStatsChartType::SChart).unwrap();
+        assert_eq!(s.chart_data(), xbar_s.secondary_chart_data());
+        assert_almost_eq!(xbar_s.secondary_cl(), s.cl(), 1e-12);
+        assert_almost_eq!(xbar_s.secondary_ucl(), s.ucl(), 1e-12);
+        assert_eq!(s.lcl(), xbar_s.secondary_lcl());
+    }
+
+    #[test]
+    pub fn test_compute_limits_from_range() {
+        let phase_one: Vec<Vec<f64>> = (0..25)
+            .map(|i| vec![10.0 + (i % 4) as f64 * 0.1, 10.2, 9.9, 10.1])
+            .collect();
+        let reference =
+            GroupStats::from_subgroups(phase_one.clone(), GroupStatsChartType::XbarRChart).unwrap();
+
+        let mut stats = GroupStats::new(4, GroupStatsChartType::XbarRChart).unwrap();
+        stats.extend_from_subgroups(phase_one).unwrap();
+        stats
+            .extend_from_subgroups(vec![vec![11.0, 11.2, 10.9, 11.1]; 5])
+            .unwrap();
+        assert!(stats.compute_limits_from_range(0, 0).is_err());
+        assert!(stats.compute_limits_from_range(0, 31).is_err());
+        assert!(!stats.limits_frozen());
+
+        stats.compute_limits_from_range(0, 25).unwrap();
+        assert!(stats.limits_frozen());
+        stats.update();
+        assert_eq!(30, stats.chart_data().len());
+        assert_eq!(reference.cl(), stats.cl());
+        assert_eq!(reference.ucl(), stats.ucl());
+        assert_eq!(reference.lcl(), stats.lcl());
+        assert_eq!(reference.sigma_estimate(), stats.sigma_estimate());
+        assert_eq!(vec![reference.ucl(); 30], stats.ucl_data());
+        assert_eq!(
+            vec![25, 26, 27, 28, 29],
+            stats
+                .out_of_control_points(vec![SpcRule::Rule1Beyond3Sigma(1, 3)])
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<usize>>()
+        );
+
+        stats.add_data(&[10.0, 10.2, 9.9, 10.1]).unwrap();
+        stats.update();
+        assert_eq!(reference.ucl(), stats.ucl());
+        assert_eq!(31, stats.ucl_data().len());
+
+        stats.unfreeze_limits();
+        stats.update();
+        assert!(stats.ucl() > reference.ucl());
+    }
+
+    #[test]
+    pub fn test_capability_caveats() {
+        let mut normal = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        normal
+            .extend_from_subgroups(vec![
+                vec![9.8, 10.1, 10.0, 9.9, 10.2],
+                vec![10.0, 9.7, 10.3, 10.1, 9.9],
+                vec![10.0, 10.1, 9.9, 10.0, 9.8],
+                vec![10.2, 10.0, 9.9, 10.1, 10.0],
+            ])
+            .unwrap();
+        assert!(normal.capability_caveats().is_empty());
+
+        let mut skewed = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        skewed
+            .extend_from_subgroups(vec![
+                vec![1.0, 1.1, 1.0, 1.2, 1.0],
+                vec![1.1, 1.0, 1.3, 1.0, 1.1],
+                vec![1.0, 1.2, 1.0, 1.1, 4.5],
+                vec![1.0, 1.1, 1.0, 1.0, 6.0],
+            ])
+            .unwrap();
+        let caveats = skewed.capability_caveats();
+        assert_eq!(3, caveats.len());
+        assert!(caveats[0].starts_with("data are skewed"));
+        assert!(caveats[2].starts_with("data fail the Anderson-Darling"));
+
+        let mut short = GroupStats::new(3, GroupStatsChartType::XbarRChart).unwrap();
+        short.add_data(&[1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(
+            Some(&"too few values to test normality".to_string()),
+            short.capability_caveats().last()
+        );
+    }
+
+    #[test]
+    pub fn test_cpk_percentile() {
+        // right-skewed: most parts sit near 1.0 with a long upper tail
+        let groups: Vec<Vec<f64>> = (0..20)
+            .map(|i| {
+                let tail = if i % 5 == 0 { 2.5 } else { 1.3 };
+                vec![1.0, 1.05, 1.1, 1.0, tail]
+            })
+            .collect();
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarSChart).unwrap();
+        assert_eq!(None, stats.cpk_percentile());
+
+        stats.set_spec_limits(Some(0.5), Some(3.0)).unwrap();
+        let all = stats.data().concat();
+        let median = all.median();
+        let cpu = (3.0 - median) / (all.quantile(0.99865) - median);
+        let cpl = (median - 0.5) / (median - all.quantile(0.00135));
+        assert_almost_eq!(stats.cpk_percentile().unwrap(), cpu.min(cpl), 1e-12);
+        assert_eq!(cpu, (3.0 - 1.05) / (2.5 - 1.05));
+        // the long tail inflates sigma, so the sigma-based cpk is far more pessimistic
+        assert!(stats.cpk_percentile().unwrap() - stats.cpk().unwrap() > 0.4);
+
+        stats.set_spec_limits(None, Some(3.0)).unwrap();
+        assert_almost_eq!(stats.cpk_percentile().unwrap(), cpu, 1e-12);
+    }
+
+    #[test]
+    pub fn test_merge() {
+        let line_a = vec![vec![1.0, 2.0, 3.0], vec![2.0, 3.0, 4.0]];
+        let line_b = vec![vec![3.0, 4.0, 5.0], vec![4.0, 5.0, 6.0]];
+        let mut stats =
+            GroupStats::from_subgroups(line_a, GroupStatsChartType::XbarRChart).unwrap();
+        let other = GroupStats::from_subgroups(line_b, GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_group_count(3);
+        stats.merge(&other).unwrap();
+        assert!(stats.dirty());
+        assert_eq!(
+            vec![
+                vec![2.0, 3.0, 4.0],
+                vec![3.0, 4.0, 5.0],
+                vec![4.0, 5.0, 6.0]
+            ],
+            stats.data()
+        );
+        stats.update();
+        assert_eq!(4.0, stats.cl());
+
+        let s_chart = GroupStats::new(3, GroupStatsChartType::XbarSChart).unwrap();
+        assert!(stats.merge(&s_chart).is_err());
+        let wide = GroupStats::new(4, GroupStatsChartType::XbarRChart).unwrap();
+        assert!(stats.merge(&wide).is_err());
+
+        let mut variable = GroupStats::new(3, GroupStatsChartType::XbarRChart).unwrap();
+        variable.set_variable_sub_group_size(true);
+        variable.add_data(&[1.0, 2.0, 3.0]).unwrap();
+        variable.add_data(&[1.0, 2.0]).unwrap();
+        assert!(stats.merge(&variable).is_err());
+        assert_eq!(3, stats.data().len());
+        assert_eq!(vec![4.0, 5.0, 6.0], stats.data()[2]);
+    }
+
+    #[test]
+    pub fn test_to_ascii_chart() {
+        let groups = vec![
+            vec![1.0, 2.0],
+            vec![1.0, 2.0],
+            vec![1.0, 2.0],
+            vec![5.0, 6.0],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        stats.compute_limits_from_range(0, 3).unwrap();
+        assert_eq!(
+            "    |   *\nUCL |----\n    |    \nCL  |ooo-\nLCL |----\n",
+            stats.to_ascii_chart(10, 5)
+        );
+    }
+
+    #[cfg(feature = "plotters")]
+    #[test]
+    pub fn test_render() {
+        use crate::render::ChartRenderOptions;
+
+        let groups = vec![
+            vec![1.0, 2.0],
+            vec![1.0, 2.0],
+            vec![1.0, 2.0],
+            vec![5.0, 6.0],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        stats.compute_limits_from_range(0, 3).unwrap();
+        let opts = ChartRenderOptions {
+            title: "Xbar".to_string(),
+            rules: vec![SpcRule::Rule1Beyond3Sigma(1, 3)],
+            ..ChartRenderOptions::default()
+        };
+        let dir = std::env::temp_dir();
+        let svg = dir.join("spc_rs_test_render.svg");
+        let png = dir.join("spc_rs_test_render.png");
+        stats
+            .render_svg(svg.to_str().unwrap(), opts.clone())
+            .unwrap();
+        stats.render_png(png.to_str().unwrap(), opts).unwrap();
+        let content = std::fs::read_to_string(&svg).unwrap();
+        assert!(content.contains("<svg") && content.contains("Xbar"));
+        assert!(std::fs::metadata(&png).unwrap().len() > 0);
+        std::fs::remove_file(svg).unwrap();
+        std::fs::remove_file(png).unwrap();
+    }
+
+    #[test]
+    pub fn test_sigma_method() {
+        let groups = vec![
+            vec![10.0, 10.4, 9.8, 10.2],
+            vec![9.9, 10.1, 10.0, 10.6],
+            vec![10.3, 9.7, 10.0, 10.1],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::SChart).unwrap();
+        assert_eq!(SigmaMethod::SBarOverC4, stats.sigma_method());
+        let s: Vec<f64> = groups.iter().map(|g| g.std_dev()).collect();
+        let r: Vec<f64> = groups.iter().map(|g| g.range()).collect();
+        assert_almost_eq!(stats.sigma_estimate(), s.average() / 0.9213, 1e-12);
+        let limits = (stats.cl(), stats.ucl(), stats.lcl());
+
+        stats.set_sigma_method(Some(SigmaMethod::RBarOverD2));
+        stats.update();
+        assert_eq!(SigmaMethod::RBarOverD2, stats.sigma_method());
+        assert_almost_eq!(stats.sigma_estimate(), r.average() / 2.059, 1e-12);
+        assert_eq!(limits, (stats.cl(), stats.ucl(), stats.lcl()));
+
+        stats.set_sigma_method(Some(SigmaMethod::PooledRms));
+        stats.update();
+        let rms = (s.iter().map(|s| s * s).sum::<f64>() / 3.0).sqrt();
+        assert_almost_eq!(stats.sigma_estimate(), rms, 1e-12);
+
+        stats.set_sigma_method(None);
+        stats.update();
+        assert_almost_eq!(stats.sigma_estimate(), s.average() / 0.9213, 1e-12);
+        let xbar_r = GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        assert_eq!(SigmaMethod::RBarOverD2, xbar_r.sigma_method());
+    }
+
+    #[test]
+    pub fn test_control_status() {
+        let groups = vec![
+            vec![1.0, 2.0],
+            vec![1.0, 2.0],
+            vec![1.0, 2.0],
+            vec![5.0, 6.0],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        stats.compute_limits_from_range(0, 3).unwrap();
+        assert_eq!(vec![true, true, true, false], stats.control_status());
+        let out: Vec<usize> = stats
+            .out_of_control_points(vec![SpcRule::Rule1Beyond3Sigma(1, 3)])
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(vec![3], out);
+    }
+
+    #[test]
+    pub fn test_population_std_dev() {
+        let groups = vec![
+            vec![1.0, 2.0, 3.0, 4.0],
+            vec![2.0, 2.0, 4.0, 4.0],
+            vec![1.0, 3.0, 3.0, 5.0],
+        ];
+        // sample s = sqrt(5/3), sqrt(4/3), sqrt(8/3); c4 = 0.9213, B4 = 2.266, B3 = 0
+        let mut stats = GroupStats::from_subgroups(groups, GroupStatsChartType::SChart).unwrap();
+        assert!(!stats.use_population_std_dev());
+        let s_bar = ((5.0f64 / 3.0).sqrt() + (4.0f64 / 3.0).sqrt() + (8.0f64 / 3.0).sqrt()) / 3.0;
+        assert_almost_eq!(stats.cl(), s_bar, 1e-12);
+        assert_almost_eq!(stats.ucl(), 2.266 * s_bar, 1e-12);
+        assert_eq!(0.0, stats.lcl());
+        assert_almost_eq!(stats.sigma_estimate(), s_bar / 0.9213, 1e-12);
+
+        // population s = sample s * sqrt(3/4)
+        stats.set_use_population_std_dev(true);
+        stats.update();
+        let population_s_bar = s_bar * 0.75f64.sqrt();
+        assert_almost_eq!(stats.stddev()[0], 1.25f64.sqrt(), 1e-12);
+        assert_almost_eq!(stats.cl(), population_s_bar, 1e-12);
+        assert_almost_eq!(stats.ucl(), 2.266 * population_s_bar, 1e-12);
+
+        stats.add_data(&[0.0, 0.0, 2.0, 2.0]).unwrap();
+        assert_eq!(1.0, stats.stddev()[3]);
+    }
+
+    #[test]
+    pub fn test_retain_subgroups() {
+        let groups = vec![
+            vec![1.0, 2.0],
+            vec![1.0, 9.0],
+            vec![2.0, 3.0],
+            vec![0.0, 8.0],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        stats.retain_subgroups(|s| s.range <= 5.0);
+        assert_eq!(vec![vec![1.0, 2.0], vec![2.0, 3.0]], stats.data());
+        assert_eq!(vec![1.0, 1.0], stats.ranges());
+        assert_eq!(vec![1.5, 2.5], stats.average());
+        assert_eq!(vec![2, 2], stats.sub_group_sizes());
+        assert!(stats.dirty());
+        stats.update();
+        assert_eq!(2.0, stats.cl());
+        assert_eq!(2.0, stats.all_average());
+
+        stats.retain_subgroups(|s| s.min > 1.0);
+        stats.update();
+        assert_eq!(vec![vec![2.0, 3.0]], stats.data());
+        assert_eq!(vec![3.0], stats.maximum());
+        assert_eq!(2.5, stats.cl());
+    }
+
+    #[test]
+    pub fn test_subgroup_summaries() {
+        let mut stats = GroupStats::new(3, GroupStatsChartType::XbarSChart).unwrap();
+        assert!(stats.subgroup_summaries().is_empty());
+        stats.set_group_count(3);
+        for group in [
+            [1.0, 2.0, 6.0],
+            [4.0, 4.0, 4.0],
+            [3.0, 5.0, 7.0],
+            [0.0, 2.0, 4.0],
+        ] {
+            stats.add_data(&group).unwrap();
+        }
+        let summaries = stats.subgroup_summaries();
+        assert_eq!(3, summaries.len());
+        assert_eq!(
+            SubgroupSummary {
+                index: 1,
+                average: 5.0,
+                range: 4.0,
+                stddev: 2.0,
+                min: 3.0,
+                max: 7.0,
+            },
+            summaries[1]
+        );
+        for summary in &summaries {
+            let i = summary.index;
+            assert_eq!(stats.average()[i], summary.average);
+            assert_eq!(stats.ranges()[i], summary.range);
+            assert_eq!(stats.stddev()[i], summary.stddev);
+            assert_eq!(stats.minimum()[i], summary.min);
+            assert_eq!(stats.maximum()[i], summary.max);
+        }
+    }
+
+    #[test]
+    pub fn test_window_size() {
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        assert_eq!(100, stats.group_count());
+        assert!(stats.is_empty());
+        stats.set_group_count(2);
+        for i in 0..3 {
+            stats.add_data(&[i as f64, 1.0]).unwrap();
+        }
+        assert_eq!(2, stats.len());
+        assert_eq!(2, stats.group_count());
+        assert!(!stats.is_empty());
+        assert_eq!(RetentionPolicy::Count(2), stats.retention_policy());
+        stats.set_retention_policy(RetentionPolicy::Count(1));
+        stats.add_data(&[3.0, 1.0]).unwrap();
+        assert_eq!(vec![vec![3.0, 1.0]], stats.data());
+    }
+
+    #[test]
+    pub fn test_shrink_group_count() {
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_group_count(1000);
+        for i in 0..50 {
+            stats.add_data(&[i as f64, i as f64 + 2.0]).unwrap();
+        }
+        stats.update();
+        assert_eq!(50, stats.len());
+        assert_almost_eq!(stats.cl(), 25.5, 1e-12);
+
+        stats.set_group_count(10);
+        assert_eq!(10, stats.len());
+        let expected: Vec<Vec<f64>> = (40..50).map(|i| vec![i as f64, i as f64 + 2.0]).collect();
+        assert_eq!(expected, stats.data());
+        assert_eq!(expected.concat(), stats.all_data());
+        assert_eq!(vec![2.0; 10], stats.ranges());
+        assert_eq!(
+            (40..50).map(|i| i as f64 + 1.0).collect::<Vec<f64>>(),
+            stats.average()
+        );
+        stats.update();
+        assert_almost_eq!(stats.cl(), 45.5, 1e-12);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    pub fn test_retention_duration() {
+        use chrono::{DateTime, Duration};
+
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let at = |minutes: i64| start + Duration::minutes(minutes);
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_group_count(2);
+        stats.set_retention_policy(RetentionPolicy::Duration(Duration::minutes(30)));
+        assert_eq!(
+            RetentionPolicy::Duration(Duration::minutes(30)),
+            stats.retention_policy()
+        );
+        // the count no longer caps the chart, a subgroup exactly 30 minutes old stays
+        for minutes in [0, 10, 20, 30] {
+            stats
+                .add_data_at(&[minutes as f64, 1.0], at(minutes))
                 .unwrap();
         }
+        assert_eq!(4, stats.len());
 
-        xbar_r_chart_stats.update();
-        let ucl = xbar_r_chart_stats.ucl();
-        let lcl = xbar_r_chart_stats.lcl();
-        let cl = xbar_r_chart_stats.cl();
-        let average = xbar_r_chart_stats.average();
-        let ranges = xbar_r_chart_stats.ranges();
-        println!("ucl: {}", ucl);
-        println!("cl: {}", cl);
-        println!("lcl: {}", lcl);
-        println!("average: {:?}", average);
-        println!("range: {:?}", ranges);
-        let res = xbar_r_chart_stats.apply_rule_validation(vec![
-            SpcRule::Rule1Beyond3Sigma(1, 2),
-            SpcRule::Rule2Of3Beyond2Sigma(2, 3, 1),
+        stats.add_data(&[99.0, 1.0]).unwrap();
+        stats.add_data_at(&[45.0, 1.0], at(45)).unwrap();
+        assert_eq!(
+            vec![Some(at(20)), Some(at(30)), None, Some(at(45))],
+            stats.timestamps()
+        );
+        assert_eq!(
+            vec![20.0, 1.0, 30.0, 1.0, 99.0, 1.0, 45.0, 1.0],
+            stats.all_data()
+        );
+
+        // a later merge ages out by the newest timestamp of either chart
+        let mut later = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        later.add_data_at(&[70.0, 1.0], at(70)).unwrap();
+        stats.merge(&later).unwrap();
+        assert_eq!(vec![None, Some(at(45)), Some(at(70))], stats.timestamps());
+
+        stats.set_group_count(2);
+        stats.add_data(&[80.0, 1.0]).unwrap();
+        assert_eq!(vec![Some(at(70)), None], stats.timestamps());
+    }
+
+    #[test]
+    pub fn test_forecast_next() {
+        let mut stats = GroupStats::new(2, GroupStatsChartType::XbarRChart).unwrap();
+        assert!(stats.forecast_next(ForecastMethod::Ewma(0.5)).is_nan());
+        for i in 0..5 {
+            stats.add_data(&[i as f64, i as f64 + 1.0]).unwrap();
+        }
+        // averages 0.5, 1.5, ..., 4.5 on the line 0.5 + i
+        assert_almost_eq!(
+            stats.forecast_next(ForecastMethod::LinearTrend(3)),
+            5.5,
+            1e-12
+        );
+        assert!(stats.forecast_next(ForecastMethod::LinearTrend(6)).is_nan());
+        assert!(stats.forecast_next(ForecastMethod::LinearTrend(1)).is_nan());
+        assert_eq!(4.5, stats.forecast_next(ForecastMethod::Ewma(1.0)));
+        // z from the average 2.5: 1.5, 1.5, 2.0, 2.75, 3.625
+        assert_almost_eq!(stats.forecast_next(ForecastMethod::Ewma(0.5)), 3.625, 1e-12);
+        assert!(stats.forecast_next(ForecastMethod::Ewma(0.0)).is_nan());
+    }
+
+    #[test]
+    pub fn test_subgroup_weights() {
+        let groups = vec![
+            vec![0.65, 0.70, 0.65, 0.65, 0.85],
+            vec![0.75, 0.85, 0.75, 0.85, 0.65],
+            vec![0.75, 0.80, 0.80, 0.70, 0.75],
+        ];
+        let mut unweighted =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::XbarRChart).unwrap();
+        let mut weighted = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        assert!(weighted.add_data_weighted(&groups[0], 0.0).is_err());
+        for group in &groups {
+            weighted.add_data_weighted(group, 2.5).unwrap();
+        }
+        weighted.update();
+        unweighted.update();
+        assert_eq!(vec![2.5; 3], weighted.weights());
+        assert_eq!(unweighted.average_average(), weighted.average_average());
+        assert_eq!(unweighted.range_average(), weighted.range_average());
+        assert_eq!(
+            (unweighted.cl(), unweighted.ucl(), unweighted.lcl()),
+            (weighted.cl(), weighted.ucl(), weighted.lcl())
+        );
+        assert_eq!(unweighted.sigma_estimate(), weighted.sigma_estimate());
+
+        // averages 0.70, 0.77, 0.76 and ranges 0.20, 0.20, 0.10 weighted 1, 1, 3
+        let mut volumes = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        for (group, weight) in groups.iter().zip([1.0, 1.0, 3.0]) {
+            volumes.add_data_weighted(group, weight).unwrap();
+        }
+        volumes.update();
+        assert_almost_eq!(
+            volumes.average_average(),
+            (0.70 + 0.77 + 3.0 * 0.76) / 5.0,
+            1e-12
+        );
+        assert_almost_eq!(
+            volumes.range_average(),
+            (0.20 + 0.20 + 3.0 * 0.10) / 5.0,
+            1e-12
+        );
+        assert_almost_eq!(volumes.cl(), 0.75, 1e-12);
+        assert_almost_eq!(volumes.ucl(), 0.75 + 0.577 * 0.14, 1e-12);
+        assert_eq!(
+            unweighted.stddev_average(),
+            volumes.stddev_average(),
+            "within-subgroup statistics stay unweighted"
+        );
+    }
+
+    #[test]
+    pub fn test_control_constants() {
+        let mut stats = GroupStats::new(4, GroupStatsChartType::XbarRChart).unwrap();
+        assert_eq!(ControlConstants::new(4), stats.control_constants());
+        assert_eq!(0.729, stats.control_constants().a2);
+
+        stats.set_variable_sub_group_size(true);
+        stats.add_data(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        stats
+            .add_data(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0])
+            .unwrap();
+        assert_eq!(7, stats.control_constants().n);
+    }
+
+    #[test]
+    pub fn test_custom_constants() {
+        let groups = vec![
+            vec![9.0, 11.0],
+            vec![10.0, 12.0],
+            vec![8.0, 10.0],
+            vec![9.0, 11.0],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        stats.update();
+        // X̿ = 10, R̄ = 2
+        assert_almost_eq!(stats.ucl(), 10.0 + 1.880 * 2.0, 1e-12);
+
+        let pinned = ControlConstants {
+            a2: 2.0,
+            ..ControlConstants::new(2)
+        };
+        stats.set_custom_constants(pinned);
+        stats.update();
+        assert_almost_eq!(stats.ucl(), 14.0, 1e-12);
+        assert_almost_eq!(stats.lcl(), 6.0, 1e-12);
+        assert_eq!(pinned, stats.control_constants());
+        assert_eq!(Some(pinned), stats.custom_constants());
+
+        // constants for another subgroup size leave the tables in place
+        stats.set_custom_constants(ControlConstants {
+            a2: 2.0,
+            ..ControlConstants::new(3)
+        });
+        stats.update();
+        assert_almost_eq!(stats.ucl(), 13.76, 1e-12);
+
+        stats.set_custom_constants(pinned);
+        stats.clear_custom_constants();
+        stats.update();
+        assert_almost_eq!(stats.ucl(), 13.76, 1e-12);
+        assert_eq!(ControlConstants::new(2), stats.control_constants());
+    }
+
+    #[test]
+    pub fn test_min_max_chart() {
+        let groups = vec![
+            vec![9.0, 10.0, 11.0],
+            vec![10.0, 12.0, 11.0],
+            vec![8.0, 10.0, 9.0],
+            vec![9.0, 11.0, 10.0],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::MinMaxChart).unwrap();
+        stats.update();
+        let max = stats.chart_data();
+        let min = stats.secondary_chart_data();
+        assert_eq!(vec![11.0, 12.0, 10.0, 11.0], max);
+        assert_eq!(vec![9.0, 10.0, 8.0, 9.0], min);
+        assert!(min.iter().zip(&max).all(|(lo, hi)| lo < hi));
+
+        // X̿ = 10, R̄ = 2, sigma = 2 / 1.693
+        let sigma = 2.0 / 1.693;
+        assert_almost_eq!(stats.cl(), 10.0, 1e-12);
+        assert_almost_eq!(stats.ucl(), 10.0 + 3.0 * sigma, 1e-12);
+        assert_almost_eq!(stats.lcl(), 10.0 - 3.0 * sigma, 1e-12);
+        assert_almost_eq!(stats.sigma_estimate(), sigma, 1e-12);
+        assert_eq!(
+            (stats.cl(), stats.ucl(), stats.lcl()),
+            (
+                stats.secondary_cl(),
+                stats.secondary_ucl(),
+                stats.secondary_lcl()
+            )
+        );
+
+        let mut xbar_r =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        xbar_r.update();
+        assert_eq!(xbar_r.cl(), stats.cl());
+        assert!(stats.ucl() > xbar_r.ucl() && stats.lcl() < xbar_r.lcl());
+    }
+
+    #[test]
+    pub fn test_min_max_chart_rules() {
+        let mut state: u64 = 7;
+        let mut next_normal = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            normal_inverse_cdf(((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64)
+        };
+        let mut stats = GroupStats::new(5, GroupStatsChartType::MinMaxChart).unwrap();
+        for _ in 0..60 {
+            let group: Vec<f64> = (0..5).map(|_| 10.0 + next_normal()).collect();
+            stats.add_data(&group).unwrap();
+        }
+        stats.update();
+        assert_almost_eq!(stats.control_limits().sigma, stats.sigma_estimate(), 1e-12);
+        // nearly all maxima lie above X̿, which the run rules must not flag
+        let above = stats
+            .chart_data()
+            .iter()
+            .filter(|&&x| x > stats.cl())
+            .count();
+        assert!(above > 55);
+        let rules = vec![
+            SpcRule::Rule1Beyond3Sigma(1, 3),
+            SpcRule::Rule2Of3Beyond2Sigma(2, 3, 2),
+            SpcRule::Rule4Of5Beyond1Sigma(4, 5, 1),
+            SpcRule::Rule6PointsUpOrDown(6),
+            SpcRule::Rule8PointsAboveOrBelowCenter(8),
+            SpcRule::Rule9PointsOnSameSideOfCenter(9),
+            SpcRule::Rule14PointsOscillating(14),
+            SpcRule::Rule15PointsWithin1Sigma(15, 1),
+        ];
+        let results = stats.apply_rule_validation(rules).unwrap();
+        assert!(results.iter().all(|r| r.validation_passed), "{:?}", results);
+
+        // a high maximum and a low minimum are both flagged, with the offending extreme
+        stats.freeze_limits();
+        stats.add_data(&[13.0, 12.5, 13.5, 12.8, 13.2]).unwrap();
+        stats.add_data(&[6.0, 8.5, 9.0, 8.0, 8.8]).unwrap();
+        stats.update();
+        let rule = vec![SpcRule::Rule1Beyond3Sigma(1, 3)];
+        let result = stats.apply_rule_validation(rule).unwrap().remove(0);
+        assert_eq!(vec![60, 61], result.bad_point_index);
+        assert_eq!(vec![13.5, 6.0], result.bad_point_data);
+        let rule = vec![SpcRule::Rule1Beyond3Sigma(3, 3)];
+        assert!(stats.apply_rule_validation(rule).unwrap()[0].validation_passed);
+        assert!(
+            stats
+                .apply_rule_validation(vec![SpcRule::Rule1Beyond3Sigma(0, 3)])
+                .is_err()
+        );
+    }
+
+    #[test]
+    pub fn test_signal_handler() {
+        let groups = vec![vec![1.0, 2.0]; 5];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        stats.freeze_limits();
+        let signals = Arc::new(Mutex::new(vec![]));
+        let sink = Arc::clone(&signals);
+        assert!(
+            stats
+                .set_signal_handler(vec![SpcRule::Rule1Beyond3Sigma(0, 3)], Box::new(|_| {}))
+                .is_err()
+        );
+        stats
+            .set_signal_handler(
+                vec![
+                    SpcRule::Rule1Beyond3Sigma(1, 3),
+                    SpcRule::Rule9PointsOnSameSideOfCenter(9),
+                ],
+                Box::new(move |res| sink.lock().unwrap().push(res.rule)),
+            )
+            .unwrap();
+
+        stats.add_data(&[1.0, 2.0]).unwrap();
+        assert!(signals.lock().unwrap().is_empty());
+        stats.add_data(&[6.0, 7.0]).unwrap();
+        assert_eq!(
+            vec![SpcRule::Rule1Beyond3Sigma(1, 3)],
+            *signals.lock().unwrap()
+        );
+        // the earlier violation does not signal again
+        stats.add_data(&[1.0, 2.0]).unwrap();
+        assert_eq!(1, signals.lock().unwrap().len());
+
+        stats.clear_signal_handler();
+        stats.add_data(&[6.0, 7.0]).unwrap();
+        assert_eq!(1, signals.lock().unwrap().len());
+    }
+
+    #[test]
+    pub fn test_read_only_access() {
+        let groups = vec![vec![1.0, 2.0], vec![1.0, 2.0], vec![1.0, 8.0]];
+        let stats = GroupStats::from_subgroups(groups, GroupStatsChartType::RChart).unwrap();
+        let shared = &stats;
+        assert_eq!(vec![1.0, 1.0, 7.0], shared.chart_data());
+        assert_eq!(3.0, shared.chart_average());
+        assert_almost_eq!(shared.chart_sigma(), (3.267 - 1.0) * 3.0 / 3.0, 1e-12);
+        assert_eq!(
+            vec![Zone::LowerC, Zone::LowerC, Zone::UpperB],
+            shared.zones()
+        );
+        assert!(
+            shared
+                .apply_rule_validation(vec![SpcRule::Rule1Beyond3Sigma(1, 3)])
+                .unwrap()
+                .iter()
+                .all(|res| res.validation_passed)
+        );
+    }
+
+    #[test]
+    pub fn test_data_points() {
+        let groups = vec![
+            vec![1.0, 2.0],
+            vec![1.0, 2.0],
+            vec![1.0, 2.0],
+            vec![5.0, 6.0],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        stats.compute_limits_from_range(0, 3).unwrap();
+        let rules = [
+            SpcRule::Rule1Beyond3Sigma(1, 3),
+            SpcRule::Rule2Of3Beyond2Sigma(2, 3, 2),
+        ];
+        let points = stats.data_points(&rules).unwrap();
+        assert_eq!(4, points.len());
+        assert_eq!(
+            DataPoint {
+                index: 0,
+                value: 1.5,
+                cl: 1.5,
+                ucl: 1.5 + 1.880,
+                lcl: 1.5 - 1.880,
+                zone: Zone::UpperC,
+                violated_rules: vec![],
+            },
+            points[0]
+        );
+        assert_eq!(5.5, points[3].value);
+        assert_eq!(Zone::AboveA, points[3].zone);
+        assert_eq!(
+            vec![SpcRule::Rule1Beyond3Sigma(1, 3)],
+            points[3].violated_rules
+        );
+    }
+
+    #[test]
+    pub fn test_target_centerline() {
+        let groups = vec![
+            vec![10.1, 10.3, 10.2],
+            vec![10.4, 10.2, 10.3],
+            vec![10.2, 10.0, 10.4],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        let half_width = stats.ucl() - stats.cl();
+        assert_almost_eq!(stats.cl(), 10.2333333333333, 1e-12);
+
+        stats.set_target_centerline(Some(10.0));
+        stats.update();
+        assert_eq!(10.0, stats.cl());
+        assert_almost_eq!(stats.ucl() - stats.cl(), half_width, 1e-12);
+        assert_almost_eq!(stats.cl() - stats.lcl(), half_width, 1e-12);
+        assert_almost_eq!(half_width, 1.023 * 0.8 / 3.0, 1e-12);
+        // 10.2 lies 2.2 sigma above the target
+        assert_eq!(Zone::UpperA, stats.zone_of(0));
+
+        stats.set_target_centerline(None);
+        stats.update();
+        assert_almost_eq!(stats.cl(), 10.2333333333333, 1e-12);
+    }
+
+    #[test]
+    pub fn test_display() {
+        let groups = vec![
+            vec![10.1, 10.3, 10.2, 10.0],
+            vec![10.4, 10.2, 10.3, 10.1],
+            vec![10.2, 10.0, 10.4, 10.3],
+            vec![9.9, 10.2, 10.1, 10.2],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups, GroupStatsChartType::XbarRChart).unwrap();
+        // x̄ = 10.18125, R̄ = 0.325, A2 = 0.729, σ̂ = 0.325 / 2.059
+        assert_eq!(
+            "xbar_r chart, 4 subgroups\nCL: 10.181, UCL: 10.418, LCL: 9.944, sigma: 0.158",
+            format!("{:.3}", stats)
+        );
+        assert!(
+            stats
+                .to_string()
+                .starts_with("xbar_r chart, 4 subgroups\nCL: 10.18125, ")
+        );
+
+        stats.set_spec_limits(Some(9.7), Some(10.7)).unwrap();
+        stats.update();
+        let text = format!("{:.2}", stats);
+        assert_eq!(3, text.lines().count());
+        assert_eq!("Cp: 1.06, Cpk: 1.02", text.lines().last().unwrap());
+
+        stats.set_spec_limits(None, Some(10.7)).unwrap();
+        stats.add_data(&[10.0, 10.1, 10.2, 10.3]).unwrap();
+        let text = format!("{:.2}", stats);
+        assert!(text.starts_with("xbar_r chart, 5 subgroups (not updated)\n"));
+        assert!(text.ends_with("\nCpk: 1.10"));
+    }
+
+    #[test]
+    pub fn test_known_sigma() {
+        let groups = vec![
+            vec![10.1, 10.3, 10.2, 10.0],
+            vec![10.4, 10.2, 10.3, 10.1],
+            vec![10.2, 10.0, 10.4, 10.3],
+            vec![9.9, 10.2, 10.1, 10.2],
+        ];
+        let mut stats =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::XbarRChart).unwrap();
+        let (estimated_cl, estimated_ucl) = (stats.cl(), stats.ucl());
+        // R̄ = 0.325, σ̂ = R̄ / d2 = 0.325 / 2.059
+        assert_almost_eq!(stats.sigma_estimate(), 0.325 / 2.059, 1e-12);
+        assert_almost_eq!(estimated_ucl - estimated_cl, 0.729 * 0.325, 1e-12);
+
+        assert!(stats.set_known_sigma(Some(0.0)).is_err());
+        assert!(stats.set_known_sigma(Some(f64::NAN)).is_err());
+        stats.set_known_sigma(Some(0.1)).unwrap();
+        assert_eq!(Some(0.1), stats.known_sigma());
+        stats.update();
+        assert_eq!(0.1, stats.sigma_estimate());
+        assert_eq!(estimated_cl, stats.cl());
+        assert_almost_eq!(stats.ucl(), estimated_cl + 3.0 * 0.1 / 2.0, 1e-12);
+        assert_almost_eq!(stats.lcl(), estimated_cl - 3.0 * 0.1 / 2.0, 1e-12);
+        assert!(stats.ucl() < estimated_ucl);
+        assert_eq!(vec![stats.ucl(); 4], stats.ucl_data());
+
+        let mut r_chart =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::RChart).unwrap();
+        r_chart.set_known_sigma(Some(0.1)).unwrap();
+        r_chart.update();
+        assert_almost_eq!(r_chart.cl(), 2.059 * 0.1, 1e-12);
+        assert_almost_eq!(r_chart.ucl(), 2.282 * 2.059 * 0.1, 1e-12);
+        assert_eq!(0.0, r_chart.lcl());
+
+        let mut s_chart = GroupStats::from_subgroups(groups, GroupStatsChartType::SChart).unwrap();
+        s_chart.set_known_sigma(Some(0.1)).unwrap();
+        s_chart.update();
+        assert_almost_eq!(s_chart.cl(), 0.9213 * 0.1, 1e-12);
+
+        stats.set_known_sigma(None).unwrap();
+        stats.update();
+        assert_eq!(estimated_ucl, stats.ucl());
+        assert_almost_eq!(stats.sigma_estimate(), 0.325 / 2.059, 1e-12);
+    }
+
+    #[test]
+    pub fn test_chart_average_is_centerline() {
+        let groups = vec![vec![10.1, 10.3, 10.2], vec![10.4, 10.2, 10.3]];
+        let mut stats =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::XbarRChart).unwrap();
+        stats.set_target_centerline(Some(10.0));
+        stats.update();
+        // the zones of the rules sit around the target, not the grand average
+        assert_eq!(10.0, stats.chart_average());
+        assert_ne!(stats.average_average(), stats.chart_average());
+
+        let r_chart = GroupStats::from_subgroups(groups, GroupStatsChartType::RChart).unwrap();
+        assert_eq!(r_chart.range_average(), r_chart.chart_average());
+    }
+
+    #[test]
+    pub fn test_mean_confidence_interval() {
+        let mut stats = GroupStats::new(3, GroupStatsChartType::XbarRChart).unwrap();
+        stats.add_data(&[9.8, 10.0, 10.4]).unwrap();
+        assert_eq!(None, stats.mean_confidence_interval(0.05));
+        stats.add_data(&[10.1, 9.9, 10.2]).unwrap();
+        assert_eq!(None, stats.mean_confidence_interval(0.0));
+
+        // mean 10.0667, s = 0.2160, t(0.975, 5) = 2.570582
+        let (low, high) = stats.mean_confidence_interval(0.05).unwrap();
+        let half_width = 2.570582 * stats.data().concat().std_dev() / 6.0f64.sqrt();
+        assert_almost_eq!((low + high) / 2.0, 60.4 / 6.0, 1e-12);
+        assert_almost_eq!(high - low, 2.0 * half_width, 1e-5);
+        let (narrow_low, narrow_high) = stats.mean_confidence_interval(0.5).unwrap();
+        assert!(narrow_low > low && narrow_high < high);
+    }
+
+    #[test]
+    pub fn test_cpk_confidence_interval() {
+        let groups: Vec<Vec<f64>> = (0..10)
+            .map(|i| vec![10.0 + 0.1 * (i % 3) as f64, 10.2, 9.9, 10.1, 9.8])
+            .collect();
+        let mut stats =
+            GroupStats::from_subgroups(groups.clone(), GroupStatsChartType::XbarRChart).unwrap();
+        assert_eq!(None, stats.cpk_confidence_interval(0.05));
+        stats.set_spec_limits(Some(9.0), Some(11.0)).unwrap();
+        assert_eq!(None, stats.cpk_confidence_interval(1.0));
+
+        let cpk = stats.cpk().unwrap();
+        let (low, high) = stats.cpk_confidence_interval(0.05).unwrap();
+        let half_width = 1.959964 * (1.0 / 450.0 + cpk * cpk / 98.0).sqrt();
+        assert_almost_eq!(low, cpk - half_width, 1e-5);
+        assert_almost_eq!(high, cpk + half_width, 1e-5);
+
+        stats.set_group_count(40);
+        for _ in 0..3 {
+            stats.extend_from_subgroups(groups.clone()).unwrap();
+        }
+        let (wider_low, wider_high) = (low, high);
+        let (low, high) = stats.cpk_confidence_interval(0.05).unwrap();
+        assert!(high - low < (wider_high - wider_low) * 0.6);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    pub fn test_in_control_arl() {
+        let stats = GroupStats::new(5, GroupStatsChartType::XbarRChart).unwrap();
+        let beyond = stats.in_control_arl(&[SpcRule::Rule1Beyond3Sigma(1, 3)]);
+        assert!((340.0..400.0).contains(&beyond), "{}", beyond);
+        let western_electric = stats.in_control_arl(&[
+            SpcRule::Rule1Beyond3Sigma(1, 3),
+            SpcRule::Rule2Of3Beyond2Sigma(2, 3, 2),
+            SpcRule::Rule4Of5Beyond1Sigma(4, 5, 1),
+            SpcRule::Rule8PointsAboveOrBelowCenter(8),
         ]);
-        println!("res: {:#?}", res);
+        assert!(western_electric < beyond / 2.0, "{}", western_electric);
+        assert_eq!(
+            beyond,
+            stats.in_control_arl(&[SpcRule::Rule1Beyond3Sigma(1, 3)])
+        );
+        assert_eq!(f64::INFINITY, stats.in_control_arl(&[]));
+        assert!(
+            stats
+                .in_control_arl(&[SpcRule::Rule1Beyond3Sigma(0, 3)])
+                .is_nan()
+        );
     }
 }
@@ -1,25 +1,54 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
 
-#[macro_use]
-extern crate approx;
+#[cfg(all(not(feature = "std"), not(feature = "libm")))]
+compile_error!("spc-rs needs the `std` feature, or the `libm` feature for no_std targets");
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod advisor;
+#[cfg(feature = "std")]
+mod ascii_chart;
+#[cfg(feature = "std")]
 pub mod attribute_stats;
+#[cfg(feature = "std")]
+pub mod batch_stats;
+pub mod constants;
+#[cfg(feature = "std")]
+pub mod cusum;
+pub mod distribution;
 pub mod error;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
 pub mod group_stats;
+#[cfg(feature = "std")]
 pub mod moving_stats;
 pub mod prec;
+#[cfg(feature = "plotters")]
+pub mod render;
+#[cfg(feature = "std")]
+mod rules;
 pub mod statistics;
 
+use crate::distribution::normal_cdf;
+#[cfg(feature = "std")]
 use crate::statistics::Statistics;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::fmt::Formatter;
+#[cfg(feature = "std")]
 use num_traits::{FromPrimitive, ToPrimitive};
+#[cfg(feature = "std")]
 use rust_decimal::{Decimal, RoundingStrategy};
-use std::fmt;
-use std::fmt::Formatter;
 
 ///
 /// ```rust
 ///     use spc_rs::group_stats::{GroupStats, GroupStatsChartType};
 ///     pub fn main() {
-///         use spc_rs::{RoundingContext, SpcRule};
+///         use spc_rs::{limits_equal, RoundingContext, SpcRule};
 ///         use spc_rs::RoundingMode::RoundHalfUp;
 ///         let v1 = vec![
 ///             0.65, 0.75, 0.75, 0.60, 0.70, 0.60, 0.75, 0.60, 0.65, 0.60, 0.80, 0.85, 0.70, 0.65,
@@ -51,14 +80,15 @@ use std::fmt::Formatter;
 ///                 .unwrap();
 ///         }
 ///
+///         xbar_r_chart_stats.update();
 ///         let ucl = xbar_r_chart_stats.ucl();
 ///         let lcl = xbar_r_chart_stats.lcl();
 ///         let cl = xbar_r_chart_stats.cl();
 ///         let average = xbar_r_chart_stats.average();
 ///         let ranges = xbar_r_chart_stats.ranges();
-///         assert_eq!(0.82,  ucl);
-///         assert_eq!(0.72,  cl);
-///         assert_eq!(0.61,  lcl);
+///         assert!(limits_equal(0.82, ucl, 2));
+///         assert!(limits_equal(0.72, cl, 2));
+///         assert!(limits_equal(0.61, lcl, 2));
 ///         println!("average: {:?}",average);
 ///         println!("range: {:?}",ranges);
 ///
@@ -66,7 +96,7 @@ use std::fmt::Formatter;
 ///         let res = xbar_r_chart_stats.apply_rule_validation(vec![
 ///             SpcRule::Rule1Beyond3Sigma(1, 3),
 ///             SpcRule::Rule2Of3Beyond2Sigma(2, 3, 2),
-///         ]);
+///         ]).unwrap();
 ///         println!("res: {:#?}", res);
 ///
 ///
@@ -74,8 +104,6 @@ use std::fmt::Formatter;
 ///
 /// ```
 ///
-///
-
 #[macro_export]
 macro_rules! assert_almost_eq {
     ($a:expr, $b:expr, $prec:expr) => {
@@ -88,10 +116,35 @@ macro_rules! assert_almost_eq {
     };
 }
 
+/// Like `assert_almost_eq!`, but with a tolerance relative to the magnitude of
+/// the values, see `prec::almost_eq_relative`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate spc_rs;
+///
+/// # fn main() {
+/// assert_almost_eq_rel!(2_000_000.0 / 3.0, 666_666.666_666_7, 1e-12);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_almost_eq_rel {
+    ($a:expr, $b:expr, $rel_tol:expr) => {
+        if !$crate::prec::almost_eq_relative($a, $b, $rel_tol) {
+            panic!(
+                "assertion failed: `abs(left - right) <= {:e} * max(abs(left), abs(right))`, (left: `{}`, right: `{}`)",
+                $rel_tol, $a, $b
+            );
+        }
+    };
+}
+
+#[cfg(feature = "std")]
 pub trait Rounding {
     fn scale(&self, scale: u32, rounding_mode: &RoundingMode) -> Self;
 }
 
+#[cfg(feature = "std")]
 impl Rounding for f64 {
     fn scale(&self, scale: u32, rounding_mode: &RoundingMode) -> Self {
         let decimal: Decimal = Decimal::from_f64(*self).unwrap();
@@ -128,12 +181,35 @@ impl Rounding for f64 {
     }
 }
 
-#[derive(Debug)]
+/// Compares two control chart values after rounding both half up to `scale`
+/// decimals, the way a `RoundingContext` reports them.
+///
+/// Non-finite values are only equal to themselves (`NaN` never is).
+///
+/// # Examples
+///
+/// ```
+/// use spc_rs::limits_equal;
+///
+/// assert!(limits_equal(0.824999999, 0.82, 2));
+/// assert!(!limits_equal(0.824999999, 0.825, 2));
+/// ```
+#[cfg(feature = "std")]
+pub fn limits_equal(a: f64, b: f64, scale: u32) -> bool {
+    if !a.is_finite() || !b.is_finite() {
+        return a == b;
+    }
+    a.scale(scale, &RoundingMode::RoundHalfUp) == b.scale(scale, &RoundingMode::RoundHalfUp)
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct RoundingContext {
     pub scale: u32,
     pub rounding_mode: RoundingMode,
 }
 
+#[cfg(feature = "std")]
 impl RoundingContext {
     pub fn new(scale: u32, rounding_mode: RoundingMode) -> Self {
         Self {
@@ -143,9 +219,17 @@ impl RoundingContext {
     }
 }
 
-#[derive(Debug)]
-pub enum RoundingMode {
+/// Two decimal places, rounding half up.
+#[cfg(feature = "std")]
+impl Default for RoundingContext {
+    fn default() -> Self {
+        Self::new(2, RoundingMode::RoundHalfUp)
+    }
+}
 
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RoundingMode {
     ///
     ///
     ///
@@ -292,8 +376,8 @@ pub enum RoundingMode {
     RoundHalfEven,
 }
 
-/// Defines mathematical expressions commonly used when computing distribution
-/// values as constants
+// Defines mathematical expressions commonly used when computing distribution
+// values as constants
 
 /// Constant value for `sqrt(2 * pi)`
 pub const SQRT_2PI: f64 = 2.5066282746310005024157652848110452530069867406099;
@@ -321,7 +405,8 @@ pub const EULER_MASCHERONI: f64 =
 /// Targeted accuracy instantiated over `f64`
 pub const ACC: f64 = 10e-11;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SpcRule {
     /// `p` points are beyond from  `s` sigma。
     ///
@@ -375,6 +460,67 @@ pub enum SpcRule {
     /// * n = 15
     /// * s = 1
     Rule15PointsWithin1Sigma(usize, usize),
+
+    /// `n` consecutive points are rising (or falling) and the least-squares line
+    /// fitted through them projects the next point beyond the UCL (or LCL).
+    ///
+    /// By default,
+    /// * n = 5
+    RuleNPointsTrendingTowardLimit(usize),
+
+    /// `p` out of `n` consecutive points fall within ±0.5σ of the centerline
+    /// (the inner half of zone C), a sign of stratification or overcontrol.
+    ///
+    /// By default,
+    /// * p = 8
+    /// * n = 10
+    RuleNPointsNearCenter(usize, usize),
+
+    /// `n` consecutive points are exactly equal, a stuck ("flat-lined") sensor that
+    /// no sigma rule catches. Points are compared as plotted, i.e. after rounding.
+    ///
+    /// By default,
+    /// * n = 5
+    RuleNIdenticalPoints(usize),
+}
+
+impl SpcRule {
+    /// Checks the rule parameters: every point count and window is at least 1, a count
+    /// `p` does not exceed its window `n`, every sigma multiple is at least 1 and the
+    /// trend and identical points windows are at least 2.
+    pub fn validate(&self) -> Result<(), String> {
+        let (p, n, s) = match *self {
+            SpcRule::Rule1Beyond3Sigma(p, s) => (p, p, s),
+            SpcRule::Rule2Of3Beyond2Sigma(p, n, s) | SpcRule::Rule4Of5Beyond1Sigma(p, n, s) => {
+                (p, n, s)
+            }
+            SpcRule::Rule6PointsUpOrDown(n)
+            | SpcRule::Rule8PointsAboveOrBelowCenter(n)
+            | SpcRule::Rule9PointsOnSameSideOfCenter(n)
+            | SpcRule::Rule14PointsOscillating(n) => (n, n, 1),
+            SpcRule::Rule15PointsWithin1Sigma(n, s) => (n, n, s),
+            SpcRule::RuleNPointsTrendingTowardLimit(n) | SpcRule::RuleNIdenticalPoints(n) => {
+                if n < 2 {
+                    return Err(format!("{:?}: the window must be at least 2", self));
+                }
+                (n, n, 1)
+            }
+            SpcRule::RuleNPointsNearCenter(p, n) => (p, n, 1),
+        };
+        if p == 0 || n == 0 {
+            return Err(format!("{:?}: point counts must be at least 1", self));
+        }
+        if p > n {
+            return Err(format!(
+                "{:?}: {} points cannot fall in a window of {}",
+                self, p, n
+            ));
+        }
+        if s == 0 {
+            return Err(format!("{:?}: the sigma multiple must be at least 1", self));
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for SpcRule {
@@ -432,11 +578,30 @@ impl fmt::Display for SpcRule {
                     p, s
                 )
             }
+            SpcRule::RuleNPointsTrendingTowardLimit(n) => {
+                write!(
+                    f,
+                    "{} consecutive points trending toward a control limit that is projected to be crossed by the next point",
+                    n
+                )
+            }
+            SpcRule::RuleNPointsNearCenter(p, n) => {
+                write!(
+                    f,
+                    "{} out of {} consecutive points fall within 0.5 sigma of the centerline",
+                    p, n
+                )
+            }
+            SpcRule::RuleNIdenticalPoints(n) => {
+                write!(f, "{} consecutive points are identical", n)
+            }
         }
     }
 }
 
-#[derive(Debug)]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpcRuleValidationResult {
     pub rule: SpcRule,
     pub bad_point_index: Vec<usize>,
@@ -444,21 +609,505 @@ pub struct SpcRuleValidationResult {
     pub validation_passed: bool,
 }
 
-// 检查是否递增
+/// The centerline, control limits and zone boundaries of a chart, taken
+/// together after an `update`.
+///
+/// `sigma` is the sigma of the plotted statistic, i.e. the width of one zone.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlLimits {
+    pub cl: f64,
+    pub ucl: f64,
+    pub lcl: f64,
+    pub sigma: f64,
+    pub upper_1s: f64,
+    pub upper_2s: f64,
+    pub lower_1s: f64,
+    pub lower_2s: f64,
+}
+
+#[cfg(feature = "std")]
+impl ControlLimits {
+    pub(crate) fn new(
+        cl: f64,
+        ucl: f64,
+        lcl: f64,
+        sigma: f64,
+        rounding_ctx: &Option<RoundingContext>,
+    ) -> Self {
+        let mut limits = Self {
+            cl,
+            ucl,
+            lcl,
+            sigma,
+            upper_1s: cl + sigma,
+            upper_2s: cl + 2.0 * sigma,
+            lower_1s: cl - sigma,
+            lower_2s: cl - 2.0 * sigma,
+        };
+        if let Some(ctx) = rounding_ctx {
+            for v in [
+                &mut limits.upper_1s,
+                &mut limits.upper_2s,
+                &mut limits.lower_1s,
+                &mut limits.lower_2s,
+            ] {
+                *v = v.scale(ctx.scale, &ctx.rounding_mode);
+            }
+        }
+        limits
+    }
+}
+
+/// Everything about a single plotted point: its value, the limits it is judged
+/// against (per subgroup for variable subgroup sizes), its zone and the rules it
+/// violates.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPoint {
+    pub index: usize,
+    pub value: f64,
+    pub cl: f64,
+    pub ucl: f64,
+    pub lcl: f64,
+    pub zone: Zone,
+    pub violated_rules: Vec<SpcRule>,
+}
+
+/// How `Rule6PointsUpOrDown` treats equal consecutive points.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TrendTies {
+    /// A tie breaks the run, the points must strictly rise or fall.
+    #[default]
+    Break,
+    /// A tie continues the run, which must rise or fall overall, e.g. `1, 2, 2, 3`.
+    Continue,
+}
+
+/// How `forecast_next` predicts the next plotted point.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForecastMethod {
+    /// The last value of the EWMA with weight `0 < alpha <= 1`, started at the average
+    /// of the series like the EWMA chart.
+    Ewma(f64),
+    /// The least squares line through the last `window` points, extrapolated one step.
+    LinearTrend(usize),
+}
+
+#[cfg(feature = "std")]
+impl ForecastMethod {
+    /// Predicts the point following `series`, `f64::NAN` if there are too few points
+    /// (none for `Ewma`, fewer than `max(window, 2)` for `LinearTrend`) or `alpha` is
+    /// out of range.
+    pub(crate) fn forecast(&self, series: &[f64]) -> f64 {
+        match *self {
+            ForecastMethod::Ewma(alpha) => {
+                if series.is_empty() || !(alpha > 0.0 && alpha <= 1.0) {
+                    return f64::NAN;
+                }
+                series
+                    .iter()
+                    .fold(series.average(), |z, x| alpha * x + (1.0 - alpha) * z)
+            }
+            ForecastMethod::LinearTrend(window) => {
+                if window < 2 || series.len() < window {
+                    return f64::NAN;
+                }
+                let x: Vec<f64> = (0..window).map(|i| i as f64).collect();
+                let (slope, intercept) = x.linear_regression(&series[series.len() - window..]);
+                intercept + slope * window as f64
+            }
+        }
+    }
+}
+
+/// The σ zone a plotted point falls in, split by the centerline.
+///
+/// Zone C spans the centerline to 1σ, zone B 1σ to 2σ and zone A 2σ to 3σ.
+/// A point lying exactly on a boundary belongs to the inner zone, and a point
+/// on the centerline belongs to `UpperC`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Zone {
+    AboveA,
+    UpperA,
+    UpperB,
+    UpperC,
+    LowerC,
+    LowerB,
+    LowerA,
+    BelowA,
+}
+
+impl Zone {
+    /// Classifies `value` against the centerline `cl` and the 1σ, 2σ and 3σ
+    /// boundaries given in `upper` (ascending) and `lower` (descending).
+    pub fn classify(value: f64, cl: f64, upper: [f64; 3], lower: [f64; 3]) -> Zone {
+        if value >= cl {
+            if value <= upper[0] {
+                Zone::UpperC
+            } else if value <= upper[1] {
+                Zone::UpperB
+            } else if value <= upper[2] {
+                Zone::UpperA
+            } else {
+                Zone::AboveA
+            }
+        } else if value >= lower[0] {
+            Zone::LowerC
+        } else if value >= lower[1] {
+            Zone::LowerB
+        } else if value >= lower[2] {
+            Zone::LowerA
+        } else {
+            Zone::BelowA
+        }
+    }
+}
+
+/// The fraction of plotted points in each σ zone, see `Zone`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ZoneProportions {
+    pub above_a: f64,
+    pub upper_a: f64,
+    pub upper_b: f64,
+    pub upper_c: f64,
+    pub lower_c: f64,
+    pub lower_b: f64,
+    pub lower_a: f64,
+    pub below_a: f64,
+}
+
+impl ZoneProportions {
+    /// Counts `zones`, all fractions are zero if it is empty.
+    pub fn from_zones(zones: &[Zone]) -> ZoneProportions {
+        let mut proportions = ZoneProportions::default();
+        if zones.is_empty() {
+            return proportions;
+        }
+        let share = 1.0 / zones.len() as f64;
+        for zone in zones {
+            *match zone {
+                Zone::AboveA => &mut proportions.above_a,
+                Zone::UpperA => &mut proportions.upper_a,
+                Zone::UpperB => &mut proportions.upper_b,
+                Zone::UpperC => &mut proportions.upper_c,
+                Zone::LowerC => &mut proportions.lower_c,
+                Zone::LowerB => &mut proportions.lower_b,
+                Zone::LowerA => &mut proportions.lower_a,
+                Zone::BelowA => &mut proportions.below_a,
+            } += share;
+        }
+        proportions
+    }
+
+    /// The proportions of a normal, in-control process: about 68.3% in zone C, 27.2%
+    /// in zone B, 4.3% in zone A and 0.27% beyond, split evenly by the centerline.
+    pub fn normal() -> ZoneProportions {
+        let c = normal_cdf(1.0) - 0.5;
+        let b = normal_cdf(2.0) - normal_cdf(1.0);
+        let a = normal_cdf(3.0) - normal_cdf(2.0);
+        let beyond = normal_cdf(-3.0);
+        ZoneProportions {
+            above_a: beyond,
+            upper_a: a,
+            upper_b: b,
+            upper_c: c,
+            lower_c: c,
+            lower_b: b,
+            lower_a: a,
+            below_a: beyond,
+        }
+    }
+
+    /// The fraction within 1σ of the centerline.
+    pub fn zone_c(&self) -> f64 {
+        self.upper_c + self.lower_c
+    }
+
+    /// The fraction between 1σ and 2σ on either side.
+    pub fn zone_b(&self) -> f64 {
+        self.upper_b + self.lower_b
+    }
+
+    /// The fraction between 2σ and 3σ on either side.
+    pub fn zone_a(&self) -> f64 {
+        self.upper_a + self.lower_a
+    }
+
+    /// The fraction beyond 3σ on either side.
+    pub fn beyond(&self) -> f64 {
+        self.above_a + self.below_a
+    }
+}
+
+/// Checks whether `data` is strictly increasing.
+///
+/// Returns `false` for slices shorter than 2, and for any plateau (equal
+/// consecutive values).
 pub fn is_increasing(data: &[f64]) -> bool {
-    data.windows(2).all(|pair| pair[1] > pair[0])
+    data.len() >= 2 && data.windows(2).all(|pair| pair[1] > pair[0])
 }
 
-// 检查是否递减
+/// Checks whether `data` is strictly decreasing.
+///
+/// Returns `false` for slices shorter than 2, and for any plateau (equal
+/// consecutive values).
 pub fn is_decreasing(data: &[f64]) -> bool {
-    data.windows(2).all(|pair| pair[1] < pair[0])
+    data.len() >= 2 && data.windows(2).all(|pair| pair[1] < pair[0])
+}
+
+/// Checks whether `data` never decreases, treating equal consecutive values
+/// as continuing the trend.
+///
+/// Returns `false` for slices shorter than 2.
+pub fn is_monotonic_nondecreasing(data: &[f64]) -> bool {
+    data.len() >= 2 && data.windows(2).all(|pair| pair[1] >= pair[0])
+}
+
+/// Checks whether `data` never increases, treating equal consecutive values
+/// as continuing the trend.
+///
+/// Returns `false` for slices shorter than 2.
+pub fn is_monotonic_nonincreasing(data: &[f64]) -> bool {
+    data.len() >= 2 && data.windows(2).all(|pair| pair[1] <= pair[0])
 }
 
-// 检查是否上下交替趋势
+/// Checks whether `data` alternates between increasing and decreasing.
+///
+/// Returns `false` for slices shorter than 3, since at least two moves are
+/// needed to alternate.
 pub fn is_alternating(data: &[f64]) -> bool {
-    data.windows(2)
-        .zip(data.windows(2).skip(1))
-        .all(|(prev, next)| {
-            (prev[1] > prev[0] && next[1] < next[0]) || (prev[1] < prev[0] && next[1] > next[0])
-        })
+    data.len() >= 3
+        && data
+            .windows(2)
+            .zip(data.windows(2).skip(1))
+            .all(|(prev, next)| {
+                (prev[1] > prev[0] && next[1] < next[0]) || (prev[1] < prev[0] && next[1] > next[0])
+            })
+}
+
+/// Lowercases a chart type name and drops everything but letters and digits, so
+/// `"Xbar-R"`, `"xbar_r"` and `"XBAR R"` compare equal.
+#[cfg(feature = "std")]
+pub(crate) fn chart_type_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Writes `label: value` pairs separated by commas, for the charts' `Display`, with the
+/// formatter's precision if one is given.
+#[cfg(feature = "std")]
+pub(crate) fn write_labeled(
+    f: &mut std::fmt::Formatter<'_>,
+    values: &[(&str, f64)],
+) -> std::fmt::Result {
+    for (i, (label, value)) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        match f.precision() {
+            Some(precision) => write!(f, "{}: {:.*}", label, precision, value)?,
+            None => write!(f, "{}: {}", label, value)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::attribute_stats::AttributeStatsChartType;
+    use crate::group_stats::GroupStatsChartType;
+    use crate::moving_stats::{MovingStats, MovingStatsChartType};
+    use crate::{
+        RoundingContext, RoundingMode, SpcRule, is_alternating, is_decreasing, is_increasing,
+        is_monotonic_nondecreasing, is_monotonic_nonincreasing, limits_equal,
+    };
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(
+            GroupStatsChartType::XbarRChart,
+            GroupStatsChartType::default()
+        );
+        assert_eq!(
+            AttributeStatsChartType::PChart,
+            AttributeStatsChartType::default()
+        );
+        assert_eq!(
+            MovingStatsChartType::IndividualsChart,
+            MovingStatsChartType::default()
+        );
+        assert_eq!(
+            RoundingContext::new(2, RoundingMode::RoundHalfUp),
+            RoundingContext::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_chart_types() {
+        let group = [
+            (GroupStatsChartType::RChart, vec!["R", "r_chart"]),
+            (
+                GroupStatsChartType::XbarRChart,
+                vec!["xbar_r", "Xbar-R", "XR"],
+            ),
+            (GroupStatsChartType::SChart, vec!["s", "SChart"]),
+            (
+                GroupStatsChartType::XbarSChart,
+                vec!["xbar s", "XbarSChart", "x-s"],
+            ),
+            (
+                GroupStatsChartType::MinMaxChart,
+                vec!["min-max", "MinMaxChart"],
+            ),
+        ];
+        for (chart_type, aliases) in group {
+            assert_eq!(Ok(chart_type), chart_type.to_string().parse());
+            for alias in aliases {
+                assert_eq!(Ok(chart_type), alias.parse(), "{}", alias);
+            }
+        }
+        let attribute = [
+            (AttributeStatsChartType::PChart, vec!["p", "P-Chart"]),
+            (AttributeStatsChartType::NpChart, vec!["NP", "np_chart"]),
+            (AttributeStatsChartType::CChart, vec!["c", "CChart"]),
+            (AttributeStatsChartType::UChart, vec!["U", "u chart"]),
+            (AttributeStatsChartType::DemeritChart, vec!["Demerit", "d"]),
+        ];
+        for (chart_type, aliases) in attribute {
+            assert_eq!(Ok(chart_type), chart_type.to_string().parse());
+            for alias in aliases {
+                assert_eq!(Ok(chart_type), alias.parse(), "{}", alias);
+            }
+        }
+        let moving = [
+            (
+                MovingStatsChartType::IndividualsChart,
+                vec!["imr", "I-MR", "X-MR", "i", "IndividualsChart"],
+            ),
+            (
+                MovingStatsChartType::MovingAverageChart,
+                vec!["MA", "moving-average"],
+            ),
+            (
+                MovingStatsChartType::MovingRangeChart,
+                vec!["mr", "MovingRangeChart"],
+            ),
+            (
+                MovingStatsChartType::EwmaIndividualsChart,
+                vec!["EWMA", "ewma_individuals"],
+            ),
+        ];
+        for (chart_type, aliases) in moving {
+            assert_eq!(Ok(chart_type), chart_type.to_string().parse());
+            for alias in aliases {
+                assert_eq!(Ok(chart_type), alias.parse(), "{}", alias);
+            }
+        }
+
+        let err = "xbar".parse::<GroupStatsChartType>().unwrap_err();
+        assert!(err.contains("\"xbar\""), "{}", err);
+        assert!("".parse::<AttributeStatsChartType>().is_err());
+        assert!("xbar_r".parse::<MovingStatsChartType>().is_err());
+    }
+
+    #[test]
+    fn test_limits_equal() {
+        assert!(limits_equal(0.824999999, 0.82, 2));
+        assert!(!limits_equal(0.824999999, 0.83, 2));
+        assert!(limits_equal(0.825, 0.83, 2));
+        assert!(limits_equal(0.825000001, 0.83, 2));
+        assert!(!limits_equal(0.824999999, 0.825, 2));
+        assert!(limits_equal(-0.825, -0.83, 2));
+        assert!(limits_equal(0.8249999, 0.825, 3));
+        assert!(limits_equal(f64::INFINITY, f64::INFINITY, 2));
+        assert!(!limits_equal(f64::NAN, f64::NAN, 2));
+        assert!(!limits_equal(f64::NAN, 0.82, 2));
+    }
+
+    #[test]
+    fn test_rule_validate() {
+        let valid = [
+            SpcRule::Rule1Beyond3Sigma(1, 3),
+            SpcRule::Rule2Of3Beyond2Sigma(2, 3, 2),
+            SpcRule::Rule4Of5Beyond1Sigma(4, 5, 1),
+            SpcRule::Rule6PointsUpOrDown(6),
+            SpcRule::Rule15PointsWithin1Sigma(15, 1),
+            SpcRule::RuleNPointsTrendingTowardLimit(2),
+            SpcRule::RuleNPointsNearCenter(8, 10),
+            SpcRule::RuleNIdenticalPoints(2),
+        ];
+        assert!(valid.iter().all(|rule| rule.validate().is_ok()));
+
+        let invalid = [
+            SpcRule::Rule1Beyond3Sigma(0, 3),
+            SpcRule::Rule1Beyond3Sigma(1, 0),
+            SpcRule::Rule2Of3Beyond2Sigma(5, 3, 2),
+            SpcRule::Rule2Of3Beyond2Sigma(0, 0, 2),
+            SpcRule::Rule4Of5Beyond1Sigma(4, 5, 0),
+            SpcRule::Rule6PointsUpOrDown(0),
+            SpcRule::Rule8PointsAboveOrBelowCenter(0),
+            SpcRule::Rule9PointsOnSameSideOfCenter(0),
+            SpcRule::Rule14PointsOscillating(0),
+            SpcRule::Rule15PointsWithin1Sigma(15, 0),
+            SpcRule::RuleNPointsTrendingTowardLimit(1),
+            SpcRule::RuleNPointsNearCenter(11, 10),
+            SpcRule::RuleNPointsNearCenter(0, 10),
+            SpcRule::RuleNIdenticalPoints(1),
+        ];
+        for rule in invalid {
+            assert!(rule.validate().is_err(), "{:?}", rule);
+        }
+        assert_eq!(
+            Err("Rule2Of3Beyond2Sigma(5, 3, 2): 5 points cannot fall in a window of 3".to_string()),
+            SpcRule::Rule2Of3Beyond2Sigma(5, 3, 2).validate()
+        );
+
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        stats.extend([1.0, 2.0, 3.0]);
+        assert!(
+            stats
+                .apply_rule_validation(vec![
+                    SpcRule::Rule1Beyond3Sigma(1, 3),
+                    SpcRule::Rule6PointsUpOrDown(0),
+                ])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_trend_helpers_short_slices() {
+        assert!(!is_increasing(&[]));
+        assert!(!is_increasing(&[1.0]));
+        assert!(!is_decreasing(&[]));
+        assert!(!is_decreasing(&[1.0]));
+        assert!(!is_monotonic_nondecreasing(&[]));
+        assert!(!is_monotonic_nondecreasing(&[1.0]));
+        assert!(!is_monotonic_nonincreasing(&[]));
+        assert!(!is_monotonic_nonincreasing(&[1.0]));
+        assert!(!is_alternating(&[]));
+        assert!(!is_alternating(&[1.0]));
+        assert!(!is_alternating(&[1.0, 2.0]));
+    }
+
+    #[test]
+    fn test_trend_helpers() {
+        assert!(is_increasing(&[1.0, 2.0, 3.0]));
+        assert!(is_decreasing(&[3.0, 2.0, 1.0]));
+        assert!(is_alternating(&[1.0, 3.0, 2.0, 4.0]));
+        assert!(!is_alternating(&[1.0, 3.0, 4.0, 2.0]));
+
+        let plateau = [1.0, 2.0, 2.0, 3.0];
+        assert!(!is_increasing(&plateau));
+        assert!(!is_decreasing(&plateau));
+        assert!(is_monotonic_nondecreasing(&plateau));
+        assert!(!is_monotonic_nondecreasing(&[1.0, 2.0, 1.5]));
+        assert!(is_monotonic_nonincreasing(&[3.0, 2.0, 2.0, 1.0]));
+        assert!(!is_monotonic_nonincreasing(&plateau));
+    }
 }
@@ -1,40 +1,77 @@
-use crate::RoundingContext;
+use crate::ascii_chart;
+use crate::constants::{D3, D4, d2, d4, e2};
+use crate::export::{ChartFamily, ControlChartSpec};
+use crate::rules::validate_rules;
 use crate::statistics::Statistics;
+use crate::{
+    ControlLimits, ForecastMethod, Rounding, RoundingContext, SpcRule, SpcRuleValidationResult,
+    TrendTies, chart_type_key, write_labeled,
+};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
 
-const A2: [f64; 11] = [
-    0.0, 0.0, 1.880, 1.187, 0.796, 0.691, 0.548, 0.508, 0.433, 0.412, 0.362,
-];
-const E2: [f64; 11] = [
-    0.0, 0.0, 2.660, 1.772, 1.457, 1.290, 1.184, 1.109, 1.054, 1.010, 0.975,
-];
-const d2: [f64; 11] = [
-    0.0, 0.0, 1.128, 1.693, 2.059, 2.326, 2.534, 2.704, 2.847, 2.97, 3.078,
-];
-const D3: [f64; 11] = [
-    0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.076, 0.136, 0.184, 0.223,
-];
-const D4: [f64; 11] = [
-    0.0, 0.0, 3.267, 2.574, 2.282, 2.114, 2.004, 1.924, 1.864, 1.816, 1.777,
-];
-
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum MovingStatsChartType {
+    #[default]
     IndividualsChart,
     MovingAverageChart,
     MovingRangeChart,
+    /// Exponentially weighted moving average of the individual values, see
+    /// `MovingStats::set_lambda`.
+    EwmaIndividualsChart,
 }
 
-#[derive(Debug)]
+impl FromStr for MovingStatsChartType {
+    type Err = String;
+
+    /// Parses a chart type name case-insensitively, ignoring separators, e.g. `"imr"`,
+    /// `"moving_range"` or `"EWMA"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match chart_type_key(s).as_str() {
+            "individuals" | "i" | "imr" | "xmr" | "individualschart" => {
+                Ok(MovingStatsChartType::IndividualsChart)
+            }
+            "movingaverage" | "ma" | "movingaveragechart" => {
+                Ok(MovingStatsChartType::MovingAverageChart)
+            }
+            "movingrange" | "mr" | "movingrangechart" => Ok(MovingStatsChartType::MovingRangeChart),
+            "ewma" | "ewmaindividuals" | "ewmaindividualschart" => {
+                Ok(MovingStatsChartType::EwmaIndividualsChart)
+            }
+            _ => Err(format!(
+                "Unknown moving chart type {:?}, expected one of individuals, moving_average, moving_range, ewma",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MovingStatsChartType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MovingStatsChartType::IndividualsChart => "individuals",
+            MovingStatsChartType::MovingAverageChart => "moving_average",
+            MovingStatsChartType::MovingRangeChart => "moving_range",
+            MovingStatsChartType::EwmaIndividualsChart => "ewma",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct MovingStats {
     cl: f64,
     ucl: f64,
     lcl: f64,
+    mr_cl: f64,
+    mr_ucl: f64,
+    mr_lcl: f64,
     pub chart_type: MovingStatsChartType,
     data: Vec<f64>,
     range_data: Vec<f64>,
     sub_group_size: usize,
     range_span_size: usize,
-    all_data: Vec<f64>,
     range: f64,
     stddev: f64,
     average: f64,
@@ -46,15 +83,21 @@ pub struct MovingStats {
     max_elements: usize,
     ucl_data: Vec<f64>,
     lcl_data: Vec<f64>,
+    ewma_data: Vec<f64>,
+    lambda: f64,
+    use_median_moving_range: bool,
+    trend_ties: TrendTies,
     rounding_ctx: Option<RoundingContext>,
 }
 
 impl MovingStats {
-    pub fn new(
-        sub_group_size: usize,
-        chart_type: MovingStatsChartType,
-    ) -> Result<Self, String> {
-        if sub_group_size < 2 || sub_group_size > 10 {
+    /// Creates an empty chart.
+    ///
+    /// `sub_group_size` must be in `2..=10` but is deprecated: the individuals, moving range
+    /// and EWMA charts ignore it and take their span from `set_range_span_size` (2 by
+    /// default). The `MovingAverageChart` is not implemented yet and charts no data.
+    pub fn new(sub_group_size: usize, chart_type: MovingStatsChartType) -> Result<Self, String> {
+        if !(2..=10).contains(&sub_group_size) {
             return Err("MovingStats: sub_group_size must be in range 2..10".to_string());
         }
 
@@ -62,12 +105,14 @@ impl MovingStats {
             cl: 0.0,
             ucl: 0.0,
             lcl: 0.0,
+            mr_cl: 0.0,
+            mr_ucl: 0.0,
+            mr_lcl: 0.0,
             chart_type,
             data: vec![],
             range_data: vec![],
             sub_group_size,
             range_span_size: 2,
-            all_data: vec![],
             range: 0.0,
             stddev: 0.0,
             average: 0.0,
@@ -79,23 +124,35 @@ impl MovingStats {
             max_elements: 100,
             ucl_data: vec![],
             lcl_data: vec![],
+            ewma_data: vec![],
+            lambda: 0.2,
+            use_median_moving_range: false,
+            trend_ties: TrendTies::Break,
             rounding_ctx: None,
         })
     }
 
-
-
     pub fn add_data(&mut self, value: f64) {
         self.data.push(value);
         if self.data.len() > self.max_elements {
             self.data.remove(0);
         }
+        self.dirty = true;
+    }
+
+    /// Adds each value with `add_data`.
+    pub fn extend<I: IntoIterator<Item = f64>>(&mut self, iter: I) {
+        for value in iter {
+            self.add_data(value);
+        }
     }
 
     pub fn update(&mut self) {
         if !self.dirty {
             return;
         }
+        self.ucl_data.clear();
+        self.lcl_data.clear();
 
         match self.chart_type {
             MovingStatsChartType::IndividualsChart => {
@@ -107,24 +164,26 @@ impl MovingStats {
                 if !self.data.is_empty() {
                     self.median = self.data.median();
                 }
-                self.range_data.clear();
-                let mut vec = vec![];
-                for i in 0..self.data.len() {
-                    vec.clear();
-                    if i < self.range_span_size - 1 {
-                        self.range_data.push(f64::NAN);
-                    } else {
-                        for j in 0..self.range_span_size {
-                            let index = i - j;
-                            vec.push(self.data[index]);
-                        }
-                        self.range_data.push(vec.range());
-                    }
-                }
-                let range_average = self.range_data.average();
-                self.sigma_estimate = range_average / d2[self.range_span_size];
-                let ucl = self.average + E2[self.range_span_size] * range_average;
-                let lcl = self.average - E2[self.range_span_size] * range_average;
+                self.fill_range_data();
+                let defined_ranges = self.defined_range_data();
+                self.update_mr_limits(defined_ranges.average());
+                let (ucl, lcl) = if self.use_median_moving_range {
+                    self.sigma_estimate = defined_ranges.median() / d4(self.range_span_size);
+                    (
+                        self.average + 3.0 * self.sigma_estimate,
+                        self.average - 3.0 * self.sigma_estimate,
+                    )
+                } else {
+                    let range_average = defined_ranges.average();
+                    self.sigma_estimate = range_average / d2(self.range_span_size);
+                    (
+                        self.average + e2(self.range_span_size) * range_average,
+                        self.average - e2(self.range_span_size) * range_average,
+                    )
+                };
+                self.cl = self.average;
+                self.ucl = ucl;
+                self.lcl = lcl;
                 for _ in 0..self.range_data.len() {
                     self.ucl_data.push(ucl);
                     self.lcl_data.push(lcl);
@@ -135,47 +194,712 @@ impl MovingStats {
                 // todo
             }
             MovingStatsChartType::MovingRangeChart => {
-                self.range_data.clear();
-                let mut vec = vec![];
-                for i in 0..self.data.len() {
-                    vec.clear();
-                    if i < self.range_span_size - 1 {
-                        self.range_data.push(f64::NAN);
-                    } else {
-                        for j in 0..self.range_span_size {
-                            let index = i - j;
-                            vec.push(self.data[index]);
-                        }
-                        self.range_data.push(vec.range());
-                    }
+                self.fill_range_data();
+                let defined_ranges = self.defined_range_data();
+                self.average = defined_ranges.average();
+                self.minimum = defined_ranges.min();
+                self.maximum = defined_ranges.max();
+                self.stddev = defined_ranges.std_dev();
+                self.range = defined_ranges.range();
+                if !defined_ranges.is_empty() {
+                    self.median = defined_ranges.median();
                 }
-                self.average = self.range_data.average();
-                self.minimum = self.range_data.min();
-                self.maximum = self.range_data.max();
-                self.stddev = self.range_data.std_dev();
-                self.range = self.range_data.range();
-                if !self.range_data.is_empty() {
-                    self.median = self.range_data.median();
-                }
-                self.sigma_estimate = self.average / d2[self.range_span_size];
-                let ucl = D4[self.range_span_size] * self.average;
-                let lcl = D3[self.range_span_size] * self.average;
+                self.sigma_estimate = self.average / d2(self.range_span_size);
+                let ucl = D4(self.range_span_size) * self.average;
+                let lcl = D3(self.range_span_size) * self.average;
+                self.cl = self.average;
+                self.ucl = ucl;
+                self.lcl = lcl;
+                self.update_mr_limits(self.average);
                 for _ in 0..self.range_data.len() {
                     self.ucl_data.push(ucl);
                     self.lcl_data.push(lcl);
                 }
             }
+            MovingStatsChartType::EwmaIndividualsChart => {
+                self.average = self.data.average();
+                self.minimum = self.data.min();
+                self.maximum = self.data.max();
+                self.stddev = self.data.std_dev();
+                self.range = self.data.range();
+                if !self.data.is_empty() {
+                    self.median = self.data.median();
+                }
+                self.fill_range_data();
+                let defined_ranges = self.defined_range_data();
+                self.sigma_estimate = defined_ranges.average() / d2(self.range_span_size);
+
+                let lambda = self.lambda;
+                let width = 3.0 * self.sigma_estimate * (lambda / (2.0 - lambda)).sqrt();
+                self.cl = self.average;
+                self.ucl = self.average + width;
+                self.lcl = self.average - width;
+                self.ewma_data.clear();
+                let mut z = self.average;
+                for (i, &x) in self.data.iter().enumerate() {
+                    z = lambda * x + (1.0 - lambda) * z;
+                    self.ewma_data.push(z);
+                    let factor = (1.0 - (1.0 - lambda).powi(2 * (i as i32 + 1))).sqrt();
+                    self.ucl_data.push(self.average + width * factor);
+                    self.lcl_data.push(self.average - width * factor);
+                }
+            }
+        }
+        if let Some(ctx) = &self.rounding_ctx {
+            self.cl = self.cl.scale(ctx.scale, &ctx.rounding_mode);
+            self.ucl = self.ucl.scale(ctx.scale, &ctx.rounding_mode);
+            self.lcl = self.lcl.scale(ctx.scale, &ctx.rounding_mode);
+            self.mr_cl = self.mr_cl.scale(ctx.scale, &ctx.rounding_mode);
+            self.mr_ucl = self.mr_ucl.scale(ctx.scale, &ctx.rounding_mode);
+            self.mr_lcl = self.mr_lcl.scale(ctx.scale, &ctx.rounding_mode);
+            self.sigma_estimate = self.sigma_estimate.scale(ctx.scale, &ctx.rounding_mode);
+            self.average = self.average.scale(ctx.scale, &ctx.rounding_mode);
+            self.median = self.median.scale(ctx.scale, &ctx.rounding_mode);
+            for v in self
+                .range_data
+                .iter_mut()
+                .chain(self.ucl_data.iter_mut())
+                .chain(self.lcl_data.iter_mut())
+                .chain(self.ewma_data.iter_mut())
+                .filter(|v| !v.is_nan())
+            {
+                *v = v.scale(ctx.scale, &ctx.rounding_mode);
+            }
+        }
+        self.dirty = false;
+    }
+
+    /// The moving range chart limits `MR̄`, `D4 * MR̄` and `D3 * MR̄`.
+    fn update_mr_limits(&mut self, range_average: f64) {
+        self.mr_cl = range_average;
+        self.mr_ucl = D4(self.range_span_size) * range_average;
+        self.mr_lcl = D3(self.range_span_size) * range_average;
+    }
+
+    fn fill_range_data(&mut self) {
+        self.range_data.clear();
+        let mut vec = vec![];
+        for i in 0..self.data.len() {
+            vec.clear();
+            if i < self.range_span_size - 1 {
+                self.range_data.push(f64::NAN);
+            } else {
+                for j in 0..self.range_span_size {
+                    let index = i - j;
+                    vec.push(self.data[index]);
+                }
+                self.range_data.push(vec.range());
+            }
+        }
+    }
+
+    /// The plotted series: the individual values, the moving ranges or the EWMA values.
+    pub fn chart_data(&mut self) -> Vec<f64> {
+        self.update();
+        match self.chart_type {
+            MovingStatsChartType::IndividualsChart => self.data.to_vec(),
+            MovingStatsChartType::MovingAverageChart => vec![],
+            MovingStatsChartType::MovingRangeChart => self.range_data.to_vec(),
+            MovingStatsChartType::EwmaIndividualsChart => self.ewma_data.to_vec(),
+        }
+    }
+
+    /// Updates the chart and predicts the next point of `chart_data`, see `ForecastMethod`.
+    pub fn forecast_next(&mut self, method: ForecastMethod) -> f64 {
+        method.forecast(&self.chart_data())
+    }
+
+    /// Updates the chart and renders the last `width` points of `chart_data` with the
+    /// centerline and control limits as a `height` line text plot, marking points outside
+    /// the limits with `*`.
+    pub fn to_ascii_chart(&mut self, width: usize, height: usize) -> String {
+        self.update();
+        let chart_data = self.chart_data();
+        ascii_chart::render(&chart_data, self.cl, self.ucl, self.lcl, width, height)
+    }
+
+    /// Applies `rules` to `chart_data`, with `sigma = (ucl - average) / 3`.
+    ///
+    /// The undefined (`NAN`) lead-in of a moving range chart never violates a rule.
+    ///
+    /// Fails if any rule has invalid parameters, see `SpcRule::validate`.
+    pub fn apply_rule_validation(
+        &mut self,
+        rules: Vec<SpcRule>,
+    ) -> Result<Vec<SpcRuleValidationResult>, String> {
+        let chart_data = self.chart_data();
+        let sigma = (self.ucl - self.average) / 3.0;
+        validate_rules(
+            rules,
+            &chart_data,
+            self.average,
+            sigma,
+            self.ucl,
+            self.lcl,
+            &self.rounding_ctx,
+            self.trend_ties,
+        )
+    }
+
+    /// Applies `rules` and returns the indices of the plotted points violating any of them.
+    pub fn out_of_control_points(
+        &mut self,
+        rules: Vec<SpcRule>,
+    ) -> Result<BTreeSet<usize>, String> {
+        Ok(self
+            .apply_rule_validation(rules)?
+            .into_iter()
+            .flat_map(|res| res.bad_point_index)
+            .collect())
+    }
+
+    /// Updates the chart and returns, for each `chart_data` point, whether it lies within
+    /// its control limits. Undefined (`NAN`) points count as within.
+    pub fn control_status(&mut self) -> Vec<bool> {
+        let chart_data = self.chart_data();
+        chart_data
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let ucl = self.ucl_data.get(i).copied().unwrap_or(self.ucl);
+                let lcl = self.lcl_data.get(i).copied().unwrap_or(self.lcl);
+                !(x > ucl || x < lcl)
+            })
+            .collect()
+    }
+
+    /// Updates the chart and returns its centerline, limits and zone boundaries.
+    pub fn control_limits(&mut self) -> ControlLimits {
+        self.update();
+        let sigma = (self.ucl - self.cl) / 3.0;
+        ControlLimits::new(self.cl, self.ucl, self.lcl, sigma, &self.rounding_ctx)
+    }
+
+    /// Updates the chart and returns it in the shape shared by all chart families.
+    pub fn to_spec(&mut self) -> ControlChartSpec {
+        ControlChartSpec {
+            family: ChartFamily::Moving,
+            chart_type: self.chart_type.to_string(),
+            limits: self.control_limits(),
+            data: self.chart_data(),
+            violations: None,
+        }
+    }
+
+    pub fn lcl(&self) -> f64 {
+        self.lcl
+    }
+
+    pub fn ucl(&self) -> f64 {
+        self.ucl
+    }
+
+    pub fn cl(&self) -> f64 {
+        self.cl
+    }
+
+    /// The centerline `MR̄` of the moving range chart, filled by the individuals and moving
+    /// range charts so one object draws both halves of an I-MR pair.
+    pub fn mr_cl(&self) -> f64 {
+        self.mr_cl
+    }
+
+    /// The moving range chart upper limit `D4 * MR̄`, see `mr_cl`.
+    pub fn mr_ucl(&self) -> f64 {
+        self.mr_ucl
+    }
+
+    /// The moving range chart lower limit `D3 * MR̄`, see `mr_cl`.
+    pub fn mr_lcl(&self) -> f64 {
+        self.mr_lcl
+    }
+
+    /// The `sub_group_size` passed to `new`, unused by the charts, see `range_span_size`.
+    pub fn sub_group_size(&self) -> usize {
+        self.sub_group_size
+    }
+
+    pub fn range_span_size(&self) -> usize {
+        self.range_span_size
+    }
+
+    /// The maximum number of values kept, older ones are evicted.
+    pub fn max_elements(&self) -> usize {
+        self.max_elements
+    }
+
+    /// Keeps at most `max_elements` values, evicting the oldest ones right away if more
+    /// are stored.
+    pub fn set_max_elements(&mut self, max_elements: usize) {
+        self.max_elements = max_elements;
+        if self.data.len() > max_elements {
+            let excess = self.data.len() - max_elements;
+            self.data.drain(..excess);
+            self.dirty = true;
+        }
+    }
+
+    /// The number of stored values, at most `max_elements`.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn data(&self) -> Vec<f64> {
+        self.data.to_vec()
+    }
+
+    /// The moving ranges, the first `range_span_size - 1` entries are `NAN`.
+    pub fn range_data(&mut self) -> Vec<f64> {
+        self.update();
+        self.range_data.to_vec()
+    }
+
+    pub fn average(&mut self) -> f64 {
+        self.update();
+        self.average
+    }
+
+    pub fn median(&mut self) -> f64 {
+        self.update();
+        self.median
+    }
+
+    pub fn minimum(&mut self) -> f64 {
+        self.update();
+        self.minimum
+    }
+
+    pub fn maximum(&mut self) -> f64 {
+        self.update();
+        self.maximum
+    }
+
+    pub fn stddev(&mut self) -> f64 {
+        self.update();
+        self.stddev
+    }
+
+    pub fn range(&mut self) -> f64 {
+        self.update();
+        self.range
+    }
+
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// The moving ranges without the undefined (`NAN`) lead-in.
+    fn defined_range_data(&self) -> Vec<f64> {
+        self.range_data
+            .iter()
+            .copied()
+            .filter(|x| !x.is_nan())
+            .collect()
+    }
+
+    pub fn sigma_estimate(&self) -> f64 {
+        self.sigma_estimate
+    }
+
+    pub fn ucl_data(&self) -> Vec<f64> {
+        self.ucl_data.to_vec()
+    }
+
+    pub fn lcl_data(&self) -> Vec<f64> {
+        self.lcl_data.to_vec()
+    }
+
+    pub fn ewma_data(&self) -> Vec<f64> {
+        self.ewma_data.to_vec()
+    }
+
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    /// Sets the EWMA weight `0 < lambda <= 1` given to the newest value, 0.2 by default.
+    ///
+    /// The limits of the `EwmaIndividualsChart` start narrow at the first point and
+    /// widen towards the asymptotic
+    /// `average ± 3 * sigma_estimate * sqrt(lambda / (2 - lambda))`.
+    pub fn set_lambda(&mut self, lambda: f64) -> Result<(), String> {
+        if !(lambda > 0.0 && lambda <= 1.0) {
+            return Err("MovingStats: lambda must be in range (0, 1]".to_string());
         }
+        self.lambda = lambda;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn use_median_moving_range(&self) -> bool {
+        self.use_median_moving_range
+    }
+
+    /// Estimates sigma of the `IndividualsChart` from the median moving range
+    /// (`median(MR) / d4`) instead of the average moving range (`MR̄ / d2`).
+    ///
+    /// The median is robust against a few large jumps, which inflate `MR̄` and
+    /// thereby widen the limits.
+    pub fn set_use_median_moving_range(&mut self, use_median_moving_range: bool) {
+        self.use_median_moving_range = use_median_moving_range;
         self.dirty = true;
     }
 
-    pub fn set_range_span_size(&mut self, range_span_size: usize) {
+    /// Sets the number of consecutive values a moving range spans, in range 2..10.
+    ///
+    /// The span indexes the `d2`, `D3`, `D4`, `d4` and `E2` constants, whose moving range
+    /// tables end at 10. The pairwise span 2 is the usual individuals chart.
+    pub fn set_range_span_size(&mut self, range_span_size: usize) -> Result<(), String> {
+        if !(2..=10).contains(&range_span_size) {
+            return Err("MovingStats: range_span_size must be in range 2..10".to_string());
+        }
         self.range_span_size = range_span_size;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn rounding_ctx(&self) -> &Option<RoundingContext> {
+        &self.rounding_ctx
     }
 
     pub fn set_rounding_ctx(&mut self, rounding_ctx: Option<RoundingContext>) {
         self.rounding_ctx = rounding_ctx;
+        self.dirty = true;
     }
 
+    pub fn trend_ties(&self) -> TrendTies {
+        self.trend_ties
+    }
+
+    /// Sets whether equal consecutive points break a run of `Rule6PointsUpOrDown`, by
+    /// default they do.
+    pub fn set_trend_ties(&mut self, trend_ties: TrendTies) {
+        self.trend_ties = trend_ties;
+    }
+}
+
+/// A summary of the chart as of the last `update`: the chart type and point count, the
+/// limits and `sigma_estimate`. `{:.3}` rounds the values.
+impl fmt::Display for MovingStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} chart, {} points", self.chart_type, self.len())?;
+        if self.dirty {
+            write!(f, " (not updated)")?;
+        }
+        writeln!(f)?;
+        write_labeled(
+            f,
+            &[
+                ("CL", self.cl),
+                ("UCL", self.ucl),
+                ("LCL", self.lcl),
+                ("sigma", self.sigma_estimate),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_moving_stats {
+    use crate::RoundingMode::RoundHalfUp;
+    use crate::assert_almost_eq;
+    use crate::moving_stats::{MovingStats, MovingStatsChartType};
+    use crate::{ForecastMethod, RoundingContext, SpcRule, TrendTies};
+
+    #[test]
+    pub fn test_ewma_individuals_chart() {
+        let data = [10.0, 11.0, 9.0, 10.5, 12.0, 11.5];
+        let mut stats = MovingStats::new(2, MovingStatsChartType::EwmaIndividualsChart).unwrap();
+        assert!(stats.set_lambda(0.0).is_err());
+        stats.set_lambda(0.5).unwrap();
+        for value in data {
+            stats.add_data(value);
+        }
+        stats.update();
 
+        let average = 64.0 / 6.0;
+        // moving ranges 1.0, 2.0, 1.5, 1.5, 0.5
+        let sigma = 1.3 / 1.128;
+        assert_almost_eq!(stats.cl(), average, 1e-12);
+        assert_almost_eq!(stats.sigma_estimate(), sigma, 1e-12);
+
+        let ewma = stats.ewma_data();
+        assert_almost_eq!(ewma[0], 0.5 * 10.0 + 0.5 * average, 1e-12);
+        assert_almost_eq!(ewma[1], 0.5 * 11.0 + 0.5 * ewma[0], 1e-12);
+
+        let width = 3.0 * sigma * (0.5f64 / 1.5).sqrt();
+        let ucl_data = stats.ucl_data();
+        assert_almost_eq!(ucl_data[0], average + width * 0.75f64.sqrt(), 1e-12);
+        assert!(ucl_data.windows(2).all(|pair| pair[1] > pair[0]));
+        assert!(stats.ucl() > ucl_data[5]);
+        assert_almost_eq!(stats.ucl(), average + width, 1e-12);
+        assert_almost_eq!(stats.lcl_data()[0], average - width * 0.75f64.sqrt(), 1e-12);
+    }
+
+    #[test]
+    pub fn test_median_moving_range() {
+        let data = [10.0, 10.4, 9.8, 10.2, 10.0, 16.0, 10.1, 9.9, 10.3, 10.0];
+        let mut classic = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        let mut robust = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        robust.set_use_median_moving_range(true);
+        for value in data {
+            classic.add_data(value);
+            robust.add_data(value);
+        }
+        classic.update();
+        robust.update();
+
+        // moving ranges: 0.4, 0.6, 0.4, 0.2, 6.0, 5.9, 0.2, 0.4, 0.3
+        let average = 106.7 / 10.0;
+        let mr_average = 14.4 / 9.0;
+        assert_almost_eq!(classic.sigma_estimate(), mr_average / 1.128, 1e-12);
+        assert_almost_eq!(classic.ucl(), average + 2.660 * mr_average, 1e-12);
+        assert_almost_eq!(robust.sigma_estimate(), 0.4 / 0.954, 1e-12);
+        assert_almost_eq!(robust.ucl(), average + 3.0 * 0.4 / 0.954, 1e-12);
+        assert_almost_eq!(robust.lcl(), average - 3.0 * 0.4 / 0.954, 1e-12);
+
+        // the spike inflates the classic limits but not the robust ones
+        assert!(robust.ucl() - robust.lcl() < (classic.ucl() - classic.lcl()) / 3.0);
+        assert!(data[5] > robust.ucl());
+    }
+
+    #[test]
+    pub fn test_range_span_size() {
+        assert!(MovingStats::new(0, MovingStatsChartType::IndividualsChart).is_err());
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        assert!(stats.set_range_span_size(0).is_err());
+        assert!(stats.set_range_span_size(11).is_err());
+        assert_eq!(2, stats.range_span_size());
+
+        stats.set_range_span_size(3).unwrap();
+        for value in [1.0, 4.0, 2.0, 3.0] {
+            stats.add_data(value);
+        }
+        let range_data = stats.range_data();
+        assert!(range_data[0].is_nan() && range_data[1].is_nan());
+        assert_eq!(vec![3.0, 2.0], range_data[2..].to_vec());
+        assert!(!stats.dirty());
+
+        // moving ranges over three points 3.0, 2.0, 3.0 use d2 = 1.693
+        stats.add_data(5.0);
+        stats.update();
+        assert_almost_eq!(stats.sigma_estimate(), 8.0 / 3.0 / 1.693, 1e-12);
+        assert_almost_eq!(stats.ucl(), 3.0 + 1.772 * 8.0 / 3.0, 1e-12);
+    }
+
+    #[test]
+    pub fn test_range_span_size_boundary() {
+        // the last span the constant tables cover updates without panicking
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        stats.set_range_span_size(10).unwrap();
+        for i in 0..12 {
+            stats.add_data(i as f64);
+        }
+        stats.update();
+        assert!(stats.ucl().is_finite() && stats.sigma_estimate().is_finite());
+
+        let error = stats.set_range_span_size(11).unwrap_err();
+        assert!(error.contains("range_span_size"));
+        assert_eq!(10, stats.range_span_size());
+        assert!(!stats.dirty());
+    }
+
+    #[test]
+    pub fn test_rounding_ctx() {
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        for value in [10.123, 10.456, 9.87, 10.2, 10.05] {
+            stats.add_data(value);
+        }
+        stats.set_rounding_ctx(Some(RoundingContext::new(2, RoundHalfUp)));
+        stats.update();
+
+        // average 10.1398, moving ranges 0.333, 0.586, 0.33, 0.15 with average 0.34975
+        assert_eq!(10.14, stats.cl());
+        assert_eq!(10.14, stats.average());
+        assert_eq!(11.07, stats.ucl());
+        assert_eq!(9.21, stats.lcl());
+        assert_eq!(0.31, stats.sigma_estimate());
+        let range_data = stats.range_data();
+        assert!(range_data[0].is_nan());
+        assert_eq!(vec![0.33, 0.59, 0.33, 0.15], range_data[1..].to_vec());
+        assert_eq!(vec![11.07; 5], stats.ucl_data());
+
+        let limits = stats.control_limits();
+        assert_eq!(10.45, limits.upper_1s);
+        assert_eq!(9.52, limits.lower_2s);
+    }
+
+    #[test]
+    pub fn test_apply_rule_validation() {
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        stats.extend([
+            10.0, 10.2, 9.9, 10.1, 9.8, 10.0, 10.3, 10.1, 10.2, 10.4, 10.3, 10.5, 10.2, 14.0,
+        ]);
+        assert_eq!(14, stats.data().len());
+        let res = stats
+            .apply_rule_validation(vec![
+                SpcRule::Rule1Beyond3Sigma(1, 3),
+                SpcRule::Rule9PointsOnSameSideOfCenter(9),
+                SpcRule::Rule6PointsUpOrDown(6),
+            ])
+            .unwrap();
+        assert!(!res[0].validation_passed);
+        assert_eq!(vec![13], res[0].bad_point_index);
+        assert_eq!(vec![14.0], res[0].bad_point_data);
+        // the spike pulls the average above all earlier points
+        assert!(!res[1].validation_passed);
+        assert_eq!((0..11).collect::<Vec<usize>>(), res[1].bad_point_index);
+        assert!(res[2].validation_passed);
+    }
+
+    #[test]
+    pub fn test_to_ascii_chart() {
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        stats.extend([1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 10.0]);
+        let chart = stats.to_ascii_chart(3, 6);
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(6, lines.len());
+        assert!(lines.iter().all(|line| line.len() == 8));
+        assert_eq!("    |  *", lines[0]);
+        assert!(lines[1].starts_with("UCL |"));
+    }
+
+    #[test]
+    pub fn test_control_status() {
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        stats.extend([1.0, 2.0, 1.0, 2.0, 1.0, 2.0, 10.0]);
+        let mut status = vec![true; 6];
+        status.push(false);
+        assert_eq!(status, stats.control_status());
+    }
+
+    #[test]
+    pub fn test_mr_limits() {
+        let values = [10.0, 12.0, 11.0, 14.0, 13.0, 12.0];
+        let mut individuals = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        individuals.extend(values);
+        individuals.update();
+        // moving ranges 2, 1, 3, 1, 1
+        assert_almost_eq!(individuals.cl(), 12.0, 1e-12);
+        assert_almost_eq!(individuals.ucl(), 12.0 + 2.660 * 1.6, 1e-12);
+        assert_almost_eq!(individuals.mr_cl(), 1.6, 1e-12);
+        assert_almost_eq!(individuals.mr_ucl(), 3.267 * 1.6, 1e-12);
+        assert_eq!(0.0, individuals.mr_lcl());
+
+        // the median moving range only changes the individuals limits
+        individuals.set_use_median_moving_range(true);
+        individuals.update();
+        assert_almost_eq!(individuals.ucl(), 12.0 + 3.0 / 0.954, 1e-12);
+        assert_almost_eq!(individuals.mr_cl(), 1.6, 1e-12);
+    }
+
+    #[test]
+    pub fn test_identical_points() {
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        stats.extend([
+            10.2, 9.8, 10.1, 10.4, 10.4, 10.4, 10.4, 10.4, 9.9, 10.0, 10.0,
+        ]);
+        stats.update();
+        let res = stats
+            .apply_rule_validation(vec![
+                SpcRule::RuleNIdenticalPoints(5),
+                SpcRule::Rule1Beyond3Sigma(1, 3),
+            ])
+            .unwrap();
+        // the stuck run hides from the sigma rules
+        assert!(!res[0].validation_passed);
+        assert_eq!(vec![3, 4, 5, 6, 7], res[0].bad_point_index);
+        assert_eq!(vec![10.4; 5], res[0].bad_point_data);
+        assert!(res[1].validation_passed);
+
+        let res = stats
+            .apply_rule_validation(vec![SpcRule::RuleNIdenticalPoints(6)])
+            .unwrap();
+        assert!(res[0].validation_passed);
+        assert!(res[0].bad_point_index.is_empty());
+    }
+
+    #[test]
+    pub fn test_trend_ties() {
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        // a six point rise 9.7 ..= 10.3 with a plateau at 10.0
+        stats.extend([
+            10.2, 9.9, 9.7, 9.8, 10.0, 10.0, 10.1, 10.3, 9.9, 10.2, 10.0, 10.1,
+        ]);
+        stats.update();
+        let rule = vec![SpcRule::Rule6PointsUpOrDown(6)];
+        assert_eq!(TrendTies::Break, stats.trend_ties());
+        let res = stats.apply_rule_validation(rule.clone()).unwrap();
+        assert!(res[0].validation_passed);
+
+        stats.set_trend_ties(TrendTies::Continue);
+        let res = stats.apply_rule_validation(rule).unwrap();
+        assert!(!res[0].validation_passed);
+        assert_eq!(vec![2, 3, 4, 5, 6, 7], res[0].bad_point_index);
+    }
+
+    #[test]
+    pub fn test_moving_range_chart() {
+        let values = [10.0, 12.0, 11.0, 14.0, 13.0, 12.0];
+        let mut stats = MovingStats::new(2, MovingStatsChartType::MovingRangeChart).unwrap();
+        stats.set_rounding_ctx(Some(RoundingContext::new(3, RoundHalfUp)));
+        stats.extend(values);
+        stats.update();
+        // moving ranges 2, 1, 3, 1, 1 after the undefined first point
+        assert!(stats.chart_data()[0].is_nan());
+        assert!(stats.cl().is_finite());
+        assert_eq!(1.6, stats.cl());
+        assert_eq!(5.227, stats.ucl());
+        assert_eq!(0.0, stats.lcl());
+        assert_eq!(stats.cl(), stats.mr_cl());
+        assert_eq!(1.0, stats.median());
+
+        let mut individuals = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        individuals.set_rounding_ctx(Some(RoundingContext::new(3, RoundHalfUp)));
+        individuals.extend(values);
+        individuals.update();
+        assert_eq!(individuals.mr_ucl(), stats.ucl());
+    }
+
+    #[test]
+    pub fn test_forecast_next() {
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        stats.extend([10.0, 11.0, 9.0, 12.0]);
+        let mut ewma = MovingStats::new(2, MovingStatsChartType::EwmaIndividualsChart).unwrap();
+        ewma.extend(stats.data());
+        assert_almost_eq!(
+            stats.forecast_next(ForecastMethod::Ewma(0.2)),
+            *ewma.chart_data().last().unwrap(),
+            1e-12
+        );
+        // the line fitted to (0, 11), (1, 9), (2, 12) is 61 / 6 + 0.5 * x
+        assert_almost_eq!(
+            stats.forecast_next(ForecastMethod::LinearTrend(3)),
+            61.0 / 6.0 + 1.5,
+            1e-12
+        );
+    }
+
+    #[test]
+    pub fn test_window_size() {
+        let mut stats = MovingStats::new(2, MovingStatsChartType::IndividualsChart).unwrap();
+        assert!(stats.is_empty());
+        stats.extend((0..120).map(|i| i as f64));
+        assert_eq!(100, stats.max_elements());
+        assert_eq!(100, stats.len());
+        assert!(!stats.is_empty());
+
+        // shrinking the window evicts right away
+        stats.update();
+        stats.set_max_elements(10);
+        assert!(stats.dirty());
+        assert_eq!(10, stats.len());
+        assert_eq!(110.0, stats.data()[0]);
+        stats.update();
+        assert_eq!(114.5, stats.cl());
+    }
 }
@@ -1,7 +1,7 @@
 //! Provides utility functions for working with floating point precision
 
 use crate::ACC;
-use approx::AbsDiffEq;
+use approx::{AbsDiffEq, UlpsEq};
 
 /// Standard epsilon, maximum relative precision of IEEE 754 double-precision
 /// floating point numbers (64 bit) e.g. `2^-53`
@@ -19,6 +19,29 @@ pub fn almost_eq(a: f64, b: f64, acc: f64) -> bool {
     a.abs_diff_eq(&b, acc)
 }
 
+/// Compares if two floats are close relative to their magnitude, i.e.
+/// `|a - b| <= rel_tol * max(|a|, |b|)`.
+///
+/// Prefer this over `almost_eq` for large values such as control limits near
+/// `1e6`, where an absolute epsilon is smaller than the spacing of `f64`. Near
+/// zero prefer `almost_eq`: no value but zero itself is relatively close to zero.
+pub fn almost_eq_relative(a: f64, b: f64, rel_tol: f64) -> bool {
+    if a.is_infinite() && b.is_infinite() {
+        return a == b;
+    }
+    (a - b).abs() <= rel_tol * a.abs().max(b.abs())
+}
+
+/// Compares if two floats are at most `max_ulps` representable `f64` values
+/// apart via `approx::UlpsEq`.
+///
+/// Like `almost_eq_relative` this scales with magnitude, but counts the
+/// floating point steps between the values, e.g. to allow for the last bits of
+/// rounding error in a reordered sum. Values of opposite sign are never close.
+pub fn almost_eq_ulps(a: f64, b: f64, max_ulps: u32) -> bool {
+    a.ulps_eq(&b, 0.0, max_ulps)
+}
+
 /// Compares if two floats are close via `approx::relative_eq!`
 /// and `crate::consts::ACC` relative precision.
 /// Updates first argument to value of second argument
@@ -27,3 +50,45 @@ pub fn convergence(x: &mut f64, x_new: f64) -> bool {
     *x = x_new;
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::prec::{almost_eq, almost_eq_relative, almost_eq_ulps};
+
+    #[test]
+    fn test_almost_eq_relative() {
+        // an absolute epsilon is below the f64 spacing around 1e6
+        let limit = (0.1 + 0.2) * 1e6;
+        assert!(!almost_eq(limit, 300_000.0, 1e-12));
+        assert!(almost_eq_relative(limit, 300_000.0, 1e-12));
+        assert!(!almost_eq_relative(1e6, 1.00001e6, 1e-6));
+
+        // relative comparison fails near zero, absolute works
+        assert!(!almost_eq_relative(1e-20, 0.0, 1e-6));
+        assert!(almost_eq(1e-20, 0.0, 1e-12));
+        assert!(almost_eq_relative(0.0, 0.0, 1e-12));
+        assert!(almost_eq_relative(f64::INFINITY, f64::INFINITY, 1e-12));
+        assert!(!almost_eq_relative(f64::NAN, f64::NAN, 1e-12));
+    }
+
+    #[test]
+    fn test_almost_eq_ulps() {
+        let a = 0.1 + 0.2;
+        assert!(almost_eq_ulps(a, 0.3, 1));
+        assert!(!almost_eq_ulps(a, 0.3, 0));
+        assert!(almost_eq_ulps(1e6, 1e6 + 2.0 * f64::EPSILON * 1e6, 4));
+        assert!(almost_eq_ulps(1e-300, 1e-300, 0));
+        assert!(!almost_eq_ulps(1e-300, -1e-300, 1000));
+    }
+
+    #[test]
+    fn test_assert_almost_eq_rel() {
+        crate::assert_almost_eq_rel!((0.1 + 0.2) * 1e6, 300_000.0, 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_almost_eq_rel_fails() {
+        crate::assert_almost_eq_rel!(1e6, 1.001e6, 1e-6);
+    }
+}
@@ -0,0 +1,103 @@
+//! Control chart images, available with the `plotters` feature.
+
+use crate::{ControlLimits, SpcRule};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::collections::BTreeSet;
+
+/// Size, title and rule overlay of a rendered chart.
+#[derive(Debug, Clone)]
+pub struct ChartRenderOptions {
+    pub width: u32,
+    pub height: u32,
+    pub title: String,
+    /// Rules whose violating points are highlighted, none by default.
+    pub rules: Vec<SpcRule>,
+}
+
+impl Default for ChartRenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 480,
+            title: String::new(),
+            rules: vec![],
+        }
+    }
+}
+
+/// Draws `chart_data` over the 1σ/2σ zone bands with the centerline and control limits,
+/// the points at `violations` in red.
+pub(crate) fn draw_chart<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    chart_data: &[f64],
+    limits: &ControlLimits,
+    violations: &BTreeSet<usize>,
+) -> Result<(), String> {
+    root.fill(&WHITE).map_err(|e| e.to_string())?;
+
+    let (low, high) = chart_data
+        .iter()
+        .chain([limits.ucl, limits.lcl].iter())
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &v| {
+            (low.min(v), high.max(v))
+        });
+    let padding = if high > low { (high - low) * 0.05 } else { 1.0 };
+    let last = chart_data.len().saturating_sub(1).max(1) as f64;
+
+    let mut chart = ChartBuilder::on(root)
+        .caption(title, ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0.0..last, (low - padding)..(high + padding))
+        .map_err(|e| e.to_string())?;
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .draw()
+        .map_err(|e| e.to_string())?;
+
+    let bands = [
+        (limits.upper_2s, limits.ucl, RED.mix(0.08)),
+        (limits.upper_1s, limits.upper_2s, YELLOW.mix(0.12)),
+        (limits.lower_1s, limits.upper_1s, GREEN.mix(0.08)),
+        (limits.lower_2s, limits.lower_1s, YELLOW.mix(0.12)),
+        (limits.lcl, limits.lower_2s, RED.mix(0.08)),
+    ];
+    chart
+        .draw_series(bands.into_iter().map(|(bottom, top, color)| {
+            Rectangle::new([(0.0, bottom), (last, top)], color.filled())
+        }))
+        .map_err(|e| e.to_string())?;
+
+    for (value, color) in [(limits.cl, GREEN), (limits.ucl, RED), (limits.lcl, RED)] {
+        chart
+            .draw_series(LineSeries::new([(0.0, value), (last, value)], color))
+            .map_err(|e| e.to_string())?;
+    }
+
+    let points: Vec<(f64, f64)> = chart_data
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| v.is_finite())
+        .map(|(i, &v)| (i as f64, v))
+        .collect();
+    chart
+        .draw_series(LineSeries::new(points.iter().copied(), BLUE))
+        .map_err(|e| e.to_string())?;
+    chart
+        .draw_series(points.iter().map(|&(x, y)| {
+            let color = if violations.contains(&(x as usize)) {
+                RED
+            } else {
+                BLUE
+            };
+            Circle::new((x, y), 3, color.filled())
+        }))
+        .map_err(|e| e.to_string())?;
+
+    root.present().map_err(|e| e.to_string())
+}
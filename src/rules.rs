@@ -0,0 +1,322 @@
+use crate::statistics::Statistics;
+use crate::{
+    Rounding, RoundingContext, SpcRule, SpcRuleValidationResult, TrendTies, is_alternating,
+    is_decreasing, is_increasing, is_monotonic_nondecreasing, is_monotonic_nonincreasing,
+};
+
+/// Applies `rules` to a plotted series.
+///
+/// `chart_average` and `sigma` place the σ zones, `ucl_limit` and `lcl_limit` are the
+/// control limits the trend rule projects towards. Zone boundaries are rounded with
+/// `rounding_ctx`, like the chart values they are compared to. `trend_ties` decides
+/// whether equal points break a run of `Rule6PointsUpOrDown`. Fails on the first rule
+/// whose parameters `SpcRule::validate` rejects, before applying any.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn validate_rules(
+    rules: Vec<SpcRule>,
+    chart_data: &[f64],
+    chart_average: f64,
+    sigma: f64,
+    ucl_limit: f64,
+    lcl_limit: f64,
+    rounding_ctx: &Option<RoundingContext>,
+    trend_ties: TrendTies,
+) -> Result<Vec<SpcRuleValidationResult>, String> {
+    for rule in &rules {
+        rule.validate()?;
+    }
+    let mut res = vec![];
+    for rule in rules {
+        let mut bad_point_index = vec![];
+        let mut passed = true;
+        match rule {
+            SpcRule::Rule1Beyond3Sigma(p, s) => {
+                let mut ucl = chart_average + s as f64 * sigma;
+                let mut lcl = chart_average - s as f64 * sigma;
+                if let Some(ctx) = rounding_ctx {
+                    ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
+                    lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
+                }
+                for (index, &value) in chart_data.iter().enumerate() {
+                    if value > ucl || value < lcl {
+                        bad_point_index.push(index);
+                    }
+                }
+                if bad_point_index.len() >= p {
+                    passed = false;
+                }
+                if passed {
+                    bad_point_index.clear();
+                }
+            }
+
+            SpcRule::Rule2Of3Beyond2Sigma(p, n, s) | SpcRule::Rule4Of5Beyond1Sigma(p, n, s) => {
+                if chart_data.len() >= n {
+                    let mut ucl = chart_average + s as f64 * sigma;
+                    let mut lcl = chart_average - s as f64 * sigma;
+                    if let Some(ctx) = rounding_ctx {
+                        ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
+                        lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
+                    }
+                    for i in 0..chart_data.len().saturating_sub(n - 1) {
+                        let window = &chart_data[i..i + n]; // Take n consecutive elements
+                        let count = window.iter().filter(|&&x| x > ucl || x < lcl).count();
+                        if count >= p {
+                            passed = false;
+                            for (offset, &value) in window.iter().enumerate() {
+                                if (value > ucl || value < lcl)
+                                    && !bad_point_index.contains(&(offset + i))
+                                {
+                                    bad_point_index.push(i + offset);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            SpcRule::Rule6PointsUpOrDown(p) => {
+                if chart_data.len() >= p {
+                    for i in 0..chart_data.len().saturating_sub(p - 1) {
+                        let window = &chart_data[i..i + p];
+                        let trending = match trend_ties {
+                            TrendTies::Break => is_increasing(window) || is_decreasing(window),
+                            TrendTies::Continue => {
+                                let (first, last) = (window[0], window[p - 1]);
+                                (is_monotonic_nondecreasing(window) && last > first)
+                                    || (is_monotonic_nonincreasing(window) && last < first)
+                            }
+                        };
+                        if trending {
+                            passed = false;
+                            for j in 0..window.len() {
+                                if !bad_point_index.contains(&(i + j)) {
+                                    bad_point_index.push(i + j);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            SpcRule::Rule8PointsAboveOrBelowCenter(p) => {
+                let mut ucl = chart_average + sigma;
+                let mut lcl = chart_average - sigma;
+                if let Some(ctx) = rounding_ctx {
+                    ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
+                    lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
+                }
+                if chart_data.len() >= p {
+                    for i in 0..chart_data.len().saturating_sub(p - 1) {
+                        let window = &chart_data[i..i + p];
+                        let count = window.iter().filter(|&&x| x > ucl || x < lcl).count();
+                        if count >= p {
+                            passed = false;
+                            for (offset, &value) in window.iter().enumerate() {
+                                if (value > ucl || value < lcl)
+                                    && !bad_point_index.contains(&(i + offset))
+                                {
+                                    bad_point_index.push(i + offset);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            SpcRule::Rule9PointsOnSameSideOfCenter(p) => {
+                if chart_data.len() >= p {
+                    for i in 0..chart_data.len().saturating_sub(p - 1) {
+                        let window = &chart_data[i..i + p];
+                        let flag = window.iter().all(|&x| x < chart_average)
+                            || window.iter().all(|&x| x > chart_average);
+                        if flag {
+                            passed = false;
+                            for offset in 0..window.len() {
+                                if !bad_point_index.contains(&(i + offset)) {
+                                    bad_point_index.push(i + offset);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            SpcRule::Rule14PointsOscillating(p) => {
+                if chart_data.len() >= p {
+                    for i in 0..chart_data.len().saturating_sub(p - 1) {
+                        let window = &chart_data[i..i + p];
+                        if is_alternating(window) {
+                            passed = false;
+                            for j in 0..window.len() {
+                                if !bad_point_index.contains(&(i + j)) {
+                                    bad_point_index.push(i + j);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            SpcRule::Rule15PointsWithin1Sigma(p, s) => {
+                if chart_data.len() >= p {
+                    let mut ucl = chart_average + s as f64 * sigma;
+                    let mut lcl = chart_average - s as f64 * sigma;
+                    if let Some(ctx) = rounding_ctx {
+                        ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
+                        lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
+                    }
+                    for i in 0..chart_data.len().saturating_sub(p - 1) {
+                        let window = &chart_data[i..i + p];
+                        let count = window.iter().filter(|&&x| x < ucl && x > lcl).count();
+                        if count >= p {
+                            passed = false;
+                            for (offset, &value) in window.iter().enumerate() {
+                                if value < ucl
+                                    && value > lcl
+                                    && !bad_point_index.contains(&(i + offset))
+                                {
+                                    bad_point_index.push(i + offset);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            SpcRule::RuleNPointsTrendingTowardLimit(n) => {
+                if n >= 2 && chart_data.len() >= n {
+                    let x: Vec<f64> = (0..n).map(|i| i as f64).collect();
+                    for i in 0..chart_data.len().saturating_sub(n - 1) {
+                        let window = &chart_data[i..i + n];
+                        let (slope, intercept) = x.linear_regression(window);
+                        let projected = slope * n as f64 + intercept;
+                        if (is_increasing(window) && projected > ucl_limit)
+                            || (is_decreasing(window) && projected < lcl_limit)
+                        {
+                            passed = false;
+                            for j in 0..window.len() {
+                                if !bad_point_index.contains(&(i + j)) {
+                                    bad_point_index.push(i + j);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            SpcRule::RuleNPointsNearCenter(p, n) => {
+                if n >= 1 && chart_data.len() >= n {
+                    let mut ucl = chart_average + 0.5 * sigma;
+                    let mut lcl = chart_average - 0.5 * sigma;
+                    if let Some(ctx) = rounding_ctx {
+                        ucl = ucl.scale(ctx.scale, &ctx.rounding_mode);
+                        lcl = lcl.scale(ctx.scale, &ctx.rounding_mode);
+                    }
+                    for i in 0..chart_data.len().saturating_sub(n - 1) {
+                        let window = &chart_data[i..i + n];
+                        let count = window.iter().filter(|&&x| x <= ucl && x >= lcl).count();
+                        if count >= p {
+                            passed = false;
+                            for (offset, &value) in window.iter().enumerate() {
+                                if value <= ucl
+                                    && value >= lcl
+                                    && !bad_point_index.contains(&(i + offset))
+                                {
+                                    bad_point_index.push(i + offset);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            SpcRule::RuleNIdenticalPoints(n) => {
+                for i in 0..chart_data.len().saturating_sub(n - 1) {
+                    let window = &chart_data[i..i + n];
+                    if window.iter().all(|&x| x == window[0]) {
+                        passed = false;
+                        for j in 0..window.len() {
+                            if !bad_point_index.contains(&(i + j)) {
+                                bad_point_index.push(i + j);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut bad_point_data = vec![];
+        for (i, &value) in chart_data.iter().enumerate() {
+            if bad_point_index.contains(&i) {
+                bad_point_data.push(value);
+            }
+        }
+        res.push(SpcRuleValidationResult {
+            rule,
+            bad_point_index,
+            bad_point_data,
+            validation_passed: passed,
+        });
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test_rules {
+    use crate::rules::validate_rules;
+    use crate::{SpcRule, SpcRuleValidationResult, TrendTies};
+
+    /// Applies `rule` to `data` on a chart centered on 0 with sigma 1 and 3σ limits.
+    fn apply(rule: SpcRule, data: &[f64]) -> SpcRuleValidationResult {
+        validate_rules(
+            vec![rule],
+            data,
+            0.0,
+            1.0,
+            3.0,
+            -3.0,
+            &None,
+            TrendTies::Break,
+        )
+        .unwrap()
+        .remove(0)
+    }
+
+    #[test]
+    pub fn test_rule8_flags_only_points_beyond_1_sigma() {
+        let rule = SpcRule::Rule8PointsAboveOrBelowCenter(8);
+        let mut data = vec![0.5, 0.2];
+        data.extend([1.5, -1.5].repeat(4));
+        let result = apply(rule, &data);
+        assert!(!result.validation_passed);
+        assert_eq!((2..10).collect::<Vec<usize>>(), result.bad_point_index);
+
+        // one point within 1σ breaks the run
+        data[6] = 0.5;
+        assert!(apply(rule, &data).validation_passed);
+    }
+
+    #[test]
+    pub fn test_rule9_needs_one_side() {
+        let rule = SpcRule::Rule9PointsOnSameSideOfCenter(9);
+        // off the centerline but on both sides of it
+        let alternating = [0.5, -0.5].repeat(5);
+        let result = apply(rule, &alternating);
+        assert!(result.validation_passed);
+        assert!(result.bad_point_index.is_empty());
+
+        let mut above = vec![-0.5];
+        above.extend([0.5; 9]);
+        let result = apply(rule, &above);
+        assert!(!result.validation_passed);
+        assert_eq!((1..10).collect::<Vec<usize>>(), result.bad_point_index);
+    }
+
+    #[test]
+    pub fn test_rule15_needs_points_within_1_sigma() {
+        let rule = SpcRule::Rule15PointsWithin1Sigma(15, 1);
+        let spread = [2.0, -2.0, 1.5, -1.5, 0.5].repeat(3);
+        let result = apply(rule, &spread);
+        assert!(result.validation_passed);
+        assert!(result.bad_point_index.is_empty());
+
+        let mut hugging = vec![2.5];
+        hugging.extend([0.5, -0.5, 0.2].repeat(5));
+        let result = apply(rule, &hugging);
+        assert!(!result.validation_passed);
+        assert_eq!((1..16).collect::<Vec<usize>>(), result.bad_point_index);
+    }
+}
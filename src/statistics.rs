@@ -1,8 +1,45 @@
+use crate::distribution::{normal_cdf, normal_inverse_cdf, student_t_inverse_cdf};
 use crate::error::StatsError;
-use num_traits::float::FloatCore;
-use num_traits::real::Real;
-use num_traits::Pow;
-use std::borrow::Borrow;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "std"), feature = "libm"))]
+#[allow(unused_imports)]
+use num_traits::Float;
+#[cfg(feature = "rand")]
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "rand")]
+use rand_chacha::ChaCha8Rng;
+
+/// The rule `Statistics::auto_histogram` picks the bin count by
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BinRule {
+    /// `ceil(log2(N)) + 1` bins, for roughly normal data
+    Sturges,
+    /// Bins of width `3.49 * s * N^(-1/3)`, with `s` the sample standard deviation
+    Scott,
+    /// Bins of width `2 * IQR * N^(-1/3)`, robust to outliers
+    FreedmanDiaconis,
+}
+
+/// The common descriptive statistics of a data set, see `Statistics::describe`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    /// The sample standard deviation, as `Statistics::std_dev`
+    pub std_dev: f64,
+    pub min: f64,
+    /// The 0.25 quantile, as `Statistics::quantile`
+    pub q1: f64,
+    pub median: f64,
+    /// The 0.75 quantile, as `Statistics::quantile`
+    pub q3: f64,
+    pub max: f64,
+    /// The moment skewness, as `Statistics::skewness`
+    pub skewness: f64,
+    /// The excess moment kurtosis, as `Statistics::kurtosis`
+    pub kurtosis: f64,
+}
 
 /// The `Statistics` trait provides a host of statistical utilities for
 /// analyzing
@@ -20,13 +57,13 @@ pub trait Statistics {
     /// use std::f64;
     /// use spc_rs::statistics::Statistics;
     ///
-    /// let x = &[];
+    /// let x: &[f64] = &[];
     /// assert!(Statistics::min(x).is_nan());
     ///
-    /// let y = &[0.0, f64::NAN, 3.0, -2.0];
+    /// let y: &[f64] = &[0.0, f64::NAN, 3.0, -2.0];
     /// assert!(Statistics::min(y).is_nan());
     ///
-    /// let z = &[0.0, 3.0, -2.0];
+    /// let z: &[f64] = &[0.0, 3.0, -2.0];
     /// assert_eq!(Statistics::min(z), -2.0);
     /// ```
     fn min(&self) -> f64;
@@ -43,13 +80,13 @@ pub trait Statistics {
     /// use std::f64;
     /// use spc_rs::statistics::Statistics;
     ///
-    /// let x = &[];
+    /// let x: &[f64] = &[];
     /// assert!(Statistics::max(x).is_nan());
     ///
-    /// let y = &[0.0, f64::NAN, 3.0, -2.0];
+    /// let y: &[f64] = &[0.0, f64::NAN, 3.0, -2.0];
     /// assert!(Statistics::max(y).is_nan());
     ///
-    /// let z = &[0.0, 3.0, -2.0];
+    /// let z: &[f64] = &[0.0, 3.0, -2.0];
     /// assert_eq!(Statistics::max(z), 3.0);
     /// ```
     fn max(&self) -> f64;
@@ -214,23 +251,28 @@ pub trait Statistics {
     /// On a dataset of size `N`, `N-1` is used as a normalizer (Bessel's
     /// correction).
     ///
-    /// Returns `f64::NAN` if data has less than two entries or if any entry is
-    /// `f64::NAN`
+    /// Returns `0.0` if data has less than two entries and `f64::NAN` if any
+    /// entry is `f64::NAN`
     ///
     /// # Examples
     ///
     /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
     /// use std::f64;
     /// use spc_rs::statistics::Statistics;
     ///
+    /// # fn main() {
     /// let x = &[];
-    /// assert!(x.variance().is_nan());
+    /// assert_eq!(x.variance(), 0.0);
     ///
     /// let y = &[0.0, f64::NAN, 3.0, -2.0];
     /// assert!(y.variance().is_nan());
     ///
     /// let z = &[0.0, 3.0, -2.0];
-    /// assert_eq!(z.variance(), 19.0 / 3.0);
+    /// assert_almost_eq!(z.variance(), 19.0 / 3.0, 1e-14);
+    /// # }
     /// ```
     fn variance(&self) -> f64;
 
@@ -242,23 +284,28 @@ pub trait Statistics {
     /// On a dataset of size `N`, `N-1` is used as a normalizer (Bessel's
     /// correction).
     ///
-    /// Returns `f64::NAN` if data has less than two entries or if any entry is
-    /// `f64::NAN`
+    /// Returns `0.0` if data has less than two entries and `f64::NAN` if any
+    /// entry is `f64::NAN`
     ///
     /// # Examples
     ///
     /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
     /// use std::f64;
     /// use spc_rs::statistics::Statistics;
     ///
+    /// # fn main() {
     /// let x = &[];
-    /// assert!(x.std_dev().is_nan());
+    /// assert_eq!(x.std_dev(), 0.0);
     ///
     /// let y = &[0.0, f64::NAN, 3.0, -2.0];
     /// assert!(y.std_dev().is_nan());
     ///
     /// let z = &[0.0, 3.0, -2.0];
-    /// assert_eq!(z.std_dev(), (19f64 / 3.0).sqrt());
+    /// assert_almost_eq!(z.std_dev(), (19f64 / 3.0).sqrt(), 1e-14);
+    /// # }
     /// ```
     fn std_dev(&self) -> f64;
 
@@ -355,6 +402,34 @@ pub trait Statistics {
     /// ```
     fn covariance(&self, other: &Self) -> f64;
 
+    /// Estimates the unbiased population covariance between the provided
+    /// samples and a series of another numeric type, e.g. `f32` measurements
+    ///
+    /// # Remarks
+    ///
+    /// Converts `other` element by element instead of allocating a copy, and
+    /// otherwise behaves like `covariance`
+    ///
+    /// # Panics
+    ///
+    /// If the two sample containers do not contain the same number of elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// # fn main() {
+    /// let x = [0.0, 3.0, -2.0];
+    /// assert_almost_eq!(x.covariance_with(&[-5.0f32, 4.0, 10.0]), -5.5, 1e-14);
+    /// assert_eq!(x.covariance_with(&[1u8, 2, 3]), x.covariance(&[1.0, 2.0, 3.0]));
+    /// # }
+    /// ```
+    fn covariance_with<T: Into<f64> + Copy>(&self, other: &[T]) -> f64;
+
     /// Evaluates the population covariance between the two provider populations
     ///
     /// # Remarks
@@ -425,6 +500,140 @@ pub trait Statistics {
     ///
     fn range(&self) -> f64;
 
+    /// Returns the index of the minimum value
+    ///
+    /// # Remarks
+    ///
+    /// Ties resolve to the first occurrence. Returns `None` if data is empty
+    /// or any entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// assert_eq!([3.0, 1.0, 2.0, 1.0].arg_min(), Some(1));
+    /// assert_eq!([0.0; 0].arg_min(), None);
+    /// assert_eq!([1.0, f64::NAN].arg_min(), None);
+    /// ```
+    fn arg_min(&self) -> Option<usize>;
+
+    /// Returns the index of the maximum value
+    ///
+    /// # Remarks
+    ///
+    /// Ties resolve to the first occurrence. Returns `None` if data is empty
+    /// or any entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// assert_eq!([3.0, 1.0, 3.0, 2.0].arg_max(), Some(0));
+    /// assert_eq!([0.0; 0].arg_max(), None);
+    /// ```
+    fn arg_max(&self) -> Option<usize>;
+
+    /// Calculates the range together with the indices of the minimum and the
+    /// maximum, `(range, arg_min, arg_max)`
+    ///
+    /// # Remarks
+    ///
+    /// Ties resolve to the first occurrence, like `arg_min` and `arg_max`.
+    /// Returns `(f64::NAN, 0, 0)` if data is empty or any entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// assert_eq!([10.2, 9.8, 10.5, 9.8].range_with_indices(), (10.5 - 9.8, 1, 2));
+    /// assert!([0.0; 0].range_with_indices().0.is_nan());
+    /// ```
+    fn range_with_indices(&self) -> (f64, usize, usize);
+
+    /// Evaluates the sample mean of the entries that are not `f64::NAN`, for
+    /// data with gaps
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty or every entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [1.0, f64::NAN, 2.0, 6.0];
+    /// assert!(x.average().is_nan());
+    /// assert_eq!(x.nan_average(), 3.0);
+    /// ```
+    fn nan_average(&self) -> f64;
+
+    /// Estimates the unbiased population standard deviation of the entries that
+    /// are not `f64::NAN`
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty, every entry is `f64::NAN` or only a
+    /// single entry is left
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [1.0, f64::NAN, 3.0];
+    /// assert_eq!(x.nan_std_dev(), 2f64.sqrt());
+    /// ```
+    fn nan_std_dev(&self) -> f64;
+
+    /// Returns the minimum of the entries that are not `f64::NAN`
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty or every entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// assert_eq!([0.0, f64::NAN, 3.0, -2.0].nan_min(), -2.0);
+    /// ```
+    fn nan_min(&self) -> f64;
+
+    /// Returns the maximum of the entries that are not `f64::NAN`
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty or every entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// assert_eq!([0.0, f64::NAN, 3.0, -2.0].nan_max(), 3.0);
+    /// ```
+    fn nan_max(&self) -> f64;
+
+    /// Calculates the range of the entries that are not `f64::NAN`
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty or every entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// assert_eq!([0.0, f64::NAN, 3.0, -2.0].nan_range(), 5.0);
+    /// ```
+    fn nan_range(&self) -> f64;
+
     /// 计算偏斜度
     fn skewness(&self) -> f64;
 
@@ -435,60 +644,638 @@ pub trait Statistics {
 
     /// 计算坡度
     fn slope(&self, other: &Self) -> f64;
-}
 
-impl Statistics for [f64] {
-    fn min(&self) -> f64 {
-        self.iter().copied().reduce(f64::min).unwrap_or(f64::NAN)
-    }
+    /// Fits `other` against `self` by ordinary least squares, returning the
+    /// `(slope, intercept)` of the regression line `y = slope * x + intercept`
+    ///
+    /// # Remarks
+    ///
+    /// Returns `(f64::NAN, f64::NAN)` if data has less than two entries, if
+    /// all `x` values are equal or if any entry is `f64::NAN`
+    ///
+    /// # Panics
+    ///
+    /// If the two sample containers do not contain the same number of elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// # fn main() {
+    /// let x = [0.0, 1.0, 2.0, 3.0];
+    /// let y = [1.0, 3.0, 5.0, 7.0];
+    /// let (slope, intercept) = x.linear_regression(&y);
+    /// assert_almost_eq!(slope, 2.0, 1e-14);
+    /// assert_almost_eq!(intercept, 1.0, 1e-14);
+    /// # }
+    /// ```
+    fn linear_regression(&self, other: &Self) -> (f64, f64);
 
-    fn max(&self) -> f64 {
-        self.iter().copied().reduce(f64::max).unwrap_or(f64::NAN)
-    }
+    /// Evaluates the weighted sample mean `Σ wᵢxᵢ / Σ wᵢ`
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty, if the weights sum to zero or if
+    /// any entry is `f64::NAN`
+    ///
+    /// # Panics
+    ///
+    /// If `weights` does not contain the same number of elements as the data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// # fn main() {
+    /// let x = [1.0, 2.0, 4.0];
+    /// assert_almost_eq!(x.weighted_average(&[1.0, 1.0, 2.0]), 2.75, 1e-14);
+    /// assert!(x.weighted_average(&[0.0, 0.0, 0.0]).is_nan());
+    /// # }
+    /// ```
+    fn weighted_average(&self, weights: &[f64]) -> f64;
 
-    fn abs_min(&self) -> f64 {
-        self.iter()
-            .map(|&x| x.abs())
-            .reduce(f64::min)
-            .unwrap_or(f64::NAN)
-    }
+    /// Estimates the weighted sample variance, treating `weights` as
+    /// reliability weights
+    ///
+    /// # Remarks
+    ///
+    /// The weighted sum of squared deviations is divided by
+    /// `Σ wᵢ - Σ wᵢ² / Σ wᵢ`, which reduces to `N-1` for equal weights.
+    ///
+    /// Returns `f64::NAN` if the weights sum to zero, if fewer than two
+    /// entries carry weight or if any entry is `f64::NAN`
+    ///
+    /// # Panics
+    ///
+    /// If `weights` does not contain the same number of elements as the data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// # fn main() {
+    /// let x = [1.0, 2.0, 4.0];
+    /// assert_almost_eq!(x.weighted_variance(&[2.0, 2.0, 2.0]), x.variance(), 1e-14);
+    /// assert!(x.weighted_variance(&[0.0, 0.0, 1.0]).is_nan());
+    /// # }
+    /// ```
+    fn weighted_variance(&self, weights: &[f64]) -> f64;
 
-    fn abs_max(&self) -> f64 {
-        self.iter()
-            .map(|&x| x.abs())
-            .reduce(f64::max)
-            .unwrap_or(f64::NAN)
-    }
+    /// Evaluates the sample autocorrelation at the given `lag`
+    ///
+    /// # Remarks
+    ///
+    /// `r_k = Σ (x_t - x̄)(x_{t+k} - x̄) / Σ (x_t - x̄)²`, where the numerator runs
+    /// over the `N-k` available pairs and the denominator over all `N` entries.
+    ///
+    /// Returns `f64::NAN` if `lag >= N`, if all entries are equal or if any
+    /// entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// # fn main() {
+    /// let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+    /// assert_almost_eq!(x.autocorrelation(0), 1.0, 1e-14);
+    /// assert_almost_eq!(x.autocorrelation(1), 0.4, 1e-14);
+    /// assert_almost_eq!(x.autocorrelation(2), -0.1, 1e-14);
+    /// assert!(x.autocorrelation(5).is_nan());
+    /// # }
+    /// ```
+    fn autocorrelation(&self, lag: usize) -> f64;
 
-    fn average(&self) -> f64 {
-        let sum: f64 = self.iter().sum();
-        if self.is_empty() {
-            f64::NAN
-        } else {
-            sum / self.len() as f64
-        }
-    }
+    /// Evaluates the sample autocorrelation for every lag in `0..=max_lag`, so
+    /// that the element at index `k` is `autocorrelation(k)`
+    fn autocorrelations(&self, max_lag: usize) -> Vec<f64>;
 
-    fn geometric_average(&self) -> f64 {
-        let mut i = 0.0;
-        let mut sum = 0.0;
-        for x in self {
-            i += 1.0;
-            sum += x.borrow().ln();
-        }
-        if i > 0.0 { (sum / i).exp() } else { f64::NAN }
-    }
+    /// Evaluates the differences of the given `order`, `diff(1)` being
+    /// `x[i] - x[i-1]` with `N-1` entries and `diff(2)` the differences of
+    /// those
+    ///
+    /// # Remarks
+    ///
+    /// `diff(0)` returns the data unchanged. Returns an empty vector if the
+    /// data has no more than `order` entries
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [1.0, 4.0, 9.0, 16.0];
+    /// assert_eq!(x.diff(1), vec![3.0, 5.0, 7.0]);
+    /// assert_eq!(x.diff(2), vec![2.0, 2.0]);
+    /// assert!(x.diff(4).is_empty());
+    /// ```
+    fn diff(&self, order: usize) -> Vec<f64>;
 
-    fn harmonic_average(&self) -> f64 {
-        let mut i = 0.0;
-        let mut sum = 0.0;
-        for x in self {
-            i += 1.0;
-            let borrow = *x.borrow();
-            if borrow < 0f64 {
-                return f64::NAN;
-            }
-            sum += 1.0 / borrow;
+    /// Returns the data lagged by `k`, i.e. the `N-k` entries `x[0..N-k]`
+    /// that pair with `x[k..N]`
+    ///
+    /// # Remarks
+    ///
+    /// Returns an empty vector if the data has no more than `k` entries
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [1.0, 2.0, 3.0, 4.0];
+    /// assert_eq!(x.lag(1), vec![1.0, 2.0, 3.0]);
+    /// assert!(x.lag(4).is_empty());
+    /// ```
+    fn lag(&self, k: usize) -> Vec<f64>;
+
+    /// Evaluates the running totals, the entry at index `i` being the sum of
+    /// `x[0..=i]`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [1.0, -2.0, 3.5];
+    /// assert_eq!(x.cumulative_sum(), vec![1.0, -1.0, 2.5]);
+    /// ```
+    fn cumulative_sum(&self) -> Vec<f64>;
+
+    /// Clamps every entry into the closed interval `[lo, hi]`, e.g. to cap gross
+    /// gauge errors before charting
+    ///
+    /// # Remarks
+    ///
+    /// `f64::NAN` entries are kept as they are
+    ///
+    /// # Panics
+    ///
+    /// If `lo > hi` or either bound is `f64::NAN`, like `f64::clamp`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [9.2, 10.1, 14.8, 10.3];
+    /// assert_eq!(x.clamp_values(9.5, 10.5), vec![9.5, 10.1, 10.5, 10.3]);
+    /// ```
+    fn clamp_values(&self, lo: f64, hi: f64) -> Vec<f64>;
+
+    /// Evaluates the empirical cumulative distribution function at `x`, the
+    /// fraction of entries less than or equal to `x`
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [3.0, 1.0, 2.0, 2.0];
+    /// assert_eq!(x.ecdf(0.5), 0.0);
+    /// assert_eq!(x.ecdf(2.0), 0.75);
+    /// assert_eq!(x.ecdf(3.0), 1.0);
+    /// ```
+    fn ecdf(&self, x: f64) -> f64;
+
+    /// Evaluates the percentile rank of `value`, the percentage of entries
+    /// less than or equal to it (`100 * ecdf(value)`)
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty. Pair with
+    /// `distribution::z_from_percentile` for the normal score of the rank
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [3.0, 1.0, 2.0, 2.0];
+    /// assert_eq!(x.percentile_rank(2.0), 75.0);
+    /// assert_eq!(x.percentile_rank(0.5), 0.0);
+    /// ```
+    fn percentile_rank(&self, value: f64) -> f64;
+
+    /// Counts the entries in the closed interval `[lo, hi]`, e.g. the
+    /// measurements within the spec limits
+    ///
+    /// # Remarks
+    ///
+    /// `f64::NAN` entries are never counted. Returns 0 if `lo > hi` or either
+    /// bound is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [9.8, 10.0, 10.2, 10.5, f64::NAN];
+    /// assert_eq!(x.count_within(9.8, 10.2), 3);
+    /// assert_eq!(x.count_within(10.3, 10.1), 0);
+    /// ```
+    fn count_within(&self, lo: f64, hi: f64) -> usize;
+
+    /// Evaluates the fraction of entries in the closed interval `[lo, hi]`,
+    /// `count_within(lo, hi)` over the number of entries that are not `f64::NAN`
+    ///
+    /// # Remarks
+    ///
+    /// `f64::NAN` entries are missing measurements and left out of both the count
+    /// and the total. Returns `f64::NAN` if no entry is left
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [9.8, 10.0, 10.2, 10.5, f64::NAN];
+    /// assert_eq!(x.proportion_within(9.8, 10.2), 0.75);
+    /// ```
+    fn proportion_within(&self, lo: f64, hi: f64) -> f64;
+
+    /// Estimates the `tau`-th quantile of the data by linear interpolation between
+    /// the order statistics at position `tau * (n - 1)` (the R-7 definition)
+    ///
+    /// # Remarks
+    ///
+    /// Returns `f64::NAN` if data is empty, any entry is `f64::NAN` or `tau` is
+    /// outside `[0, 1]`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [4.0, 1.0, 3.0, 2.0];
+    /// assert_eq!(x.quantile(0.0), 1.0);
+    /// assert_eq!(x.quantile(0.5), 2.5);
+    /// assert_eq!(x.quantile(0.75), 3.25);
+    /// ```
+    fn quantile(&self, tau: f64) -> f64;
+
+    /// Counts the data in equal width bins spanning its minimum to maximum, the
+    /// bin count chosen by `rule`. Returns `(lower, upper, count)` per bin,
+    /// each bin holding the values `lower <= x < upper` except the last, which
+    /// also holds the maximum
+    ///
+    /// # Remarks
+    ///
+    /// Returns an empty histogram if data is empty or any entry is `f64::NAN`
+    /// or infinite. Constant data, or a zero standard deviation or IQR, gives
+    /// a single bin
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::{BinRule, Statistics};
+    ///
+    /// let x = [1.0, 2.0, 2.0, 3.0, 3.0, 3.0, 4.0, 5.0];
+    /// let histogram = x.auto_histogram(BinRule::Sturges);
+    /// assert_eq!(histogram, vec![(1.0, 2.0, 1), (2.0, 3.0, 2), (3.0, 4.0, 3), (4.0, 5.0, 2)]);
+    /// assert_eq!(x[..1].auto_histogram(BinRule::Scott), vec![(1.0, 1.0, 1)]);
+    /// ```
+    fn auto_histogram(&self, rule: BinRule) -> Vec<(f64, f64, usize)>;
+
+    /// Summarizes the data: count, mean, sample standard deviation, minimum,
+    /// quartiles, maximum, skewness and excess kurtosis
+    ///
+    /// # Remarks
+    ///
+    /// The quartiles and median use the R-7 definition of `quantile`, linear
+    /// interpolation between the order statistics at `tau * (n - 1)`. The data
+    /// are sorted once and the moments take two passes.
+    ///
+    /// Every statistic but `count` is `f64::NAN` if data is empty or any entry
+    /// is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let summary = [4.0, 1.0, 3.0, 2.0].describe();
+    /// assert_eq!(summary.count, 4);
+    /// assert_eq!(summary.mean, 2.5);
+    /// assert_eq!((summary.min, summary.max), (1.0, 4.0));
+    /// assert_eq!((summary.q1, summary.median, summary.q3), (1.75, 2.5, 3.25));
+    /// assert!([0.0; 0].describe().mean.is_nan());
+    /// ```
+    fn describe(&self) -> Summary;
+
+    /// Evaluates the Welford state `(count, mean, M2)` of the data, `M2` being
+    /// the sum of squared deviations from the mean
+    ///
+    /// # Remarks
+    ///
+    /// Computed in one pass with Welford's update. The sample variance is
+    /// `M2 / (count - 1)`, and states of separate data sets combine with
+    /// `combine_moments`. Returns `(0, f64::NAN, f64::NAN)` if data is empty
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// assert_eq!([1.0, 2.0, 3.0, 6.0].moments(), (4, 3.0, 14.0));
+    /// ```
+    fn moments(&self) -> (usize, f64, f64);
+
+    /// Estimates the `1 - alpha` percentile bootstrap confidence interval of
+    /// the mean, available with the `rand` feature
+    ///
+    /// # Remarks
+    ///
+    /// Draws `resamples` samples of the data's size with replacement from a
+    /// ChaCha8 generator seeded with `seed`, so the interval is reproducible,
+    /// and returns the `alpha / 2` and `1 - alpha / 2` quantiles of their means
+    ///
+    /// Returns `(f64::NAN, f64::NAN)` if data is empty, any entry is
+    /// `f64::NAN`, `resamples` is zero or `alpha` is outside `(0, 1)`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [9.8, 10.1, 10.0, 9.9, 10.2, 10.0];
+    /// let (lower, upper) = x.bootstrap_mean_ci(1000, 0.05, 42);
+    /// assert!(lower < 10.0 && 10.0 < upper);
+    /// assert_eq!((lower, upper), x.bootstrap_mean_ci(1000, 0.05, 42));
+    /// ```
+    #[cfg(feature = "rand")]
+    fn bootstrap_mean_ci(&self, resamples: usize, alpha: f64, seed: u64) -> (f64, f64);
+
+    /// Evaluates the coefficient of determination `R²` of the least squares
+    /// line fitting `other` against `self`
+    ///
+    /// # Remarks
+    ///
+    /// Equals the squared Pearson correlation of the two samples. Returns
+    /// `f64::NAN` if data has less than two entries, if either sample is
+    /// constant or if any entry is `f64::NAN`
+    ///
+    /// # Panics
+    ///
+    /// If the two sample containers do not contain the same number of elements
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// # fn main() {
+    /// let x = [1.0, 2.0, 3.0, 4.0];
+    /// assert_almost_eq!(x.r_squared(&[2.0, 4.0, 6.0, 8.0]), 1.0, 1e-14);
+    /// assert_almost_eq!(x.r_squared(&[1.0, 3.0, 2.0, 4.0]), 0.64, 1e-14);
+    /// # }
+    /// ```
+    fn r_squared(&self, other: &Self) -> f64;
+
+    /// Evaluates the coordinates of a normal probability plot, pairing each
+    /// sorted value with the standard normal quantile of its plotting position
+    /// `(i - 0.375) / (N + 0.25)` (Blom)
+    ///
+    /// # Remarks
+    ///
+    /// Normally distributed data lie close to a straight line, so the
+    /// `r_squared` of the values against the quantiles scores normality
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate spc_rs;
+    ///
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// # fn main() {
+    /// let points = [2.0, 1.0, 3.0].normal_probability_plot();
+    /// assert_eq!(points[0].0, 1.0);
+    /// assert_almost_eq!(points[0].1, -0.8694237733, 1e-9);
+    /// assert_eq!(points[1], (2.0, 0.0));
+    /// # }
+    /// ```
+    fn normal_probability_plot(&self) -> Vec<(f64, f64)>;
+
+    /// Tests the data for normality, returning the Anderson-Darling statistic
+    /// `A²` and its approximate p-value
+    ///
+    /// # Remarks
+    ///
+    /// The data are standardized with the sample mean and standard deviation,
+    /// so `A²` is compared against the case of estimated parameters. The
+    /// p-value is D'Agostino and Stephens' (1986) piecewise approximation
+    /// evaluated at `A²(1 + 0.75/N + 2.25/N²)`. A small p-value (e.g. below
+    /// 0.05) rejects normality.
+    ///
+    /// The p-value is `f64::NAN` for fewer than eight entries. Both values are
+    /// `f64::NAN` for fewer than two entries, if all entries are equal or if
+    /// any entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let skewed = [1.0, 1.1, 1.0, 1.2, 1.0, 1.1, 5.0, 9.0, 1.0, 1.3];
+    /// let (a2, p) = skewed.anderson_darling();
+    /// assert!(a2 > 2.0);
+    /// assert!(p < 0.001);
+    /// ```
+    fn anderson_darling(&self) -> (f64, f64);
+
+    /// Runs a two-sided Grubbs test at significance level `alpha`, returning
+    /// the index of the most extreme entry if it is an outlier
+    ///
+    /// # Remarks
+    ///
+    /// The statistic `G = max|xᵢ - x̄| / s` is compared against
+    /// `(N-1)/√N · √(t² / (N-2+t²))`, where `t` is the upper `alpha/(2N)`
+    /// quantile of Student's t distribution with `N-2` degrees of freedom.
+    ///
+    /// The test assumes otherwise normal data and detects at most one
+    /// outlier; a second outlier can mask the first. Returns `None` for fewer
+    /// than three entries, if all entries are equal or if any entry is
+    /// `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [10.1, 9.9, 10.0, 10.2, 9.8, 10.1, 12.5, 10.0];
+    /// assert_eq!(x.grubbs_test(0.05), Some(6));
+    /// assert_eq!(x[..6].grubbs_test(0.05), None);
+    /// ```
+    fn grubbs_test(&self, alpha: f64) -> Option<usize>;
+
+    /// Evaluates the sum of the data
+    ///
+    /// # Remarks
+    ///
+    /// Returns `0.0` if data is empty and `f64::NAN` if any entry is
+    /// `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x: &[f64] = &[];
+    /// assert_eq!(x.sum(), 0.0);
+    /// assert_eq!([1.5, 2.0, -0.5].sum(), 3.0);
+    /// assert!([1.0, f64::NAN].sum().is_nan());
+    /// ```
+    fn sum(&self) -> f64;
+
+    /// Evaluates the product of the data
+    ///
+    /// # Remarks
+    ///
+    /// Returns `1.0` if data is empty and `f64::NAN` if any entry is
+    /// `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x: &[f64] = &[];
+    /// assert_eq!(x.product(), 1.0);
+    /// assert_eq!([1.5, 2.0, -0.5].product(), -1.5);
+    /// assert!([0.0, f64::NAN].product().is_nan());
+    /// ```
+    fn product(&self) -> f64;
+
+    /// Evaluates the most frequent value(s), treating values within
+    /// `tolerance` of each other as equal
+    ///
+    /// # Remarks
+    ///
+    /// The sorted data are split into buckets whose entries lie within
+    /// `tolerance` of the bucket's smallest entry, and the mean of each of the
+    /// largest buckets is returned in ascending order.
+    ///
+    /// Returns an empty vector if data is empty, if no bucket holds more than
+    /// one entry or if any entry is `f64::NAN`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use spc_rs::statistics::Statistics;
+    ///
+    /// let x = [1.0, 2.0, 2.0, 3.0, 3.0];
+    /// assert_eq!(x.mode(0.0), vec![2.0, 3.0]);
+    /// assert!([1.0, 2.0, 3.0].mode(0.0).is_empty());
+    /// ```
+    fn mode(&self, tolerance: f64) -> Vec<f64>;
+}
+
+impl Statistics for [f64] {
+    fn min(&self) -> f64 {
+        self.iter()
+            .copied()
+            .reduce(|a, b| {
+                if a.is_nan() || b.is_nan() {
+                    f64::NAN
+                } else {
+                    a.min(b)
+                }
+            })
+            .unwrap_or(f64::NAN)
+    }
+
+    fn max(&self) -> f64 {
+        self.iter()
+            .copied()
+            .reduce(|a, b| {
+                if a.is_nan() || b.is_nan() {
+                    f64::NAN
+                } else {
+                    a.max(b)
+                }
+            })
+            .unwrap_or(f64::NAN)
+    }
+
+    fn abs_min(&self) -> f64 {
+        self.iter()
+            .map(|&x| x.abs())
+            .reduce(|a, b| {
+                if a.is_nan() || b.is_nan() {
+                    f64::NAN
+                } else {
+                    a.min(b)
+                }
+            })
+            .unwrap_or(f64::NAN)
+    }
+
+    fn abs_max(&self) -> f64 {
+        self.iter()
+            .map(|&x| x.abs())
+            .reduce(|a, b| {
+                if a.is_nan() || b.is_nan() {
+                    f64::NAN
+                } else {
+                    a.max(b)
+                }
+            })
+            .unwrap_or(f64::NAN)
+    }
+
+    fn average(&self) -> f64 {
+        let sum: f64 = self.iter().sum();
+        if self.is_empty() {
+            f64::NAN
+        } else {
+            sum / self.len() as f64
+        }
+    }
+
+    fn geometric_average(&self) -> f64 {
+        let mut i = 0.0;
+        let mut sum = 0.0;
+        for x in self {
+            i += 1.0;
+            sum += x.ln();
+        }
+        if i > 0.0 { (sum / i).exp() } else { f64::NAN }
+    }
+
+    fn harmonic_average(&self) -> f64 {
+        let mut i = 0.0;
+        let mut sum = 0.0;
+        for x in self {
+            i += 1.0;
+            let borrow = *x;
+            if borrow < 0f64 {
+                return f64::NAN;
+            }
+            sum += 1.0 / borrow;
         }
         if i > 0.0 { i / sum } else { f64::NAN }
     }
@@ -516,17 +1303,17 @@ impl Statistics for [f64] {
     }
 
     fn population_variance(&self) -> f64 {
-        let mut iter = self.into_iter();
+        let mut iter = self.iter();
         let mut sum = match iter.next() {
             None => return f64::NAN,
-            Some(x) => *x.borrow(),
+            Some(x) => *x,
         };
         let mut i = 1.0;
         let mut variance = 0.0;
 
         for x in iter {
             i += 1.0;
-            let borrow = *x.borrow();
+            let borrow = *x;
             sum += borrow;
             let diff = i * borrow - sum;
             variance += diff * diff / (i * (i - 1.0));
@@ -539,17 +1326,21 @@ impl Statistics for [f64] {
     }
 
     fn covariance(&self, other: &Self) -> f64 {
+        self.covariance_with(other)
+    }
+
+    fn covariance_with<T: Into<f64> + Copy>(&self, other: &[T]) -> f64 {
         let mut n = 0.0;
         let mut mean1 = 0.0;
         let mut mean2 = 0.0;
         let mut comoment = 0.0;
 
-        let mut iter = other.into_iter();
+        let mut iter = other.iter();
         for x in self {
-            let borrow = *x.borrow();
+            let borrow = *x;
             let borrow2 = match iter.next() {
                 None => panic!("{}", StatsError::ContainersMustBeSameLength),
-                Some(x) => *x.borrow(),
+                Some(x) => (*x).into(),
             };
             let old_mean2 = mean2;
             n += 1.0;
@@ -574,12 +1365,12 @@ impl Statistics for [f64] {
         let mut mean2 = 0.0;
         let mut comoment = 0.0;
 
-        let mut iter = other.into_iter();
+        let mut iter = other.iter();
         for x in self {
-            let borrow = *x.borrow();
+            let borrow = *x;
             let borrow2 = match iter.next() {
                 None => panic!("{}", StatsError::ContainersMustBeSameLength),
-                Some(x) => *x.borrow(),
+                Some(x) => *x,
             };
             let old_mean2 = mean2;
             n += 1.0;
@@ -597,15 +1388,56 @@ impl Statistics for [f64] {
         let mut i = 0.0;
         let mut average = 0.0;
         for x in self {
-            let borrow = *x.borrow();
+            let borrow = *x;
             i += 1.0;
             average += (borrow * borrow - average) / i;
         }
         if i > 0.0 { average.sqrt() } else { f64::NAN }
     }
 
-    fn range(&self) -> f64 {
-        self.max() - self.min()
+    fn range(&self) -> f64 {
+        self.max() - self.min()
+    }
+
+    fn nan_average(&self) -> f64 {
+        without_nan(self).average()
+    }
+
+    fn nan_std_dev(&self) -> f64 {
+        let values = without_nan(self);
+        if values.len() < 2 {
+            return f64::NAN;
+        }
+        values.std_dev()
+    }
+
+    fn nan_min(&self) -> f64 {
+        without_nan(self).min()
+    }
+
+    fn nan_max(&self) -> f64 {
+        without_nan(self).max()
+    }
+
+    fn nan_range(&self) -> f64 {
+        without_nan(self).range()
+    }
+
+    fn arg_min(&self) -> Option<usize> {
+        let (_, arg_min, _) = extreme_indices(self)?;
+        Some(arg_min)
+    }
+
+    fn arg_max(&self) -> Option<usize> {
+        let (_, _, arg_max) = extreme_indices(self)?;
+        Some(arg_max)
+    }
+
+    fn range_with_indices(&self) -> (f64, usize, usize) {
+        match extreme_indices(self) {
+            Some((range, arg_min, arg_max)) => (range, arg_min, arg_max),
+            None => (f64::NAN, 0, 0),
+        }
     }
 
     fn median(&self) -> f64 {
@@ -617,7 +1449,7 @@ impl Statistics for [f64] {
         let mut sorted_data = self.to_vec();
         sorted_data.sort_by(|a, b| a.partial_cmp(b).unwrap()); // 排序处理
         let len = sorted_data.len();
-        if len % 2 == 0 {
+        if len.is_multiple_of(2) {
             // 偶数个数据时，取中间两个数的平均值
             let mid1 = sorted_data[len / 2 - 1];
             let mid2 = sorted_data[len / 2];
@@ -662,8 +1494,8 @@ impl Statistics for [f64] {
     }
 
     fn slope(&self, other: &Self) -> f64 {
-        let mut iter_x = self.into_iter();
-        let mut iter_y = other.into_iter();
+        let mut iter_x = self.iter();
+        let mut iter_y = other.iter();
 
         let len_x = self.len();
         let len_y = other.len();
@@ -675,8 +1507,8 @@ impl Statistics for [f64] {
         let mut denominator = 0.0; // 分母
 
         while let (Some(x_val), Some(y_val)) = (iter_x.next(), iter_y.next()) {
-            let x_f64 = *x_val.borrow();
-            let y_f64 = *y_val.borrow();
+            let x_f64 = *x_val;
+            let y_f64 = *y_val;
             numerator += x_f64 * y_f64;
             denominator += x_f64 * x_f64;
         }
@@ -685,23 +1517,927 @@ impl Statistics for [f64] {
             numerator / denominator
         } else {
             // 如果分母为 0，计算 y 的平均值
-            let y_sum: f64 = iter_y.map(|y_val| *y_val.borrow()).sum();
+            let y_sum: f64 = iter_y.copied().sum();
             y_sum / len_x as f64
         }
     }
+
+    fn linear_regression(&self, other: &Self) -> (f64, f64) {
+        let slope = self.covariance(other) / self.variance();
+        if !slope.is_finite() {
+            return (f64::NAN, f64::NAN);
+        }
+        (slope, other.average() - slope * self.average())
+    }
+
+    fn weighted_average(&self, weights: &[f64]) -> f64 {
+        if self.len() != weights.len() {
+            panic!("{}", StatsError::ContainersMustBeSameLength);
+        }
+        let weight_sum: f64 = weights.iter().sum();
+        if weight_sum == 0.0 {
+            return f64::NAN;
+        }
+        self.iter().zip(weights).map(|(x, w)| x * w).sum::<f64>() / weight_sum
+    }
+
+    fn weighted_variance(&self, weights: &[f64]) -> f64 {
+        let mean = self.weighted_average(weights);
+        let weight_sum: f64 = weights.iter().sum();
+        let weight_square_sum: f64 = weights.iter().map(|w| w * w).sum();
+        let denominator = weight_sum - weight_square_sum / weight_sum;
+        if mean.is_nan() || denominator <= 0.0 {
+            return f64::NAN;
+        }
+        self.iter()
+            .zip(weights)
+            .map(|(x, w)| w * (x - mean) * (x - mean))
+            .sum::<f64>()
+            / denominator
+    }
+
+    fn autocorrelation(&self, lag: usize) -> f64 {
+        if lag >= self.len() {
+            return f64::NAN;
+        }
+        let mean = self.average();
+        let denominator: f64 = self.iter().map(|x| (x - mean) * (x - mean)).sum();
+        if denominator == 0.0 {
+            return f64::NAN;
+        }
+        self.iter()
+            .zip(&self[lag..])
+            .map(|(x, y)| (x - mean) * (y - mean))
+            .sum::<f64>()
+            / denominator
+    }
+
+    fn autocorrelations(&self, max_lag: usize) -> Vec<f64> {
+        (0..=max_lag).map(|lag| self.autocorrelation(lag)).collect()
+    }
+
+    fn diff(&self, order: usize) -> Vec<f64> {
+        let mut differences = self.to_vec();
+        for _ in 0..order {
+            differences = differences.windows(2).map(|w| w[1] - w[0]).collect();
+        }
+        differences
+    }
+
+    fn lag(&self, k: usize) -> Vec<f64> {
+        self[..self.len().saturating_sub(k)].to_vec()
+    }
+
+    fn cumulative_sum(&self) -> Vec<f64> {
+        let mut sum = 0.0;
+        self.iter()
+            .map(|x| {
+                sum += x;
+                sum
+            })
+            .collect()
+    }
+
+    fn clamp_values(&self, lo: f64, hi: f64) -> Vec<f64> {
+        assert!(
+            lo <= hi,
+            "clamp_values: lo must not exceed hi, got [{}, {}]",
+            lo,
+            hi
+        );
+        self.iter().map(|x| x.clamp(lo, hi)).collect()
+    }
+
+    fn ecdf(&self, x: f64) -> f64 {
+        if self.is_empty() {
+            return f64::NAN;
+        }
+        self.iter().filter(|&&value| value <= x).count() as f64 / self.len() as f64
+    }
+
+    fn percentile_rank(&self, value: f64) -> f64 {
+        100.0 * self.ecdf(value)
+    }
+
+    fn count_within(&self, lo: f64, hi: f64) -> usize {
+        self.iter().filter(|&&x| lo <= x && x <= hi).count()
+    }
+
+    fn proportion_within(&self, lo: f64, hi: f64) -> f64 {
+        let total = self.iter().filter(|x| !x.is_nan()).count();
+        if total == 0 {
+            return f64::NAN;
+        }
+        self.count_within(lo, hi) as f64 / total as f64
+    }
+
+    fn quantile(&self, tau: f64) -> f64 {
+        if self.is_empty() || !(0.0..=1.0).contains(&tau) || self.iter().any(|x| x.is_nan()) {
+            return f64::NAN;
+        }
+        let mut sorted = self.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        sorted_quantile(&sorted, tau)
+    }
+
+    fn auto_histogram(&self, rule: BinRule) -> Vec<(f64, f64, usize)> {
+        if self.is_empty() || self.iter().any(|x| !x.is_finite()) {
+            return vec![];
+        }
+        let min = Statistics::min(self);
+        let max = Statistics::max(self);
+        let n = self.len() as f64;
+        let bins = match rule {
+            BinRule::Sturges => n.log2().ceil() + 1.0,
+            BinRule::Scott => (max - min) / (3.49 * self.std_dev() / n.cbrt()),
+            BinRule::FreedmanDiaconis => {
+                let iqr = self.quantile(0.75) - self.quantile(0.25);
+                (max - min) / (2.0 * iqr / n.cbrt())
+            }
+        };
+        if max == min || !bins.is_finite() || bins < 1.0 {
+            return vec![(min, max, self.len())];
+        }
+        let bins = bins.ceil() as usize;
+        let width = (max - min) / bins as f64;
+        let mut counts = vec![0; bins];
+        for x in self {
+            counts[(((x - min) / width) as usize).min(bins - 1)] += 1;
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let upper = if i + 1 == bins {
+                    max
+                } else {
+                    min + (i + 1) as f64 * width
+                };
+                (min + i as f64 * width, upper, count)
+            })
+            .collect()
+    }
+
+    fn describe(&self) -> Summary {
+        let count = self.len();
+        if count == 0 || self.iter().any(|x| x.is_nan()) {
+            return Summary {
+                count,
+                mean: f64::NAN,
+                std_dev: f64::NAN,
+                min: f64::NAN,
+                q1: f64::NAN,
+                median: f64::NAN,
+                q3: f64::NAN,
+                max: f64::NAN,
+                skewness: f64::NAN,
+                kurtosis: f64::NAN,
+            };
+        }
+        let mut sorted = self.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = count as f64;
+        let mean = self.average();
+        let (mut m2, mut m3, mut m4) = (0.0, 0.0, 0.0);
+        for x in self {
+            let d = x - mean;
+            m2 += d * d;
+            m3 += d * d * d;
+            m4 += d * d * d * d;
+        }
+        let std_dev = if count < 2 {
+            0.0
+        } else {
+            (m2 / (n - 1.0)).sqrt()
+        };
+        let (m2, m3, m4) = (m2 / n, m3 / n, m4 / n);
+        Summary {
+            count,
+            mean,
+            std_dev,
+            min: sorted[0],
+            q1: sorted_quantile(&sorted, 0.25),
+            median: sorted_quantile(&sorted, 0.5),
+            q3: sorted_quantile(&sorted, 0.75),
+            max: sorted[count - 1],
+            skewness: m3 / m2.powf(1.5),
+            kurtosis: m4 / m2.powi(2) - 3.0,
+        }
+    }
+
+    fn moments(&self) -> (usize, f64, f64) {
+        if self.is_empty() {
+            return (0, f64::NAN, f64::NAN);
+        }
+        let (mut mean, mut m2) = (0.0, 0.0);
+        for (i, x) in self.iter().enumerate() {
+            let delta = x - mean;
+            mean += delta / (i + 1) as f64;
+            m2 += delta * (x - mean);
+        }
+        (self.len(), mean, m2)
+    }
+
+    #[cfg(feature = "rand")]
+    fn bootstrap_mean_ci(&self, resamples: usize, alpha: f64, seed: u64) -> (f64, f64) {
+        if self.is_empty()
+            || resamples == 0
+            || !(alpha > 0.0 && alpha < 1.0)
+            || self.iter().any(|x| x.is_nan())
+        {
+            return (f64::NAN, f64::NAN);
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let n = self.len();
+        let means: Vec<f64> = (0..resamples)
+            .map(|_| (0..n).map(|_| self[rng.gen_range(0..n)]).sum::<f64>() / n as f64)
+            .collect();
+        (
+            means.quantile(alpha / 2.0),
+            means.quantile(1.0 - alpha / 2.0),
+        )
+    }
+
+    fn r_squared(&self, other: &Self) -> f64 {
+        let covariance = self.covariance(other);
+        covariance * covariance / (self.variance() * other.variance())
+    }
+
+    fn normal_probability_plot(&self) -> Vec<(f64, f64)> {
+        let mut sorted = self.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let n = sorted.len() as f64;
+        sorted
+            .into_iter()
+            .enumerate()
+            .map(|(i, x)| (x, normal_inverse_cdf((i as f64 + 1.0 - 0.375) / (n + 0.25))))
+            .collect()
+    }
+
+    fn anderson_darling(&self) -> (f64, f64) {
+        let mean = self.average();
+        let std_dev = self.std_dev();
+        if self.len() < 2 || std_dev.is_nan() || std_dev == 0.0 {
+            return (f64::NAN, f64::NAN);
+        }
+        let mut cdf: Vec<f64> = self
+            .iter()
+            .map(|x| normal_cdf((x - mean) / std_dev))
+            .collect();
+        cdf.sort_by(f64::total_cmp);
+
+        let n = cdf.len();
+        let sum: f64 = (0..n)
+            .map(|i| (2 * i + 1) as f64 * (cdf[i].ln() + (1.0 - cdf[n - 1 - i]).ln()))
+            .sum();
+        let a2 = -(n as f64) - sum / n as f64;
+        if n < 8 {
+            return (a2, f64::NAN);
+        }
+
+        let n = n as f64;
+        let a = a2 * (1.0 + 0.75 / n + 2.25 / (n * n));
+        let p = if a >= 0.6 {
+            (1.2937 - 5.709 * a + 0.0186 * a * a).exp()
+        } else if a >= 0.34 {
+            (0.9177 - 4.279 * a - 1.38 * a * a).exp()
+        } else if a >= 0.2 {
+            1.0 - (-8.318 + 42.796 * a - 59.938 * a * a).exp()
+        } else {
+            1.0 - (-13.436 + 101.14 * a - 223.73 * a * a).exp()
+        };
+        (a2, p)
+    }
+
+    fn grubbs_test(&self, alpha: f64) -> Option<usize> {
+        let n = self.len();
+        let mean = self.average();
+        let std_dev = self.std_dev();
+        if n < 3 || std_dev.is_nan() || std_dev == 0.0 {
+            return None;
+        }
+        let (index, deviation) = self
+            .iter()
+            .map(|x| (x - mean).abs())
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        let n = n as f64;
+        let t = student_t_inverse_cdf(1.0 - alpha / (2.0 * n), n - 2.0);
+        let critical = (n - 1.0) / n.sqrt() * (t * t / (n - 2.0 + t * t)).sqrt();
+        if deviation / std_dev > critical {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    fn sum(&self) -> f64 {
+        self.iter().sum()
+    }
+
+    fn product(&self) -> f64 {
+        self.iter().product()
+    }
+
+    fn mode(&self, tolerance: f64) -> Vec<f64> {
+        if self.iter().any(|x| x.is_nan()) {
+            return vec![];
+        }
+        let mut sorted = self.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let mut buckets: Vec<&[f64]> = vec![];
+        let mut start = 0;
+        for i in 1..=sorted.len() {
+            if i == sorted.len() || sorted[i] - sorted[start] > tolerance {
+                buckets.push(&sorted[start..i]);
+                start = i;
+            }
+        }
+        let largest = buckets.iter().map(|b| b.len()).max().unwrap_or(0);
+        if largest < 2 {
+            return vec![];
+        }
+        buckets
+            .into_iter()
+            .filter(|b| b.len() == largest)
+            .map(|b| b.average())
+            .collect()
+    }
+}
+
+/// Computes the symmetric sample covariance matrix of equal-length `series`,
+/// entry `[i][j]` being `series[i].covariance(series[j])`
+///
+/// # Panics
+///
+/// If the series do not all contain the same number of elements
+///
+/// # Examples
+///
+/// ```
+/// use spc_rs::statistics::covariance_matrix;
+///
+/// let x = [1.0, 2.0, 3.0];
+/// let y = [3.0, 2.0, 1.0];
+/// let m = covariance_matrix(&[&x, &y]);
+/// assert_eq!(m, vec![vec![1.0, -1.0], vec![-1.0, 1.0]]);
+/// ```
+pub fn covariance_matrix(series: &[&[f64]]) -> Vec<Vec<f64>> {
+    if let Some(first) = series.first() {
+        assert!(
+            series.iter().all(|s| s.len() == first.len()),
+            "covariance_matrix: series must have the same length"
+        );
+    }
+    let mut matrix = vec![vec![0.0; series.len()]; series.len()];
+    for i in 0..series.len() {
+        for j in i..series.len() {
+            let covariance = series[i].covariance(series[j]);
+            matrix[i][j] = covariance;
+            matrix[j][i] = covariance;
+        }
+    }
+    matrix
+}
+
+/// Combines the Welford states `(count, mean, M2)` of two data sets into the
+/// state of their union, see `Statistics::moments`
+///
+/// # Remarks
+///
+/// Uses the pairwise update of Chan, Golub and LeVeque (1979), so per-shard
+/// summaries merge without the raw data in any order. A state with a zero
+/// count is the identity
+///
+/// # Examples
+///
+/// ```
+/// use spc_rs::statistics::{Statistics, combine_moments};
+///
+/// let x = [1.0, 2.0, 3.0, 6.0];
+/// assert_eq!(combine_moments(x[..2].moments(), x[2..].moments()), x.moments());
+/// ```
+pub fn combine_moments(a: (usize, f64, f64), b: (usize, f64, f64)) -> (usize, f64, f64) {
+    let ((n_a, mean_a, m2_a), (n_b, mean_b, m2_b)) = (a, b);
+    if n_a == 0 {
+        return b;
+    }
+    if n_b == 0 {
+        return a;
+    }
+    let n = n_a + n_b;
+    let delta = mean_b - mean_a;
+    let mean = mean_a + delta * n_b as f64 / n as f64;
+    let m2 = m2_a + m2_b + delta * delta * (n_a as f64 * n_b as f64) / n as f64;
+    (n, mean, m2)
+}
+
+/// The entries of `data` that are not `f64::NAN`, in order
+fn without_nan(data: &[f64]) -> Vec<f64> {
+    data.iter().copied().filter(|x| !x.is_nan()).collect()
+}
+
+/// The range and the first indices of the minimum and maximum, `None` if data
+/// is empty or any entry is `f64::NAN`
+fn extreme_indices(data: &[f64]) -> Option<(f64, usize, usize)> {
+    if data.is_empty() || data.iter().any(|x| x.is_nan()) {
+        return None;
+    }
+    let (mut arg_min, mut arg_max) = (0, 0);
+    for (i, &x) in data.iter().enumerate() {
+        if x < data[arg_min] {
+            arg_min = i;
+        }
+        if x > data[arg_max] {
+            arg_max = i;
+        }
+    }
+    Some((data[arg_max] - data[arg_min], arg_min, arg_max))
+}
+
+/// The R-7 `tau`-th quantile of non-empty ascending data
+fn sorted_quantile(sorted: &[f64], tau: f64) -> f64 {
+    let position = tau * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (position - lower as f64) * (sorted[upper] - sorted[lower])
+}
+
+/// Parallel counterparts of the `Statistics` reductions for large data sets,
+/// available with the `rayon` feature
+///
+/// # Remarks
+///
+/// The parallel sums are reduced in a different order than the serial ones,
+/// so results agree with `Statistics` up to floating point rounding (a
+/// relative difference of about `1e-12` for well-conditioned data) rather
+/// than bit for bit. Edge cases (empty data, `f64::NAN` entries) behave like
+/// the serial methods.
+#[cfg(feature = "rayon")]
+pub trait ParallelStatistics {
+    /// Parallel `Statistics::average`
+    fn par_average(&self) -> f64;
+
+    /// Parallel `Statistics::variance`
+    fn par_variance(&self) -> f64;
+
+    /// Parallel `Statistics::std_dev`
+    fn par_std_dev(&self) -> f64;
+}
+
+#[cfg(feature = "rayon")]
+impl ParallelStatistics for [f64] {
+    fn par_average(&self) -> f64 {
+        use rayon::prelude::*;
+
+        if self.is_empty() {
+            return f64::NAN;
+        }
+        self.par_iter().sum::<f64>() / self.len() as f64
+    }
+
+    fn par_variance(&self) -> f64 {
+        use rayon::prelude::*;
+
+        if self.len() < 2 {
+            return 0.0;
+        }
+        let average = self.par_average();
+        let v: f64 = self.par_iter().map(|s| (s - average) * (s - average)).sum();
+        v / (self.len() - 1) as f64
+    }
+
+    fn par_std_dev(&self) -> f64 {
+        self.par_variance().sqrt()
+    }
 }
 
 #[cfg(test)]
 mod op_test {
-    use crate::statistics::Statistics;
+    use crate::assert_almost_eq;
+    use crate::statistics::{BinRule, Statistics, combine_moments, covariance_matrix};
 
     #[test]
     fn test_op() {
-        let nums = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let nums = [1.0, 2.0, 3.0, 4.0, 5.0];
         println!("range =    {:?}", nums.range());
         println!("min =      {:?}", nums.min());
         println!("max =      {:?}", nums.max());
         println!("skewness = {:?}", nums.skewness());
         println!("kurtosis = {:?}", nums.kurtosis());
     }
+
+    #[test]
+    fn test_weighted_statistics() {
+        let nums = [9.8, 10.1, 10.4, 9.9, 10.3, 10.0];
+        let weights = [0.5; 6];
+        assert_almost_eq!(nums.weighted_average(&weights), nums.average(), 1e-12);
+        assert_almost_eq!(nums.weighted_variance(&weights), nums.variance(), 1e-12);
+        assert!(nums.weighted_average(&[0.0; 6]).is_nan());
+        assert!(nums.weighted_variance(&[0.0; 6]).is_nan());
+    }
+
+    #[test]
+    fn test_autocorrelation() {
+        // AR(1) x_t = 0.8 * x_{t-1} + e_t, with e_t from a fixed linear congruential sequence
+        let mut seed: u64 = 12345;
+        let mut x = vec![0.0];
+        for _ in 0..5000 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let e = (seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+            x.push(0.8 * x.last().unwrap() + e);
+        }
+        let acf = x.autocorrelations(3);
+        assert_eq!(4, acf.len());
+        assert_almost_eq!(acf[0], 1.0, 1e-12);
+        // the theoretical ACF of an AR(1) process is phi^k
+        assert_almost_eq!(acf[1], 0.8, 0.03);
+        assert_almost_eq!(acf[2], 0.64, 0.05);
+        assert_almost_eq!(acf[3], 0.512, 0.06);
+        assert!(x.autocorrelation(x.len()).is_nan());
+        assert!([2.0, 2.0, 2.0].autocorrelation(1).is_nan());
+    }
+
+    #[test]
+    fn test_diff_and_lag() {
+        let empty: [f64; 0] = [];
+        assert!(empty.diff(1).is_empty());
+        assert!(empty.lag(1).is_empty());
+        assert!([1.0].diff(1).is_empty());
+        assert_eq!(vec![1.0], [1.0].diff(0));
+        assert!([1.0, 2.0].diff(2).is_empty());
+        assert_eq!(vec![1.0, 2.0], [1.0, 2.0].lag(0));
+        assert_eq!(vec![-2.0, 3.0], [5.0, 3.0, 6.0].diff(1));
+    }
+
+    #[test]
+    fn test_covariance_matrix() {
+        let x = [1.0, 2.0, 3.0, 4.0];
+        let y = [2.0, 4.0, 6.0, 8.0];
+        let z = [4.0, 3.0, 2.5, 1.0];
+        let m = covariance_matrix(&[&x, &y, &z]);
+        // deviations x: -1.5 -0.5 0.5 1.5, z: 1.375 0.375 -0.125 -1.625
+        let expected = [
+            [5.0 / 3.0, 10.0 / 3.0, -4.75 / 3.0],
+            [10.0 / 3.0, 20.0 / 3.0, -9.5 / 3.0],
+            [-4.75 / 3.0, -9.5 / 3.0, 4.6875 / 3.0],
+        ];
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_almost_eq!(m[i][j], expected[i][j], 1e-12);
+                assert_eq!(m[i][j], m[j][i]);
+            }
+        }
+        assert!(covariance_matrix(&[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "same length")]
+    fn test_covariance_matrix_ragged() {
+        covariance_matrix(&[&[1.0, 2.0], &[1.0, 2.0, 3.0]]);
+    }
+
+    #[test]
+    fn test_quantile() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+        assert_eq!(x.quantile(0.5), x.median());
+        assert_eq!(x.quantile(1.0), 10.0);
+        assert_almost_eq!(x.quantile(0.1), 1.9, 1e-12);
+        assert_almost_eq!(x.quantile(0.00135), 1.01215, 1e-12);
+        assert!(x.quantile(1.5).is_nan());
+        assert!([1.0, f64::NAN].quantile(0.5).is_nan());
+        assert!(Vec::<f64>::new().quantile(0.5).is_nan());
+    }
+
+    #[test]
+    fn test_auto_histogram() {
+        let x: Vec<f64> = (0..27).map(|i| i as f64).collect();
+        // IQR = 13, width 2 * 13 / 3, so 26 / (26 / 3) = 3 bins
+        let histogram = x.auto_histogram(BinRule::FreedmanDiaconis);
+        assert_eq!(3, histogram.len());
+        assert_eq!((0.0, 26.0), (histogram[0].0, histogram[2].1));
+        assert_eq!(27, histogram.iter().map(|bin| bin.2).sum::<usize>());
+        assert_eq!(6, x.auto_histogram(BinRule::Sturges).len());
+        // s = sqrt(63), width 3.49 * sqrt(63) / 3 = 9.23...
+        assert_eq!(3, x.auto_histogram(BinRule::Scott).len());
+
+        let constant = [2.0; 5];
+        for rule in [BinRule::Sturges, BinRule::Scott, BinRule::FreedmanDiaconis] {
+            assert_eq!(vec![(2.0, 2.0, 5)], constant.auto_histogram(rule));
+        }
+        // IQR zero with a spread: a single bin rather than dividing by zero
+        let spiked = [1.0, 1.0, 1.0, 1.0, 1.0, 9.0];
+        assert_eq!(
+            vec![(1.0, 9.0, 6)],
+            spiked.auto_histogram(BinRule::FreedmanDiaconis)
+        );
+        assert!([1.0, f64::NAN].auto_histogram(BinRule::Sturges).is_empty());
+        assert!(Vec::<f64>::new().auto_histogram(BinRule::Scott).is_empty());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_bootstrap_mean_ci() {
+        let x: Vec<f64> = (1..=9).map(|i| i as f64).collect();
+        let (lower, upper) = x.bootstrap_mean_ci(5000, 0.05, 7);
+        // symmetric data: the interval straddles the mean, with the bootstrap standard
+        // error sqrt(60 / 9) / 3 = 0.861
+        assert!(lower < 5.0 && 5.0 < upper);
+        assert_almost_eq!((lower + upper) / 2.0, 5.0, 0.1);
+        assert_almost_eq!(upper - lower, 2.0 * 1.96 * 0.861, 0.3);
+        assert_eq!((lower, upper), x.bootstrap_mean_ci(5000, 0.05, 7));
+        assert_ne!((lower, upper), x.bootstrap_mean_ci(5000, 0.05, 8));
+
+        let (narrow_lower, narrow_upper) = x.bootstrap_mean_ci(5000, 0.5, 7);
+        assert!(lower < narrow_lower && narrow_upper < upper);
+        assert_eq!((3.0, 3.0), [3.0].bootstrap_mean_ci(10, 0.05, 1));
+        for (lower, upper) in [
+            [0.0; 0].bootstrap_mean_ci(10, 0.05, 1),
+            [1.0, f64::NAN].bootstrap_mean_ci(10, 0.05, 1),
+            x.bootstrap_mean_ci(0, 0.05, 1),
+            x.bootstrap_mean_ci(10, 1.0, 1),
+        ] {
+            assert!(lower.is_nan() && upper.is_nan());
+        }
+    }
+
+    #[test]
+    fn test_covariance_with() {
+        let reference = [9.8, 10.1, 10.4, 9.9, 10.3, 10.0];
+        let measured = [9.75f32, 10.2, 10.35, 9.95, 10.25, 10.05];
+        let widened: Vec<f64> = measured.iter().map(|&x| x as f64).collect();
+        assert_almost_eq!(
+            reference.covariance_with(&measured),
+            reference.covariance(&widened),
+            1e-12
+        );
+        assert!(reference[..1].covariance_with(&measured[..1]).is_nan());
+        assert!(std::panic::catch_unwind(|| reference.covariance_with(&measured[..5])).is_err());
+        assert!(std::panic::catch_unwind(|| reference[..5].covariance_with(&measured)).is_err());
+    }
+
+    #[test]
+    fn test_range_with_indices() {
+        let x = [10.1, 9.7, 10.4, 9.7, 10.4, 10.0];
+        assert_eq!(Some(1), x.arg_min());
+        assert_eq!(Some(2), x.arg_max());
+        let (range, arg_min, arg_max) = x.range_with_indices();
+        assert_eq!(x.range(), range);
+        assert_eq!((1, 2), (arg_min, arg_max));
+
+        let constant = [5.0; 4];
+        assert_eq!((0.0, 0, 0), constant.range_with_indices());
+        assert_eq!((0.0, 0, 0), [7.5].range_with_indices());
+        assert_eq!(Some(1), [1.0, -0.0, 0.0].arg_min());
+
+        for data in [&[][..], &[1.0, f64::NAN, 3.0][..]] {
+            assert_eq!(None, data.arg_min());
+            assert_eq!(None, data.arg_max());
+            let (range, arg_min, arg_max) = data.range_with_indices();
+            assert!(range.is_nan());
+            assert_eq!((0, 0), (arg_min, arg_max));
+        }
+    }
+
+    #[test]
+    fn test_clamp_values() {
+        let x = [9.2, 10.1, f64::NAN, 14.8, 10.5, f64::NEG_INFINITY];
+        let clamped = x.clamp_values(9.5, 10.5);
+        assert_eq!(x.len(), clamped.len());
+        assert_eq!(vec![9.5, 10.1], clamped[..2].to_vec());
+        assert!(clamped[2].is_nan());
+        assert_eq!(vec![10.5, 10.5, 9.5], clamped[3..].to_vec());
+        assert_eq!(vec![10.0; 3], [9.0, 10.0, 11.0].clamp_values(10.0, 10.0));
+        assert!([0.0; 0].clamp_values(0.0, 1.0).is_empty());
+
+        assert!(std::panic::catch_unwind(|| x.clamp_values(10.5, 9.5)).is_err());
+        assert!(std::panic::catch_unwind(|| x.clamp_values(f64::NAN, 9.5)).is_err());
+    }
+
+    #[test]
+    fn test_nan_skipping() {
+        let x = [10.2, f64::NAN, 9.8, 10.5, f64::NAN, 9.9, 10.1];
+        let filtered: Vec<f64> = x.iter().copied().filter(|v| !v.is_nan()).collect();
+        assert!(x.average().is_nan() && x.std_dev().is_nan() && x.range().is_nan());
+        assert_eq!(filtered.average(), x.nan_average());
+        assert_eq!(filtered.std_dev(), x.nan_std_dev());
+        assert_eq!(filtered.min(), x.nan_min());
+        assert_eq!(filtered.max(), x.nan_max());
+        assert_eq!(filtered.range(), x.nan_range());
+        assert_eq!(9.8, x.nan_min());
+        assert_eq!(10.5, x.nan_max());
+
+        // without gaps they agree with the plain statistics
+        assert_eq!(filtered.average(), filtered.nan_average());
+        assert_eq!(filtered.std_dev(), filtered.nan_std_dev());
+
+        let single = [f64::NAN, 4.0];
+        assert_eq!(4.0, single.nan_average());
+        assert_eq!(0.0, single.nan_range());
+        assert!(single.nan_std_dev().is_nan());
+
+        for data in [&[][..], &[f64::NAN; 3][..]] {
+            assert!(data.nan_average().is_nan());
+            assert!(data.nan_std_dev().is_nan());
+            assert!(data.nan_min().is_nan());
+            assert!(data.nan_max().is_nan());
+            assert!(data.nan_range().is_nan());
+        }
+    }
+
+    #[test]
+    fn test_count_within() {
+        let x = [9.7, 9.8, 10.0, 10.2, 10.3, f64::NAN];
+        assert_eq!(3, x.count_within(9.8, 10.2));
+        assert_eq!(0.6, x.proportion_within(9.8, 10.2));
+        assert_eq!(1, x.count_within(10.0, 10.0));
+        assert_eq!(5, x.count_within(f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(1.0, x.proportion_within(f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(0, x.count_within(10.2, 9.8));
+        assert_eq!(0, x.count_within(f64::NAN, 10.2));
+        assert_eq!(0.0, x.proportion_within(f64::NAN, 10.2));
+
+        assert_eq!(0, [].count_within(0.0, 1.0));
+        assert!([].proportion_within(0.0, 1.0).is_nan());
+        assert!([f64::NAN; 3].proportion_within(0.0, 1.0).is_nan());
+    }
+
+    #[test]
+    fn test_combine_moments() {
+        let x = [9.8, 10.1, 10.4, 9.9, 10.3, 10.0, 12.5, 7.2, 10.0];
+        let (n, mean, m2) = x.moments();
+        assert_eq!(9, n);
+        assert_almost_eq!(mean, x.average(), 1e-12);
+        assert_almost_eq!(m2 / (n - 1) as f64, x.variance(), 1e-12);
+
+        let (a, b, c) = (x[..2].moments(), x[2..6].moments(), x[6..].moments());
+        let left = combine_moments(combine_moments(a, b), c);
+        let right = combine_moments(a, combine_moments(b, c));
+        for (n, mean, m2) in [left, right, combine_moments(c, combine_moments(a, b))] {
+            assert_eq!(9, n);
+            assert_almost_eq!(mean, x.average(), 1e-12);
+            assert_almost_eq!(m2 / 8.0, x.variance(), 1e-12);
+        }
+
+        let empty = [0.0; 0].moments();
+        assert_eq!(0, empty.0);
+        assert!(empty.1.is_nan() && empty.2.is_nan());
+        assert_eq!(a, combine_moments(a, empty));
+        assert_eq!(a, combine_moments(empty, a));
+        assert_eq!((1, 2.0, 0.0), [2.0].moments());
+    }
+
+    #[test]
+    fn test_describe() {
+        let x = [2.0, 8.0, 1.0, 4.0, 9.0, 3.0, 3.0];
+        let summary = x.describe();
+        assert_eq!(7, summary.count);
+        assert_almost_eq!(summary.mean, x.average(), 1e-12);
+        assert_almost_eq!(summary.std_dev, x.std_dev(), 1e-12);
+        assert_eq!(x.min(), summary.min);
+        assert_eq!(x.quantile(0.25), summary.q1);
+        assert_eq!(x.median(), summary.median);
+        assert_eq!(x.quantile(0.75), summary.q3);
+        assert_eq!(x.max(), summary.max);
+        assert_almost_eq!(summary.skewness, x.skewness(), 1e-12);
+        assert_almost_eq!(summary.kurtosis, x.kurtosis(), 1e-12);
+
+        let single = [5.0].describe();
+        assert_eq!((1, 5.0, 0.0), (single.count, single.median, single.std_dev));
+
+        for summary in [[0.0; 0].describe(), [1.0, f64::NAN].describe()] {
+            assert!(summary.mean.is_nan() && summary.q1.is_nan() && summary.max.is_nan());
+            assert!(summary.std_dev.is_nan() && summary.kurtosis.is_nan());
+        }
+        assert_eq!(2, [1.0, f64::NAN].describe().count);
+    }
+
+    #[test]
+    fn test_cumulative_sum_and_ecdf() {
+        let empty: [f64; 0] = [];
+        assert!(empty.cumulative_sum().is_empty());
+        assert!(empty.ecdf(0.0).is_nan());
+
+        let nums = [0.4, -1.2, 2.5, 0.4, 1.1];
+        let total = nums.cumulative_sum();
+        assert_almost_eq!(*total.last().unwrap(), 3.2, 1e-12);
+        let mut previous = 0.0;
+        for x in [-2.0, -1.2, 0.0, 0.4, 1.0, 2.5, 3.0] {
+            let fraction = nums.ecdf(x);
+            assert!(fraction >= previous);
+            previous = fraction;
+        }
+        assert_eq!(0.0, nums.ecdf(-1.3));
+        assert_eq!(0.2, nums.ecdf(-1.2));
+        assert_eq!(0.6, nums.ecdf(0.4));
+        assert_eq!(1.0, nums.ecdf(2.5));
+        assert_eq!(60.0, nums.percentile_rank(0.4));
+        assert_eq!(100.0, nums.percentile_rank(3.0));
+        assert!(empty.percentile_rank(0.0).is_nan());
+    }
+
+    #[test]
+    fn test_normal_probability_plot() {
+        let nums = [10.2, 9.8, 10.0, 10.5, 9.6, 10.1, 9.9, 10.3, 9.7, 10.0];
+        let points = nums.normal_probability_plot();
+        assert_eq!(nums.len(), points.len());
+        assert!(
+            points
+                .windows(2)
+                .all(|w| w[0].0 <= w[1].0 && w[0].1 < w[1].1)
+        );
+        // symmetric plotting positions give symmetric quantiles
+        assert_almost_eq!(points[0].1, -points[9].1, 1e-12);
+        let (values, quantiles): (Vec<f64>, Vec<f64>) = points.into_iter().unzip();
+        assert!(values.r_squared(&quantiles) > 0.97);
+
+        let skewed = [1.0, 1.1, 1.0, 1.2, 1.0, 1.1, 5.0, 9.0, 1.0, 1.3];
+        let (values, quantiles): (Vec<f64>, Vec<f64>) =
+            skewed.normal_probability_plot().into_iter().unzip();
+        assert!(values.r_squared(&quantiles) < 0.75);
+    }
+
+    #[test]
+    fn test_anderson_darling() {
+        let nums = [10.2, 9.8, 10.0, 10.5, 9.6, 10.1, 9.9, 10.3, 9.7, 10.0];
+        let (a2, p) = nums.anderson_darling();
+        assert_almost_eq!(a2, 0.11715785230407327, 1e-10);
+        assert_almost_eq!(p, 0.9839215179335223, 1e-10);
+
+        let skewed = [1.0, 1.1, 1.0, 1.2, 1.0, 1.1, 5.0, 9.0, 1.0, 1.3];
+        let (a2, p) = skewed.anderson_darling();
+        assert_almost_eq!(a2, 2.1106191436358905, 1e-10);
+        assert_almost_eq!(p, 7.27657136460246e-06, 1e-12);
+
+        let (a2, p) = nums[..5].anderson_darling();
+        assert!(a2.is_finite() && p.is_nan());
+        let (a2, p) = [1.0, 1.0, 1.0].anderson_darling();
+        assert!(a2.is_nan() && p.is_nan());
+    }
+
+    #[test]
+    fn test_grubbs_test() {
+        let clean = [10.2, 9.8, 10.0, 10.5, 9.6, 10.1, 9.9, 10.3, 9.7, 10.0];
+        assert_eq!(None, clean.grubbs_test(0.05));
+
+        // G = 2.38 lies between the tabulated critical values 2.290 (alpha = 0.05) and 2.482
+        // (alpha = 0.01) for N = 10
+        let mut outlier = clean;
+        outlier[3] = 11.0;
+        assert_eq!(Some(3), outlier.grubbs_test(0.05));
+        assert_eq!(None, outlier.grubbs_test(0.01));
+        assert_eq!(Some(3), outlier.map(|x| -x).grubbs_test(0.05));
+
+        assert_eq!(None, [1.0, 5.0].grubbs_test(0.05));
+        assert_eq!(None, [2.0; 5].grubbs_test(0.05));
+    }
+
+    #[test]
+    fn test_mode() {
+        let bimodal = [1.0, 5.0, 5.0, 5.0, 2.0, 9.0, 9.0, 9.0, 7.0];
+        assert_eq!(vec![5.0, 9.0], bimodal.mode(0.0));
+
+        let near_duplicates = [10.0, 10.0000001, 9.9999999, 10.3, 10.6, 10.30000001];
+        let mode = near_duplicates.mode(1e-6);
+        assert_eq!(1, mode.len());
+        assert_almost_eq!(mode[0], 10.0, 1e-9);
+        assert!(near_duplicates.mode(0.0).is_empty());
+
+        let empty: [f64; 0] = [];
+        assert!(empty.mode(0.1).is_empty());
+        assert!([1.0, 1.0, f64::NAN].mode(0.1).is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_statistics() {
+        use crate::statistics::ParallelStatistics;
+
+        let mut seed: u64 = 42;
+        let nums: Vec<f64> = (0..1_000_000)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                10.0 + (seed >> 11) as f64 / (1u64 << 53) as f64
+            })
+            .collect();
+        assert_almost_eq!(nums.par_average(), nums.average(), 1e-9);
+        assert_almost_eq!(nums.par_variance(), nums.variance(), 1e-9);
+        assert_almost_eq!(nums.par_std_dev(), nums.std_dev(), 1e-9);
+
+        let empty: [f64; 0] = [];
+        assert!(empty.par_average().is_nan());
+        assert_eq!(0.0, [1.0].par_variance());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_weighted_average_length_mismatch() {
+        [1.0, 2.0].weighted_average(&[1.0]);
+    }
 }